@@ -0,0 +1,189 @@
+use super::{check_step, InvalidStep, Simulation};
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::math::F32Ext;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// A simulation sampled ahead of time at a fixed resolution, so many identical animations can
+/// share one curve instead of each evaluating the simulation every frame. Between samples the
+/// value is linearly interpolated, and past the end it stays on the last sample.
+///
+/// Springs scale linearly with their start distance: bake one from 1 to 0 and map it with
+/// `end + (start - end) * curve.x(t)` to reuse it for any start and end.
+pub struct BakedCurve {
+    samples: Vec<f32>,
+    step: f32,
+}
+
+impl BakedCurve {
+    /// Samples `simulation` from 0 to `duration` with `resolution` samples, both ends included.
+    /// The duration has to be positive.
+    pub fn bake(
+        simulation: &impl Simulation,
+        duration: f32,
+        resolution: usize,
+    ) -> Result<Self, InvalidStep> {
+        let resolution = resolution.max(2);
+        let step = check_step(duration / (resolution - 1) as f32)?;
+        Ok(Self {
+            samples: (0..resolution)
+                .map(|i| simulation.x(step * i as f32))
+                .collect(),
+            step,
+        })
+    }
+
+    /// Samples `simulation` every `step` until it's done, or until `max_duration`.
+    pub fn bake_until_done(
+        simulation: &impl Simulation,
+        step: f32,
+        max_duration: f32,
+    ) -> Result<Self, InvalidStep> {
+        let step = check_step(step)?;
+        let mut samples = Vec::new();
+        loop {
+            // Times are counted in steps rather than summed, so they don't drift.
+            let t = step * samples.len() as f32;
+            samples.push(simulation.x(t));
+            if simulation.is_done(t) || t >= max_duration {
+                break;
+            }
+        }
+        if samples.len() < 2 {
+            samples.push(samples[0]);
+        }
+        Ok(Self { samples, step })
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.step * (self.samples.len() - 1) as f32
+    }
+}
+
+impl Simulation for BakedCurve {
+    fn x(&self, time: f32) -> f32 {
+        let last = self.samples.len() - 1;
+        let position = (time / self.step).max(0.);
+        let i = position.floor() as usize;
+        if i >= last {
+            return self.samples[last];
+        }
+        let fraction = position - i as f32;
+        self.samples[i] + (self.samples[i + 1] - self.samples[i]) * fraction
+    }
+
+    fn dx(&self, time: f32) -> f32 {
+        let last = self.samples.len() - 1;
+        let i = ((time / self.step).max(0.).floor() as usize).min(last - 1);
+        if time >= self.duration() {
+            0.
+        } else {
+            (self.samples[i + 1] - self.samples[i]) / self.step
+        }
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        time >= self.duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        spring::{SpringDescription, SpringSimulation},
+        DEFAULT_TOLERANCE,
+    };
+
+    fn spring() -> SpringSimulation {
+        SpringSimulation::new(
+            SpringDescription::from_damping_ratio(1., 200., 0.3),
+            1.,
+            0.,
+            0.,
+            DEFAULT_TOLERANCE,
+        )
+    }
+
+    #[test]
+    fn samples_match_the_simulation() {
+        let simulation = spring();
+        let curve = BakedCurve::bake(&simulation, 2., 201).unwrap();
+        assert_eq!(curve.duration(), 2.);
+        for i in 0..201 {
+            let t = 0.01 * i as f32;
+            assert!((curve.x(t) - simulation.x(t)).abs() < 0.0001, "at {}", t);
+        }
+    }
+
+    #[test]
+    fn interpolates_between_samples() {
+        let simulation = spring();
+        let curve = BakedCurve::bake(&simulation, 2., 201).unwrap();
+        for i in 0..200 {
+            let (before, after) = (0.01 * i as f32, 0.01 * (i + 1) as f32);
+            let t = before + 0.0025;
+            let expected = curve.x(before) + (curve.x(after) - curve.x(before)) * 0.25;
+            assert!((curve.x(t) - expected).abs() < 0.0001, "at {}", t);
+            // Fine enough to stand in for the simulation.
+            assert!((curve.x(t) - simulation.x(t)).abs() < 0.01, "at {}", t);
+            assert!(
+                (curve.dx(t) - (curve.x(after) - curve.x(before)) / 0.01).abs() < 0.01,
+                "at {}",
+                t
+            );
+        }
+    }
+
+    #[test]
+    fn clamps_to_the_ends() {
+        let simulation = spring();
+        let curve = BakedCurve::bake(&simulation, 2., 201).unwrap();
+        assert_eq!(curve.x(-1.), simulation.x(0.));
+        assert_eq!(curve.x(5.), curve.x(2.));
+        assert_eq!(curve.dx(5.), 0.);
+        assert!(!curve.is_done(1.9));
+        assert!(curve.is_done(2.));
+    }
+
+    #[test]
+    fn bakes_until_the_simulation_is_done() {
+        let simulation = spring();
+        let curve = BakedCurve::bake_until_done(&simulation, 0.01, 10.).unwrap();
+        let end = curve.duration();
+        assert!(simulation.is_done(end));
+        assert!(!simulation.is_done(end - 0.01));
+        for i in 0..(end / 0.01) as usize {
+            let t = 0.01 * i as f32;
+            assert!((curve.x(t) - simulation.x(t)).abs() < 0.0001, "at {}", t);
+        }
+
+        let cut_short = BakedCurve::bake_until_done(&simulation, 0.01, 0.5).unwrap();
+        assert!((cut_short.duration() - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn rejects_a_step_that_goes_nowhere() {
+        let simulation = spring();
+        assert_eq!(
+            BakedCurve::bake_until_done(&simulation, 0., 1.).err(),
+            Some(InvalidStep)
+        );
+        assert_eq!(
+            BakedCurve::bake_until_done(&simulation, -0.01, 1.).err(),
+            Some(InvalidStep)
+        );
+        assert_eq!(
+            BakedCurve::bake_until_done(&simulation, f32::NAN, 1.).err(),
+            Some(InvalidStep)
+        );
+        assert_eq!(
+            BakedCurve::bake(&simulation, 0., 10).err(),
+            Some(InvalidStep)
+        );
+        assert_eq!(
+            BakedCurve::bake(&simulation, -1., 10).err(),
+            Some(InvalidStep)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use super::Simulation;
+
+/// Runs `first` until `switch_time`, then `second` with its own time starting from 0, for
+/// motions made of two parts like a fling that snaps back with a spring. `second` usually
+/// starts where `first` is at the switch: use the first simulation's `x` and `dx` to build it.
+pub struct ChainedSimulation<A: Simulation, B: Simulation> {
+    first: A,
+    second: B,
+    switch_time: f32,
+}
+
+impl<A: Simulation, B: Simulation> ChainedSimulation<A, B> {
+    pub fn new(first: A, second: B, switch_time: f32) -> Self {
+        Self {
+            first,
+            second,
+            switch_time,
+        }
+    }
+
+    pub fn switch_time(&self) -> f32 {
+        self.switch_time
+    }
+}
+
+impl<A: Simulation, B: Simulation> Simulation for ChainedSimulation<A, B> {
+    fn x(&self, time: f32) -> f32 {
+        if time < self.switch_time {
+            self.first.x(time)
+        } else {
+            self.second.x(time - self.switch_time)
+        }
+    }
+
+    fn dx(&self, time: f32) -> f32 {
+        if time < self.switch_time {
+            self.first.dx(time)
+        } else {
+            self.second.dx(time - self.switch_time)
+        }
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        time >= self.switch_time && self.second.is_done(time - self.switch_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        friction::FrictionSimulation,
+        spring::{SpringDescription, SpringSimulation},
+        DEFAULT_TOLERANCE,
+    };
+
+    // A fling to the right for half a second, then a spring back to 0 from wherever it got.
+    fn fling_then_snap_back() -> ChainedSimulation<FrictionSimulation, SpringSimulation> {
+        let fling = FrictionSimulation::new(0.1.into(), 0., 300., 1.);
+        let switch_time = 0.5;
+        let snap_back = SpringSimulation::new(
+            SpringDescription::from_damping_ratio(1., 200., 1.),
+            fling.x(switch_time),
+            0.,
+            fling.dx(switch_time),
+            DEFAULT_TOLERANCE,
+        );
+        ChainedSimulation::new(fling, snap_back, switch_time)
+    }
+
+    #[test]
+    fn follows_each_simulation_in_turn() {
+        let chained = fling_then_snap_back();
+        for i in 0..300 {
+            let t = i as f32 * 0.01;
+            if t < chained.switch_time() {
+                assert_eq!(chained.x(t), chained.first.x(t));
+                assert_eq!(chained.dx(t), chained.first.dx(t));
+            } else {
+                assert_eq!(chained.x(t), chained.second.x(t - 0.5));
+                assert_eq!(chained.dx(t), chained.second.dx(t - 0.5));
+            }
+        }
+    }
+
+    #[test]
+    fn is_continuous_at_the_switch() {
+        let chained = fling_then_snap_back();
+        let before = chained.switch_time() - 0.0001;
+        assert!((chained.x(before) - chained.x(0.5)).abs() < 0.1);
+        assert!((chained.dx(before) - chained.dx(0.5)).abs() < 0.1);
+    }
+
+    #[test]
+    fn is_done_once_the_second_simulation_is() {
+        let first = FrictionSimulation::new(0.1.into(), 0., 300., 1000.);
+        let second = SpringSimulation::new(
+            SpringDescription::from_damping_ratio(1., 200., 1.),
+            1.,
+            0.,
+            0.,
+            DEFAULT_TOLERANCE,
+        );
+        // The first one being done doesn't end the chain early.
+        assert!(first.is_done(0.));
+        let chained = ChainedSimulation::new(first, second, 0.5);
+        assert!(!chained.is_done(0.));
+        assert!(!chained.is_done(0.6));
+        assert!(chained.is_done(10.));
+    }
+}
@@ -0,0 +1,166 @@
+use super::Simulation;
+
+/// Keeps another simulation's position between `min` and `max`. While it's held on a bound the
+/// velocity is 0, and it's done when the inner simulation is.
+pub struct ClampedSimulation<S: Simulation> {
+    simulation: S,
+    min: f32,
+    max: f32,
+}
+
+impl<S: Simulation> ClampedSimulation<S> {
+    pub fn new(simulation: S, min: f32, max: f32) -> Self {
+        Self {
+            simulation,
+            min,
+            max,
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.simulation
+    }
+}
+
+impl<S: Simulation> Simulation for ClampedSimulation<S> {
+    fn x(&self, time: f32) -> f32 {
+        self.simulation.x(time).clamp(self.min, self.max)
+    }
+
+    fn dx(&self, time: f32) -> f32 {
+        let x = self.simulation.x(time);
+        if x < self.min || x > self.max {
+            0.
+        } else {
+            self.simulation.dx(time)
+        }
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        self.simulation.is_done(time)
+    }
+}
+
+/// Another simulation that is done while its position is out of `min..=max`, or when it's done
+/// itself. The position is clamped, so it stops on the bound it went through.
+///
+/// Animations stop the first time it's done, so a motion that would come back in range stays
+/// on the bound.
+pub struct BoundedSimulation<S: Simulation> {
+    simulation: S,
+    min: f32,
+    max: f32,
+}
+
+impl<S: Simulation> BoundedSimulation<S> {
+    pub fn new(simulation: S, min: f32, max: f32) -> Self {
+        Self {
+            simulation,
+            min,
+            max,
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.simulation
+    }
+
+    fn is_out(&self, time: f32) -> bool {
+        let x = self.simulation.x(time);
+        x < self.min || x > self.max
+    }
+}
+
+impl<S: Simulation> Simulation for BoundedSimulation<S> {
+    fn x(&self, time: f32) -> f32 {
+        self.simulation.x(time).clamp(self.min, self.max)
+    }
+
+    fn dx(&self, time: f32) -> f32 {
+        if self.is_out(time) {
+            0.
+        } else {
+            self.simulation.dx(time)
+        }
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        self.is_out(time) || self.simulation.is_done(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        friction::FrictionSimulation,
+        spring::{SpringDescription, SpringSimulation},
+        DEFAULT_TOLERANCE,
+    };
+
+    // From 0 to 1, going past 1 on the way.
+    fn bouncy() -> SpringSimulation {
+        SpringSimulation::new(
+            SpringDescription::from_damping_ratio(1., 200., 0.3),
+            0.,
+            1.,
+            0.,
+            DEFAULT_TOLERANCE,
+        )
+    }
+
+    fn times() -> impl Iterator<Item = f32> {
+        (0..500).map(|i| i as f32 * 0.01)
+    }
+
+    #[test]
+    fn clamped_holds_on_the_bounds() {
+        let clamped = ClampedSimulation::new(bouncy(), 0., 1.);
+        let inner = clamped.inner();
+        assert!(times().any(|t| inner.x(t) > 1.));
+        for t in times() {
+            if inner.x(t) > 1. {
+                assert_eq!(clamped.x(t), 1.);
+                assert_eq!(clamped.dx(t), 0.);
+            } else {
+                assert_eq!(clamped.x(t), inner.x(t));
+                assert_eq!(clamped.dx(t), inner.dx(t));
+            }
+            assert_eq!(clamped.is_done(t), inner.is_done(t));
+        }
+    }
+
+    #[test]
+    fn bounded_is_done_when_it_leaves_the_range() {
+        let bounded = BoundedSimulation::new(bouncy(), -1., 1.);
+        let inner = bounded.inner();
+        let out = times().find(|t| inner.x(*t) > 1.).unwrap();
+        assert!(times()
+            .take_while(|t| *t < out)
+            .all(|t| !bounded.is_done(t)));
+        assert!(bounded.is_done(out));
+        assert_eq!(bounded.x(out), 1.);
+        assert_eq!(bounded.dx(out), 0.);
+        assert!(!inner.is_done(out));
+    }
+
+    #[test]
+    fn bounded_in_range_is_done_with_the_inner_simulation() {
+        let fling = FrictionSimulation::new(0.1.into(), 0., 50., 1.);
+        let bounded = BoundedSimulation::new(fling, -100., 100.);
+        for t in times() {
+            assert_eq!(bounded.x(t), bounded.inner().x(t));
+            assert_eq!(bounded.is_done(t), bounded.inner().is_done(t));
+        }
+    }
+
+    #[test]
+    fn bounded_stops_a_fling_on_the_bound() {
+        let fling = FrictionSimulation::new(0.1.into(), 0., 500., 1.);
+        let bounded = BoundedSimulation::new(fling, -100., 100.);
+        let out = bounded.inner().time_at_x(100.);
+        assert!(!bounded.is_done(out - 0.01));
+        assert!(bounded.is_done(out + 0.01));
+        assert_eq!(bounded.x(out + 0.01), 100.);
+    }
+}
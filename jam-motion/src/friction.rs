@@ -0,0 +1,301 @@
+use super::Simulation;
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::math::F32Ext;
+use core::f32::consts::E;
+#[cfg(feature = "glam")]
+use {super::Simulation2D, glam::Vec2};
+
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrictionDescription {
+    pub drag: f32,
+}
+
+impl From<f32> for FrictionDescription {
+    fn from(value: f32) -> Self {
+        Self { drag: value }
+    }
+}
+
+/// Why no friction simulation can go through the requested positions and velocities.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FrictionThroughError {
+    /// The start and end velocities point in opposite directions, friction can't turn around.
+    VelocityReversed,
+    /// The end speed isn't lower than the start speed, friction only slows down.
+    NotSlowingDown,
+    /// The end position isn't in the direction the start velocity is heading to.
+    EndNotAhead,
+}
+
+pub struct FrictionSimulation {
+    friction: FrictionDescription,
+    drag_log: f32,
+    x: f32,
+    v: f32,
+    tolerance: f32,
+}
+
+impl FrictionSimulation {
+    pub fn new(
+        friction: FrictionDescription,
+        position: f32,
+        velocity: f32,
+        tolerance: f32,
+    ) -> Self {
+        Self {
+            friction,
+            drag_log: friction.drag.ln(),
+            x: position,
+            v: velocity,
+            tolerance,
+        }
+    }
+
+    /// A simulation that goes through `start_position` at `start_velocity` and through
+    /// `end_position` at `end_velocity`, with the drag that makes it so.
+    pub fn try_through(
+        start_position: f32,
+        end_position: f32,
+        start_velocity: f32,
+        end_velocity: f32,
+    ) -> Result<Self, FrictionThroughError> {
+        if start_velocity != 0.
+            && end_velocity != 0.
+            && start_velocity.signum() != end_velocity.signum()
+        {
+            return Err(FrictionThroughError::VelocityReversed);
+        }
+        if start_velocity.abs() <= end_velocity.abs() {
+            return Err(FrictionThroughError::NotSlowingDown);
+        }
+        if end_position == start_position
+            || (end_position - start_position).signum() != start_velocity.signum()
+        {
+            return Err(FrictionThroughError::EndNotAhead);
+        }
+
+        let friction =
+            Self::drag_for(start_position, end_position, start_velocity, end_velocity).into();
+        Ok(Self {
+            friction,
+            drag_log: friction.drag.ln(),
+            x: start_position,
+            v: start_velocity,
+            tolerance: end_velocity.abs(),
+        })
+    }
+
+    fn drag_for(
+        start_position: f32,
+        end_position: f32,
+        start_velocity: f32,
+        end_velocity: f32,
+    ) -> f32 {
+        E.powf((start_velocity - end_velocity) / (start_position - end_position))
+    }
+
+    pub fn final_x(&self) -> f32 {
+        self.x - self.v / self.drag_log
+    }
+
+    pub fn time_at_x(&self, x: f32) -> f32 {
+        if x == self.x {
+            0.
+        } else if self.v == 0. || {
+            if self.v > 0. {
+                x < self.x || x > self.final_x()
+            } else {
+                x > self.x || x < self.final_x()
+            }
+        } {
+            f32::INFINITY
+        } else {
+            (self.drag_log * (x - self.x) / self.v + 1.).ln() / self.drag_log
+        }
+    }
+}
+
+impl Simulation for FrictionSimulation {
+    fn x(&self, time: f32) -> f32 {
+        self.x + self.v * self.friction.drag.powf(time) / self.drag_log - self.v / self.drag_log
+    }
+
+    fn dx(&self, time: f32) -> f32 {
+        self.v * self.friction.drag.powf(time)
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        self.dx(time).abs() < self.tolerance
+    }
+}
+
+/// Friction applied to a 2D motion: both axes share the drag, so the position moves along a
+/// straight line and stops at `final_position`.
+#[cfg(feature = "glam")]
+pub struct FrictionSimulation2D {
+    friction: FrictionDescription,
+    drag_log: f32,
+    position: Vec2,
+    velocity: Vec2,
+    tolerance: f32,
+}
+
+#[cfg(feature = "glam")]
+impl FrictionSimulation2D {
+    pub fn new(
+        friction: FrictionDescription,
+        position: Vec2,
+        velocity: Vec2,
+        tolerance: f32,
+    ) -> Self {
+        Self {
+            friction,
+            drag_log: friction.drag.ln(),
+            position,
+            velocity,
+            tolerance,
+        }
+    }
+
+    pub fn final_position(&self) -> Vec2 {
+        self.position - self.velocity / self.drag_log
+    }
+}
+
+#[cfg(feature = "glam")]
+impl Simulation2D for FrictionSimulation2D {
+    fn x(&self, time: f32) -> Vec2 {
+        self.position + self.velocity * (self.friction.drag.powf(time) - 1.) / self.drag_log
+    }
+
+    fn dx(&self, time: f32) -> Vec2 {
+        self.velocity * self.friction.drag.powf(time)
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        self.dx(time).length() < self.tolerance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.001 * b.abs().max(1.)
+    }
+
+    #[test]
+    fn goes_through_both_ends() {
+        for (start, end, start_velocity, end_velocity) in [
+            (0., 100., 500., 10.),
+            (100., 0., -500., -10.),
+            (-20., 30., 200., 1.),
+        ] {
+            let simulation =
+                FrictionSimulation::try_through(start, end, start_velocity, end_velocity).unwrap();
+            assert!(near(simulation.x(0.), start));
+            assert_eq!(simulation.dx(0.), start_velocity);
+
+            let t = simulation.time_at_x(end);
+            assert!(t.is_finite() && t > 0.);
+            assert!(
+                near(simulation.x(t), end),
+                "{} but {}",
+                simulation.x(t),
+                end
+            );
+            assert!(near(simulation.dx(t), end_velocity));
+            // Done from the moment it's as slow as the end velocity.
+            assert!(!simulation.is_done(t - 0.01));
+            assert!(simulation.is_done(t + 0.01));
+        }
+    }
+
+    #[test]
+    fn rejects_ends_friction_cannot_reach() {
+        assert_eq!(
+            FrictionSimulation::try_through(0., 100., 500., -10.).err(),
+            Some(FrictionThroughError::VelocityReversed)
+        );
+        assert_eq!(
+            FrictionSimulation::try_through(0., 100., 10., 10.).err(),
+            Some(FrictionThroughError::NotSlowingDown)
+        );
+        assert_eq!(
+            FrictionSimulation::try_through(0., 100., 10., 500.).err(),
+            Some(FrictionThroughError::NotSlowingDown)
+        );
+        assert_eq!(
+            FrictionSimulation::try_through(0., -100., 500., 10.).err(),
+            Some(FrictionThroughError::EndNotAhead)
+        );
+        assert_eq!(
+            FrictionSimulation::try_through(0., 0., 500., 10.).err(),
+            Some(FrictionThroughError::EndNotAhead)
+        );
+    }
+
+    #[test]
+    fn slows_down_towards_the_final_position() {
+        let simulation = FrictionSimulation::new(0.1.into(), 10., 50., 0.01);
+        let final_x = simulation.final_x();
+        assert!(final_x > 10.);
+        let mut last_x = simulation.x(0.);
+        for i in 1..1000 {
+            let x = simulation.x(i as f32 * 0.01);
+            assert!(x >= last_x && x <= final_x);
+            last_x = x;
+        }
+        assert!(near(simulation.x(20.), final_x));
+    }
+
+    #[test]
+    fn never_reaches_positions_out_of_its_path() {
+        let simulation = FrictionSimulation::new(0.1.into(), 10., 50., 0.01);
+        assert_eq!(simulation.time_at_x(10.), 0.);
+        assert_eq!(simulation.time_at_x(5.), f32::INFINITY);
+        assert_eq!(
+            simulation.time_at_x(simulation.final_x() + 1.),
+            f32::INFINITY
+        );
+        let halfway = (10. + simulation.final_x()) / 2.;
+        assert!(near(simulation.x(simulation.time_at_x(halfway)), halfway));
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn a_2d_fling_moves_like_one_simulation_per_axis() {
+        let friction = FrictionDescription::from(0.1);
+        let (position, velocity) = (Vec2::new(10., -5.), Vec2::new(300., 400.));
+        let fling = FrictionSimulation2D::new(friction, position, velocity, 1.);
+        let x = FrictionSimulation::new(friction, position.x, velocity.x, 1.);
+        let y = FrictionSimulation::new(friction, position.y, velocity.y, 1.);
+        for i in 0..300 {
+            let t = i as f32 * 0.01;
+            assert!(near(fling.x(t).x, x.x(t)) && near(fling.x(t).y, y.x(t)));
+            assert!(near(fling.dx(t).x, x.dx(t)) && near(fling.dx(t).y, y.dx(t)));
+        }
+        assert!(near(fling.final_position().x, x.final_x()));
+        assert!(near(fling.final_position().y, y.final_x()));
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn a_2d_fling_stops_on_a_straight_line() {
+        let (position, velocity) = (Vec2::new(10., -5.), Vec2::new(300., 400.));
+        let fling = FrictionSimulation2D::new(0.1.into(), position, velocity, 1.);
+        let direction = velocity.normalize();
+        for i in 1..300 {
+            let moved = fling.x(i as f32 * 0.01) - position;
+            assert!(moved.perp_dot(direction).abs() < 0.01);
+            assert!(moved.dot(direction) > 0.);
+        }
+        // Done once the speed, not each axis, is below the tolerance.
+        let t = (1. / velocity.length()).ln() / fling.drag_log;
+        assert!(!fling.is_done(t - 0.01));
+        assert!(fling.is_done(t + 0.01));
+        assert!((fling.x(100.) - fling.final_position()).length() < 0.01);
+    }
+}
@@ -0,0 +1,178 @@
+use super::Simulation;
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::math::F32Ext;
+
+/// Constant acceleration from a starting position and velocity, like a thrown or dropped object.
+/// With a floor the motion stops there, otherwise it goes on forever and is never done.
+pub struct GravitySimulation {
+    acceleration: f32,
+    x: f32,
+    v: f32,
+    floor: Option<f32>,
+    // When `x` reaches the floor, if it ever does.
+    floor_time: f32,
+}
+
+impl GravitySimulation {
+    pub fn new(acceleration: f32, position: f32, velocity: f32) -> Self {
+        Self {
+            acceleration,
+            x: position,
+            v: velocity,
+            floor: None,
+            floor_time: f32::INFINITY,
+        }
+    }
+
+    /// Stops the motion on `floor`, which is on the side the acceleration points to. A
+    /// simulation starting past the floor is done straight away, one starting on it only if it
+    /// isn't moving away from it.
+    pub fn with_floor(mut self, floor: f32) -> Self {
+        let ahead = if self.acceleration != 0. {
+            self.acceleration
+        } else {
+            self.v
+        };
+        self.floor = Some(floor);
+        self.floor_time = if (floor - self.x) * ahead < 0. {
+            0.
+        } else {
+            self.time_at_x(floor)
+        };
+        self
+    }
+
+    /// When the motion goes through `x` heading the way the acceleration points, or infinity
+    /// if it never does.
+    pub fn time_at_x(&self, x: f32) -> f32 {
+        let a = self.acceleration;
+        let distance = x - self.x;
+        if a == 0. {
+            if distance == 0. {
+                return 0.;
+            }
+            let t = distance / self.v;
+            return if t >= 0. { t } else { f32::INFINITY };
+        }
+        let discriminant = self.v * self.v + 2. * a * distance;
+        if discriminant < 0. {
+            return f32::INFINITY;
+        }
+        // The later root, after any rise against the acceleration.
+        let t = (-self.v + a.signum() * discriminant.sqrt()) / a;
+        if t >= 0. {
+            t
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    fn unclamped_x(&self, time: f32) -> f32 {
+        self.x + self.v * time + 0.5 * self.acceleration * time * time
+    }
+}
+
+impl Simulation for GravitySimulation {
+    fn x(&self, time: f32) -> f32 {
+        match self.floor {
+            Some(floor) if time >= self.floor_time => floor,
+            _ => self.unclamped_x(time),
+        }
+    }
+
+    fn dx(&self, time: f32) -> f32 {
+        if time >= self.floor_time {
+            0.
+        } else {
+            self.v + self.acceleration * time
+        }
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        time >= self.floor_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{near_equal, DEFAULT_TOLERANCE};
+
+    #[test]
+    fn follows_constant_acceleration() {
+        let sim = GravitySimulation::new(-10., 5., 3.);
+        for &t in &[0., 0.25, 1., 4.] {
+            assert!(near_equal(
+                sim.x(t),
+                5. + 3. * t - 5. * t * t,
+                DEFAULT_TOLERANCE
+            ));
+            assert!(near_equal(sim.dx(t), 3. - 10. * t, DEFAULT_TOLERANCE));
+        }
+    }
+
+    #[test]
+    fn is_never_done_without_a_floor() {
+        let sim = GravitySimulation::new(-10., 0., 0.);
+        assert!(!sim.is_done(0.));
+        assert!(!sim.is_done(1000.));
+    }
+
+    #[test]
+    fn stops_on_the_floor() {
+        // Thrown up from 0 at 10/s under -10/s², back down on the floor at 0 after 2s.
+        let sim = GravitySimulation::new(-10., 0., 10.).with_floor(0.);
+        assert!(near_equal(sim.time_at_x(0.), 2., DEFAULT_TOLERANCE));
+        assert!(!sim.is_done(1.));
+        assert!(near_equal(sim.x(1.), 5., DEFAULT_TOLERANCE));
+        for &t in &[2., 2.5, 100.] {
+            assert!(sim.is_done(t));
+            assert_eq!(sim.x(t), 0.);
+            assert_eq!(sim.dx(t), 0.);
+        }
+    }
+
+    #[test]
+    fn lands_without_a_jump() {
+        let sim = GravitySimulation::new(-9.8, 3., 1.5).with_floor(-2.);
+        let landing = sim.time_at_x(-2.);
+        let just_before = landing - DEFAULT_TOLERANCE;
+        assert!(!sim.is_done(just_before));
+        assert!(sim.is_done(landing));
+        assert!(near_equal(sim.x(just_before), -2., 0.05));
+        assert!(near_equal(sim.unclamped_x(landing), -2., DEFAULT_TOLERANCE));
+    }
+
+    #[test]
+    fn is_done_straight_away_past_the_floor() {
+        let sim = GravitySimulation::new(-10., -1., 0.).with_floor(0.);
+        assert!(sim.is_done(0.));
+        assert_eq!(sim.x(0.), 0.);
+        assert_eq!(sim.dx(0.), 0.);
+    }
+
+    #[test]
+    fn without_acceleration_the_floor_is_ahead_of_the_velocity() {
+        let sim = GravitySimulation::new(0., 0., 4.).with_floor(8.);
+        assert!(near_equal(sim.time_at_x(8.), 2., DEFAULT_TOLERANCE));
+        assert!(!sim.is_done(1.));
+        assert!(sim.is_done(2.));
+        assert_eq!(sim.x(3.), 8.);
+
+        let receding = GravitySimulation::new(0., 0., -4.).with_floor(8.);
+        assert!(receding.is_done(0.));
+    }
+
+    #[test]
+    fn on_the_floor_is_done_unless_moving_away() {
+        assert!(GravitySimulation::new(-10., 0., 0.)
+            .with_floor(0.)
+            .is_done(0.));
+        assert!(GravitySimulation::new(-10., 0., -3.)
+            .with_floor(0.)
+            .is_done(0.));
+        assert!(GravitySimulation::new(0., 0., 0.)
+            .with_floor(0.)
+            .is_done(0.));
+    }
+}
@@ -0,0 +1,268 @@
+use super::{check_step, near_zero, spring::SpringDescription, InvalidStep, Simulation};
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::math::F32Ext;
+use core::cell::Cell;
+
+/// Forces without a closed-form solution, simulated by an `IntegratedSimulation`.
+pub trait ForceModel {
+    /// Acceleration of a body at position `x` moving at velocity `v`, at `time`.
+    fn acceleration(&self, x: f32, v: f32, time: f32) -> f32;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Integrator {
+    /// Cheap and stable for springs, but loses precision with large steps.
+    SemiImplicitEuler,
+    /// Runge-Kutta 4th order, accurate even with fairly large steps.
+    Rk4,
+}
+
+/// Default integration step, in seconds.
+pub const DEFAULT_STEP: f32 = 1. / 240.;
+
+/// A simulation of any `ForceModel`, numerically integrated in fixed steps from its start. It's
+/// done once the body has both stopped and has no force left pushing it.
+///
+/// The state at the last whole step queried is kept, so querying forward in time only integrates
+/// the steps in between. Going back in time starts over from the start.
+pub struct IntegratedSimulation<F: ForceModel> {
+    model: F,
+    integrator: Integrator,
+    x: f32,
+    v: f32,
+    step: f32,
+    tolerance: f32,
+
+    // Cache: how many whole steps were integrated, and the position and velocity after them.
+    checkpoint: Cell<(u32, f32, f32)>,
+}
+
+impl<F: ForceModel> IntegratedSimulation<F> {
+    pub fn new(
+        model: F,
+        integrator: Integrator,
+        position: f32,
+        velocity: f32,
+        tolerance: f32,
+    ) -> Self {
+        Self {
+            model,
+            integrator,
+            x: position,
+            v: velocity,
+            step: DEFAULT_STEP,
+            tolerance,
+
+            checkpoint: Cell::new((0, position, velocity)),
+        }
+    }
+
+    /// Integrates in steps of `step` seconds instead of `DEFAULT_STEP`.
+    pub fn with_step(mut self, step: f32) -> Result<Self, InvalidStep> {
+        self.step = check_step(step)?;
+        self.checkpoint.set((0, self.x, self.v));
+        Ok(self)
+    }
+
+    fn advance(&self, x: f32, v: f32, t: f32, dt: f32) -> (f32, f32) {
+        let a = |x: f32, v: f32, t: f32| self.model.acceleration(x, v, t);
+        match self.integrator {
+            Integrator::SemiImplicitEuler => {
+                let v = v + a(x, v, t) * dt;
+                (x + v * dt, v)
+            }
+            Integrator::Rk4 => {
+                let (k1x, k1v) = (v, a(x, v, t));
+                let (k2x, k2v) = (
+                    v + k1v * dt / 2.,
+                    a(x + k1x * dt / 2., v + k1v * dt / 2., t + dt / 2.),
+                );
+                let (k3x, k3v) = (
+                    v + k2v * dt / 2.,
+                    a(x + k2x * dt / 2., v + k2v * dt / 2., t + dt / 2.),
+                );
+                let (k4x, k4v) = (v + k3v * dt, a(x + k3x * dt, v + k3v * dt, t + dt));
+                (
+                    x + (k1x + 2. * k2x + 2. * k3x + k4x) * dt / 6.,
+                    v + (k1v + 2. * k2v + 2. * k3v + k4v) * dt / 6.,
+                )
+            }
+        }
+    }
+
+    /// Position and velocity at `time`.
+    pub fn state(&self, time: f32) -> (f32, f32) {
+        let steps = (time / self.step).max(0.).floor() as u32;
+        let (mut done, mut x, mut v) = self.checkpoint.get();
+        if done > steps {
+            done = 0;
+            x = self.x;
+            v = self.v;
+        }
+        while done < steps {
+            let (next_x, next_v) = self.advance(x, v, done as f32 * self.step, self.step);
+            x = next_x;
+            v = next_v;
+            done += 1;
+        }
+        self.checkpoint.set((done, x, v));
+
+        // What's left of `time` after the whole steps, which isn't kept.
+        let t = done as f32 * self.step;
+        if time > t {
+            self.advance(x, v, t, time - t)
+        } else {
+            (x, v)
+        }
+    }
+}
+
+impl<F: ForceModel> Simulation for IntegratedSimulation<F> {
+    fn x(&self, time: f32) -> f32 {
+        self.state(time).0
+    }
+
+    fn dx(&self, time: f32) -> f32 {
+        self.state(time).1
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        let (x, v) = self.state(time);
+        near_zero(v, self.tolerance)
+            && near_zero(self.model.acceleration(x, v, time), self.tolerance)
+    }
+}
+
+/// A spring towards `end`, with air drag proportional to the velocity and a constant external
+/// force (e.g. gravity) on top.
+pub struct DrivenSpring {
+    pub spring: SpringDescription,
+    pub end: f32,
+    pub drag: f32,
+    pub force: f32,
+}
+
+impl ForceModel for DrivenSpring {
+    fn acceleration(&self, x: f32, v: f32, _time: f32) -> f32 {
+        let spring_force = -self.spring.stiffness() * (x - self.end) - self.spring.damping() * v;
+        (spring_force - self.drag * v + self.force) / self.spring.mass()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{spring::SpringSimulation, DEFAULT_TOLERANCE};
+
+    fn free_spring(spring: SpringDescription, end: f32) -> DrivenSpring {
+        DrivenSpring {
+            spring,
+            end,
+            drag: 0.,
+            force: 0.,
+        }
+    }
+
+    fn springs() -> [SpringDescription; 3] {
+        [
+            SpringDescription::from_damping_ratio(1., 200., 0.3),
+            SpringDescription::from_damping_ratio(2.5, 120., 1.),
+            SpringDescription::from_damping_ratio(1., 200., 1.8),
+        ]
+    }
+
+    fn follows_the_analytic_spring(integrator: Integrator, tolerance: f32) {
+        for spring in springs() {
+            let analytic = SpringSimulation::new(spring, 0., 1., 3., DEFAULT_TOLERANCE);
+            let integrated = IntegratedSimulation::new(
+                free_spring(spring, 1.),
+                integrator,
+                0.,
+                3.,
+                DEFAULT_TOLERANCE,
+            );
+            for i in 0..300 {
+                let t = i as f32 * 0.01;
+                assert!(
+                    (integrated.x(t) - analytic.x(t)).abs() < tolerance,
+                    "{:?} x at {}: {} but {}",
+                    integrator,
+                    t,
+                    integrated.x(t),
+                    analytic.x(t)
+                );
+                assert!(
+                    (integrated.dx(t) - analytic.dx(t)).abs() < tolerance * 10.,
+                    "{:?} dx at {}: {} but {}",
+                    integrator,
+                    t,
+                    integrated.dx(t),
+                    analytic.dx(t)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rk4_follows_the_analytic_spring() {
+        follows_the_analytic_spring(Integrator::Rk4, 0.0001);
+    }
+
+    #[test]
+    fn semi_implicit_euler_follows_the_analytic_spring() {
+        follows_the_analytic_spring(Integrator::SemiImplicitEuler, 0.05);
+    }
+
+    #[test]
+    fn settles_like_the_analytic_spring() {
+        for spring in springs() {
+            let analytic = SpringSimulation::new(spring, 0., 1., 3., DEFAULT_TOLERANCE);
+            let integrated = IntegratedSimulation::new(
+                free_spring(spring, 1.),
+                Integrator::Rk4,
+                0.,
+                3.,
+                DEFAULT_TOLERANCE,
+            );
+            assert!(!integrated.is_done(0.1));
+            assert!(integrated.is_done(10.));
+            assert!(analytic.is_done(10.));
+        }
+    }
+
+    #[test]
+    fn the_cache_does_not_change_the_result() {
+        let spring = SpringDescription::from_damping_ratio(1., 200., 0.3);
+        let fresh = || {
+            IntegratedSimulation::new(
+                free_spring(spring, 1.),
+                Integrator::Rk4,
+                0.,
+                3.,
+                DEFAULT_TOLERANCE,
+            )
+        };
+        let cached = fresh();
+        for t in [0.5, 0.503, 1.2, 0.1, 0.1, 2.7, 0., 3.] {
+            assert_eq!(cached.state(t), fresh().state(t), "at {}", t);
+        }
+    }
+
+    #[test]
+    fn rejects_a_step_that_goes_nowhere() {
+        let simulation = || {
+            IntegratedSimulation::new(
+                free_spring(SpringDescription::new(1., 100., 10.), 1.),
+                Integrator::Rk4,
+                0.,
+                0.,
+                DEFAULT_TOLERANCE,
+            )
+        };
+        assert!(simulation().with_step(0.).is_err());
+        assert!(simulation().with_step(-0.01).is_err());
+        assert!(simulation().with_step(f32::NAN).is_err());
+        assert!(simulation().with_step(f32::INFINITY).is_err());
+        assert!(simulation().with_step(0.01).is_ok());
+    }
+}
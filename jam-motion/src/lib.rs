@@ -0,0 +1,58 @@
+//! Spring, friction and gravity simulations, in the style of Flutter's physics library. Simulations are
+//! pure functions of the time elapsed since they started, so they can drive any animation system.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(all(not(feature = "std"), not(feature = "libm")))]
+compile_error!("jam-motion needs either the `std` or the `libm` feature");
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+pub mod baked;
+pub mod chained;
+pub mod clamped;
+pub mod friction;
+pub mod gravity;
+pub mod integrator;
+// Tests link std, whose inherent float methods take precedence over `F32Ext`.
+#[cfg(all(not(feature = "std"), not(test)))]
+mod math;
+pub mod spring;
+
+pub const DEFAULT_TOLERANCE: f32 = 0.001;
+
+/// A time step that isn't a positive, finite number of seconds, so stepping by it would never
+/// get anywhere.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct InvalidStep;
+
+fn check_step(step: f32) -> Result<f32, InvalidStep> {
+    if step > 0. && step.is_finite() {
+        Ok(step)
+    } else {
+        Err(InvalidStep)
+    }
+}
+
+fn near_equal(a: f32, b: f32, epsilon: f32) -> bool {
+    (a > (b - epsilon)) && (a < (b + epsilon)) || a == b
+}
+
+fn near_zero(n: f32, epsilon: f32) -> bool {
+    near_equal(n, 0., epsilon)
+}
+
+pub trait Simulation {
+    fn x(&self, time: f32) -> f32;
+    fn dx(&self, time: f32) -> f32;
+    fn is_done(&self, time: f32) -> bool;
+}
+
+/// A `Simulation` of a 2D position.
+#[cfg(feature = "glam")]
+pub trait Simulation2D {
+    fn x(&self, time: f32) -> glam::Vec2;
+    fn dx(&self, time: f32) -> glam::Vec2;
+    fn is_done(&self, time: f32) -> bool;
+}
@@ -0,0 +1,45 @@
+// The float functions that std provides as inherent methods, for no_std builds.
+pub(crate) trait F32Ext {
+    fn powf(self, n: f32) -> f32;
+    fn powi(self, n: i32) -> f32;
+    fn ln(self) -> f32;
+    fn sqrt(self) -> f32;
+    fn sin(self) -> f32;
+    fn cos(self) -> f32;
+    fn floor(self) -> f32;
+    fn atan2(self, other: f32) -> f32;
+}
+
+impl F32Ext for f32 {
+    fn powf(self, n: f32) -> f32 {
+        libm_math::powf(self, n)
+    }
+
+    fn powi(self, n: i32) -> f32 {
+        libm_math::powf(self, n as f32)
+    }
+
+    fn ln(self) -> f32 {
+        libm_math::logf(self)
+    }
+
+    fn sqrt(self) -> f32 {
+        libm_math::sqrtf(self)
+    }
+
+    fn sin(self) -> f32 {
+        libm_math::sinf(self)
+    }
+
+    fn cos(self) -> f32 {
+        libm_math::cosf(self)
+    }
+
+    fn floor(self) -> f32 {
+        libm_math::floorf(self)
+    }
+
+    fn atan2(self, other: f32) -> f32 {
+        libm_math::atan2f(self, other)
+    }
+}
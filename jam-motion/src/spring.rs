@@ -0,0 +1,701 @@
+use super::{near_zero, Simulation, DEFAULT_TOLERANCE};
+#[cfg(all(not(feature = "std"), not(test)))]
+use crate::math::F32Ext;
+use core::f32::consts::{E, PI};
+#[cfg(feature = "glam")]
+use {super::Simulation2D, glam::Vec2};
+
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpringDescription {
+    mass: f32,
+    stiffness: f32,
+    damping: f32,
+}
+
+/// Why no spring can be made from the requested duration and bounce.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpringFromDurationError {
+    /// The duration isn't above 0 seconds.
+    DurationNotPositive,
+    /// The bounce isn't strictly between -1 and 1: at 1 the spring has no damping and never
+    /// settles, at -1 the damping is infinite.
+    BounceOutOfRange,
+}
+
+impl SpringDescription {
+    pub fn new(mass: f32, stiffness: f32, damping: f32) -> Self {
+        Self {
+            mass,
+            stiffness,
+            damping,
+        }
+    }
+
+    /// Creates a spring given the mass (m), stiffness (k), and damping ratio (ζ). The damping
+    /// ratio is especially useful trying to determining the type of spring to create.
+    /// A ratio of 1.0 creates a critically damped spring, > 1.0 creates an overdamped spring and
+    /// < 1.0 an underdamped one.
+    pub fn from_damping_ratio(mass: f32, stiffness: f32, ratio: f32) -> Self {
+        Self {
+            mass,
+            stiffness,
+            damping: ratio * 2. * (mass * stiffness).sqrt(),
+        }
+    }
+
+    /// Creates a spring of mass 1 from how long it roughly takes to settle, in seconds, and how
+    /// bouncy it is. A bounce of 0 is critically damped, towards 1 it overshoots more and more,
+    /// and towards -1 it gets more and more sluggish.
+    pub fn with_duration_and_bounce(
+        duration: f32,
+        bounce: f32,
+    ) -> Result<Self, SpringFromDurationError> {
+        if !(duration > 0. && duration.is_finite()) {
+            return Err(SpringFromDurationError::DurationNotPositive);
+        }
+        if !(bounce > -1. && bounce < 1.) {
+            return Err(SpringFromDurationError::BounceOutOfRange);
+        }
+        let stiffness = (2. * PI / duration).powi(2);
+        let damping = if bounce >= 0. {
+            (1. - bounce) * 4. * PI / duration
+        } else {
+            4. * PI / (duration * (1. + bounce))
+        };
+        Ok(Self::new(1., stiffness, damping))
+    }
+
+    pub fn mass(&self) -> f32 {
+        self.mass
+    }
+
+    pub fn stiffness(&self) -> f32 {
+        self.stiffness
+    }
+
+    pub fn damping(&self) -> f32 {
+        self.damping
+    }
+}
+
+/// Springs whose damping ratio is this close to 1 are simulated as critically damped: the
+/// under and overdamped solutions are numerically unstable around it.
+pub const SPRING_TYPE_EPSILON: f32 = 0.001;
+
+/// The kind of spring solution that the spring simulation is using to simulate the spring.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpringType {
+    /// A spring that does not bounce and returns to its rest position in the shortest possible
+    /// time.
+    CriticallyDamped,
+
+    /// A spring that bounces.
+    UnderDamped,
+
+    /// A spring that does not bounce but takes longer to return to its rest position than a
+    /// critically damped one.
+    OverDamped,
+}
+
+impl SpringType {
+    /// Classifies `spring`, treating it as critically damped when its damping ratio squared is
+    /// within `epsilon` of 1.
+    pub fn classify(spring: &SpringDescription, epsilon: f32) -> Self {
+        let critical = 4. * spring.mass * spring.stiffness;
+        let cmk = spring.damping * spring.damping - critical;
+        if cmk.abs() <= epsilon * critical {
+            SpringType::CriticallyDamped
+        } else if cmk > 0. {
+            SpringType::OverDamped
+        } else {
+            SpringType::UnderDamped
+        }
+    }
+}
+
+pub struct SpringSimulation {
+    spring: SpringDescription,
+    start: f32,
+    end: f32,
+    velocity: f32,
+    tolerance: f32,
+
+    // Cache
+    solution: SpringSolution,
+}
+
+impl SpringSimulation {
+    pub fn new(
+        spring: SpringDescription,
+        start: f32,
+        end: f32,
+        velocity: f32,
+        tolerance: f32,
+    ) -> Self {
+        SpringSimulation {
+            spring,
+            start,
+            end,
+            velocity,
+            tolerance,
+
+            solution: SpringSolution::new(spring, start - end, velocity),
+        }
+    }
+
+    pub fn spring(&self) -> SpringDescription {
+        self.spring
+    }
+
+    pub fn start(&self) -> f32 {
+        self.start
+    }
+
+    pub fn end(&self) -> f32 {
+        self.end
+    }
+
+    /// The velocity the simulation started with.
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    /// A new simulation towards `end` that starts where this one is at `time`, with the same
+    /// velocity, so that changing target mid-flight doesn't snap.
+    pub fn retarget(&self, time: f32, spring: SpringDescription, end: f32) -> Self {
+        SpringSimulation::new(spring, self.x(time), end, self.dx(time), self.tolerance)
+    }
+
+    /// How far past `end` the simulation goes before settling, 0 if it never does.
+    pub fn max_overshoot(&self) -> f32 {
+        self.solution.max_overshoot()
+    }
+
+    /// Whether the simulation goes past `boundary`, which lies beyond `end` on the side it
+    /// overshoots to.
+    pub fn overshoots_past(&self, boundary: f32) -> bool {
+        let direction = self.solution.overshoot_direction();
+        let furthest = self.end + direction * self.max_overshoot();
+        (boundary - self.end) * direction > 0. && (furthest - boundary) * direction > 0.
+    }
+
+    /// Kinetic plus potential energy of the spring at `time`.
+    pub fn energy(&self, time: f32) -> f32 {
+        self.solution.energy(time)
+    }
+
+    pub fn x_or_end_x(&self, time: f32) -> XOrEndX {
+        if self.is_done(time) {
+            XOrEndX {
+                is_done: true,
+                x: self.end,
+            }
+        } else {
+            XOrEndX {
+                is_done: false,
+                x: self.x(time),
+            }
+        }
+    }
+}
+
+pub struct XOrEndX {
+    pub is_done: bool,
+    pub x: f32,
+}
+
+impl Simulation for SpringSimulation {
+    fn x(&self, time: f32) -> f32 {
+        self.end + self.solution.x(time)
+    }
+
+    fn dx(&self, time: f32) -> f32 {
+        self.solution.dx(time)
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        near_zero(self.solution.x(time), DEFAULT_TOLERANCE)
+            && near_zero(self.solution.dx(time), self.tolerance)
+    }
+}
+
+/// A spring pulling a 2D position towards `end`. Both axes share the spring, so one simulation
+/// does what would otherwise take a `SpringSimulation` per axis.
+#[cfg(feature = "glam")]
+pub struct SpringSimulation2D {
+    start: Vec2,
+    end: Vec2,
+    velocity: Vec2,
+    tolerance: f32,
+
+    // The spring is linear, so any motion is the one released from 1 at rest scaled by the start
+    // offset, plus the one thrown from rest at 1 scaled by the velocity.
+    released: SpringSolution,
+    thrown: SpringSolution,
+}
+
+#[cfg(feature = "glam")]
+impl SpringSimulation2D {
+    pub fn new(
+        spring: SpringDescription,
+        start: Vec2,
+        end: Vec2,
+        velocity: Vec2,
+        tolerance: f32,
+    ) -> Self {
+        Self {
+            start,
+            end,
+            velocity,
+            tolerance,
+
+            released: SpringSolution::new(spring, 1., 0.),
+            thrown: SpringSolution::new(spring, 0., 1.),
+        }
+    }
+
+    /// A new simulation towards `end` that starts where this one is at `time`, with the same
+    /// velocity, so that changing target mid-flight doesn't snap.
+    pub fn retarget(&self, time: f32, spring: SpringDescription, end: Vec2) -> Self {
+        Self::new(spring, self.x(time), end, self.dx(time), self.tolerance)
+    }
+
+    pub fn end(&self) -> Vec2 {
+        self.end
+    }
+
+    fn offset(&self, time: f32) -> Vec2 {
+        (self.start - self.end) * self.released.x(time) + self.velocity * self.thrown.x(time)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl Simulation2D for SpringSimulation2D {
+    fn x(&self, time: f32) -> Vec2 {
+        self.end + self.offset(time)
+    }
+
+    fn dx(&self, time: f32) -> Vec2 {
+        (self.start - self.end) * self.released.dx(time) + self.velocity * self.thrown.dx(time)
+    }
+
+    fn is_done(&self, time: f32) -> bool {
+        self.offset(time).length() < DEFAULT_TOLERANCE && self.dx(time).length() < self.tolerance
+    }
+}
+
+pub struct SpringSolution {
+    spring: SpringDescription,
+    initial_position: f32,
+    initial_velocity: f32,
+}
+
+impl SpringSolution {
+    pub fn new(spring: SpringDescription, initial_position: f32, initial_velocity: f32) -> Self {
+        Self {
+            spring,
+            initial_position,
+            initial_velocity,
+        }
+    }
+
+    pub fn spring_type(&self) -> SpringType {
+        SpringType::classify(&self.spring, SPRING_TYPE_EPSILON)
+    }
+
+    /// Kinetic plus potential energy at `time`.
+    pub fn energy(&self, time: f32) -> f32 {
+        let x = self.x(time);
+        let v = self.dx(time);
+        0.5 * self.spring.mass * v * v + 0.5 * self.spring.stiffness * x * x
+    }
+
+    // The side of the rest position an overshoot would be on: opposite to where the spring
+    // starts, or where it's thrown to when it starts at rest position.
+    fn overshoot_direction(&self) -> f32 {
+        if self.initial_position != 0. {
+            -self.initial_position.signum()
+        } else {
+            self.initial_velocity.signum()
+        }
+    }
+
+    /// How far past the rest position the spring goes before settling, 0 if it never does.
+    pub fn max_overshoot(&self) -> f32 {
+        let distance = self.initial_position;
+        let velocity = self.initial_velocity;
+        if distance == 0. && velocity == 0. {
+            return 0.;
+        }
+
+        // The extremes of the motion are where the velocity is 0. After the first two, the
+        // motion only gets smaller.
+        let turning_points = match self.spring_type() {
+            SpringType::CriticallyDamped => {
+                let r = -self.spring.damping / (2. * self.spring.mass);
+                let c1 = distance;
+                let c2 = velocity - r * distance;
+
+                [-c1 / c2 - 1. / r, f32::NAN]
+            }
+            SpringType::OverDamped => {
+                let cmk = self.spring.damping * self.spring.damping
+                    - 4. * self.spring.mass * self.spring.stiffness;
+                let r1 = (-self.spring.damping - cmk.sqrt()) / (2. * self.spring.mass);
+                let r2 = (-self.spring.damping + cmk.sqrt()) / (2. * self.spring.mass);
+                let c2 = (velocity - r1 * distance) / (r2 - r1);
+                let c1 = distance - c2;
+
+                [(-(c2 * r2) / (c1 * r1)).ln() / (r1 - r2), f32::NAN]
+            }
+            SpringType::UnderDamped => {
+                let w = (4. * self.spring.mass * self.spring.stiffness
+                    - self.spring.damping * self.spring.damping)
+                    .sqrt()
+                    / (2. * self.spring.mass);
+                let r = -self.spring.damping / (2. * self.spring.mass);
+                let c1 = distance;
+                let c2 = (velocity - r * distance) / w;
+
+                // dx is 0 where a * cos(wt) + b * sin(wt) is.
+                let a = c2 * w + r * c1;
+                let b = r * c2 - c1 * w;
+                let half_period = PI / w;
+                let mut first = (-a).atan2(b) / w;
+                while first <= 0. {
+                    first += half_period;
+                }
+                [first, first + half_period]
+            }
+        };
+
+        let direction = self.overshoot_direction();
+        turning_points
+            .iter()
+            .filter(|t| t.is_finite() && **t > 0.)
+            .map(|t| direction * self.x(*t))
+            .fold(0., f32::max)
+    }
+
+    pub fn x(&self, time: f32) -> f32 {
+        let distance = self.initial_position;
+        let velocity = self.initial_velocity;
+
+        match self.spring_type() {
+            SpringType::CriticallyDamped => {
+                let r = -self.spring.damping / (2. * self.spring.mass);
+                let c1 = distance;
+                let c2 = velocity - r * distance;
+
+                (c1 + c2 * time) * E.powf(r * time)
+            }
+            SpringType::OverDamped => {
+                let cmk = self.spring.damping * self.spring.damping
+                    - 4. * self.spring.mass * self.spring.stiffness;
+                let r1 = (-self.spring.damping - cmk.sqrt()) / (2. * self.spring.mass);
+                let r2 = (-self.spring.damping + cmk.sqrt()) / (2. * self.spring.mass);
+                let c2 = (velocity - r1 * distance) / (r2 - r1);
+                let c1 = distance - c2;
+
+                c1 * E.powf(r1 * time) + c2 * E.powf(r2 * time)
+            }
+            SpringType::UnderDamped => {
+                let w = (4. * self.spring.mass * self.spring.stiffness
+                    - self.spring.damping * self.spring.damping)
+                    .sqrt()
+                    / (2. * self.spring.mass);
+                let r = -self.spring.damping / (2. * self.spring.mass);
+                let c1 = distance;
+                let c2 = (velocity - r * distance) / w;
+
+                E.powf(r * time) * (c1 * (w * time).cos() + c2 * (w * time).sin())
+            }
+        }
+    }
+
+    pub fn dx(&self, time: f32) -> f32 {
+        let distance = self.initial_position;
+        let velocity = self.initial_velocity;
+
+        match self.spring_type() {
+            SpringType::CriticallyDamped => {
+                let r = -self.spring.damping / (2. * self.spring.mass);
+                let c1 = distance;
+                let c2 = velocity - r * distance;
+
+                let power = E.powf(r * time);
+                r * (c1 + c2 * time) * power + c2 * power
+            }
+            SpringType::OverDamped => {
+                let cmk = self.spring.damping * self.spring.damping
+                    - 4. * self.spring.mass * self.spring.stiffness;
+                let r1 = (-self.spring.damping - cmk.sqrt()) / (2. * self.spring.mass);
+                let r2 = (-self.spring.damping + cmk.sqrt()) / (2. * self.spring.mass);
+                let c2 = (velocity - r1 * distance) / (r2 - r1);
+                let c1 = distance - c2;
+
+                c1 * r1 * E.powf(r1 * time) + c2 * r2 * E.powf(r2 * time)
+            }
+            SpringType::UnderDamped => {
+                let w = (4. * self.spring.mass * self.spring.stiffness
+                    - self.spring.damping * self.spring.damping)
+                    .sqrt()
+                    / (2. * self.spring.mass);
+                let r = -self.spring.damping / (2. * self.spring.mass);
+                let c1 = distance;
+                let c2 = (velocity - r * distance) / w;
+
+                let power = E.powf(r * time);
+                let cosine = (w * time).cos();
+                let sine = (w * time).sin();
+                power * (c2 * w * cosine - c1 * w * sine) + r * power * (c2 * sine + c1 * cosine)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    #[test]
+    fn bounce_picks_the_spring_type() {
+        let critical = SpringDescription::with_duration_and_bounce(0.5, 0.).unwrap();
+        let bouncy = SpringDescription::with_duration_and_bounce(0.5, 0.5).unwrap();
+        let sluggish = SpringDescription::with_duration_and_bounce(0.5, -0.5).unwrap();
+        assert_eq!(
+            SpringType::classify(&critical, SPRING_TYPE_EPSILON),
+            SpringType::CriticallyDamped
+        );
+        assert_eq!(
+            SpringType::classify(&bouncy, SPRING_TYPE_EPSILON),
+            SpringType::UnderDamped
+        );
+        assert_eq!(
+            SpringType::classify(&sluggish, SPRING_TYPE_EPSILON),
+            SpringType::OverDamped
+        );
+    }
+
+    #[test]
+    fn rejects_a_duration_that_is_not_positive() {
+        for duration in [0., -0.5, f32::NAN, f32::INFINITY] {
+            assert_eq!(
+                SpringDescription::with_duration_and_bounce(duration, 0.).err(),
+                Some(SpringFromDurationError::DurationNotPositive)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_a_bounce_out_of_range() {
+        // A bounce of 1 would have no damping, and never settle.
+        for bounce in [1., -1., 1.5, -3., f32::NAN] {
+            assert_eq!(
+                SpringDescription::with_duration_and_bounce(0.5, bounce).err(),
+                Some(SpringFromDurationError::BounceOutOfRange)
+            );
+        }
+        assert!(SpringDescription::with_duration_and_bounce(0.5, 0.99).is_ok());
+        assert!(SpringDescription::with_duration_and_bounce(0.5, -0.99).is_ok());
+    }
+
+    #[test]
+    fn classifies_by_damping_ratio_at_any_scale() {
+        for (mass, stiffness) in [(1., 100.), (0.01, 3.), (500., 20_000.)] {
+            let classify = |ratio| {
+                SpringType::classify(
+                    &SpringDescription::from_damping_ratio(mass, stiffness, ratio),
+                    SPRING_TYPE_EPSILON,
+                )
+            };
+            assert_eq!(classify(0.5), SpringType::UnderDamped);
+            assert_eq!(classify(1.), SpringType::CriticallyDamped);
+            assert_eq!(classify(2.), SpringType::OverDamped);
+        }
+    }
+
+    #[test]
+    fn nearly_critical_springs_are_critical_within_epsilon() {
+        let below = SpringDescription::from_damping_ratio(1., 100., 0.9999);
+        let above = SpringDescription::from_damping_ratio(1., 100., 1.0001);
+        for spring in [below, above] {
+            assert_eq!(
+                SpringType::classify(&spring, SPRING_TYPE_EPSILON),
+                SpringType::CriticallyDamped
+            );
+        }
+        assert_eq!(SpringType::classify(&below, 0.), SpringType::UnderDamped);
+        assert_eq!(SpringType::classify(&above, 0.), SpringType::OverDamped);
+    }
+
+    #[test]
+    fn motion_is_continuous_across_the_epsilon() {
+        let simulation = |ratio| {
+            SpringSimulation::new(
+                SpringDescription::from_damping_ratio(1., 100., ratio),
+                0.,
+                1.,
+                2.,
+                DEFAULT_TOLERANCE,
+            )
+        };
+        let inside = simulation(1. + SPRING_TYPE_EPSILON / 4.);
+        let outside = simulation(1. + SPRING_TYPE_EPSILON);
+        assert_eq!(inside.solution.spring_type(), SpringType::CriticallyDamped);
+        assert_eq!(outside.solution.spring_type(), SpringType::OverDamped);
+        for i in 0..200 {
+            let t = i as f32 * 0.01;
+            assert!((inside.x(t) - outside.x(t)).abs() < 0.01, "at {}", t);
+        }
+    }
+
+    const SAMPLE_STEP: f32 = 0.0005;
+    const SAMPLE_DURATION: f32 = 10.;
+
+    fn samples() -> impl Iterator<Item = f32> {
+        (0..(SAMPLE_DURATION / SAMPLE_STEP) as u32).map(|i| i as f32 * SAMPLE_STEP)
+    }
+
+    // How far past `end` the sampled motion goes, on the side it overshoots to.
+    fn sampled_overshoot(simulation: &SpringSimulation) -> f32 {
+        let direction = simulation.solution.overshoot_direction();
+        samples()
+            .map(|t| direction * (simulation.x(t) - simulation.end()))
+            .fold(0., f32::max)
+    }
+
+    fn simulations() -> Vec<SpringSimulation> {
+        let springs = [
+            SpringDescription::from_damping_ratio(1., 200., 0.3),
+            SpringDescription::from_damping_ratio(2.5, 120., 0.4),
+            SpringDescription::from_damping_ratio(1., 200., 1.),
+            SpringDescription::from_damping_ratio(2.5, 120., 1.),
+            SpringDescription::from_damping_ratio(1., 200., 1.8),
+            SpringDescription::from_damping_ratio(2.5, 120., 2.5),
+        ];
+        let throws = [(0., 1.), (0., 5.), (-0.5, 1.), (0.5, -30.), (1., 40.)];
+        springs
+            .iter()
+            .flat_map(|spring| {
+                throws.iter().map(move |(start, velocity)| {
+                    SpringSimulation::new(*spring, *start, 1., *velocity, DEFAULT_TOLERANCE)
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn max_overshoot_matches_the_sampled_motion() {
+        for simulation in simulations() {
+            let sampled = sampled_overshoot(&simulation);
+            let predicted = simulation.max_overshoot();
+            assert!(
+                (sampled - predicted).abs() < 0.001,
+                "mass {}, damping {}, start {}, velocity {}: sampled {} but predicted {}",
+                simulation.spring().mass(),
+                simulation.spring().damping(),
+                simulation.start(),
+                simulation.velocity(),
+                sampled,
+                predicted
+            );
+        }
+    }
+
+    #[test]
+    fn overshoots_past_matches_the_sampled_motion() {
+        for simulation in simulations() {
+            let sampled = sampled_overshoot(&simulation);
+            if sampled < 0.01 {
+                continue;
+            }
+            let direction = simulation.solution.overshoot_direction();
+            let nearer = simulation.end() + direction * (sampled - 0.005);
+            let further = simulation.end() + direction * (sampled + 0.005);
+            assert!(simulation.overshoots_past(nearer));
+            assert!(!simulation.overshoots_past(further));
+        }
+    }
+
+    #[test]
+    fn an_underdamped_spring_overshoots_and_an_overdamped_one_from_rest_does_not() {
+        let bouncy = SpringDescription::from_damping_ratio(2.5, 120., 0.4);
+        let sluggish = SpringDescription::from_damping_ratio(2.5, 120., 2.5);
+        assert!(SpringSimulation::new(bouncy, 0., 1., 0., DEFAULT_TOLERANCE).max_overshoot() > 0.);
+        assert_eq!(
+            SpringSimulation::new(sluggish, 0., 1., 0., DEFAULT_TOLERANCE).max_overshoot(),
+            0.
+        );
+    }
+
+    #[test]
+    fn energy_matches_the_sampled_motion_and_never_grows() {
+        for simulation in simulations() {
+            let spring = simulation.spring();
+            let mut last_energy = f32::INFINITY;
+            for t in samples().skip(1).step_by(50) {
+                let x = simulation.x(t) - simulation.end();
+                let v = (simulation.x(t + SAMPLE_STEP) - simulation.x(t - SAMPLE_STEP))
+                    / (2. * SAMPLE_STEP);
+                let sampled = 0.5 * spring.mass() * v * v + 0.5 * spring.stiffness() * x * x;
+                let energy = simulation.energy(t);
+                assert!(
+                    (sampled - energy).abs() <= 0.01 * energy.max(1.),
+                    "mass {}, damping {}, at {}: sampled {} but computed {}",
+                    spring.mass(),
+                    spring.damping(),
+                    t,
+                    sampled,
+                    energy
+                );
+                assert!(energy <= last_energy + 0.001);
+                last_energy = energy;
+            }
+        }
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn a_2d_spring_moves_like_one_simulation_per_axis() {
+        let (start, end, velocity) = (Vec2::new(-3., 2.), Vec2::new(5., -1.), Vec2::new(20., 40.));
+        for spring in [
+            SpringDescription::from_damping_ratio(1., 200., 0.3),
+            SpringDescription::from_damping_ratio(2.5, 120., 1.),
+            SpringDescription::from_damping_ratio(1., 200., 1.8),
+        ] {
+            let simulation =
+                SpringSimulation2D::new(spring, start, end, velocity, DEFAULT_TOLERANCE);
+            let x = SpringSimulation::new(spring, start.x, end.x, velocity.x, DEFAULT_TOLERANCE);
+            let y = SpringSimulation::new(spring, start.y, end.y, velocity.y, DEFAULT_TOLERANCE);
+            for i in 0..300 {
+                let t = i as f32 * 0.01;
+                let expected = Vec2::new(x.x(t), y.x(t));
+                let expected_dx = Vec2::new(x.dx(t), y.dx(t));
+                assert!((simulation.x(t) - expected).length() < 0.001, "at {}", t);
+                assert!((simulation.dx(t) - expected_dx).length() < 0.01, "at {}", t);
+            }
+            assert!((simulation.x(0.) - start).length() < 0.001);
+            assert!((simulation.dx(0.) - velocity).length() < 0.001);
+            assert!(!simulation.is_done(0.));
+            assert!(simulation.is_done(10.));
+            assert!(x.is_done(10.) && y.is_done(10.));
+        }
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn a_retargeted_2d_spring_carries_on_from_where_it_was() {
+        let spring = SpringDescription::from_damping_ratio(1., 200., 0.5);
+        let simulation =
+            SpringSimulation2D::new(spring, Vec2::ZERO, Vec2::ONE, Vec2::ZERO, DEFAULT_TOLERANCE);
+        let retargeted = simulation.retarget(0.1, spring, Vec2::new(-2., 3.));
+        assert_eq!(retargeted.end(), Vec2::new(-2., 3.));
+        assert!((retargeted.x(0.) - simulation.x(0.1)).length() < 0.001);
+        assert!((retargeted.dx(0.) - simulation.dx(0.1)).length() < 0.01);
+        assert!((retargeted.x(10.) - Vec2::new(-2., 3.)).length() < 0.001);
+    }
+}
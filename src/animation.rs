@@ -0,0 +1,624 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use jam_motion::{
+    spring::{SpringDescription, SpringSimulation},
+    Simulation,
+};
+
+use crate::MotionSettings;
+
+/// Speed of gameplay animations. Menus and toasts keep running at real speed.
+pub struct TimeScale {
+    /// 1 is normal speed, lower values slow animations down.
+    pub speed: f32,
+    /// The animation speed picked in the options, applied on top of `speed`. `INSTANT_SPEED`
+    /// skips gameplay animations to their end.
+    pub preference: f32,
+    /// Freezes gameplay animations where they are, e.g. while a menu is open.
+    pub paused: bool,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            speed: 1.,
+            preference: 1.,
+            paused: false,
+        }
+    }
+}
+
+/// The "instant" animation speed.
+pub const INSTANT_SPEED: f32 = f32::INFINITY;
+
+impl TimeScale {
+    pub fn is_instant(&self) -> bool {
+        self.preference == INSTANT_SPEED
+    }
+
+    fn factor(&self) -> f32 {
+        if self.paused {
+            0.
+        } else if self.is_instant() {
+            // Animations snap to their end instead, what else is timed on the clock keeps its pace.
+            self.speed
+        } else {
+            self.speed * self.preference
+        }
+    }
+}
+
+// A frame longer than this is most likely the app coming back from being suspended (e.g. the
+// browser tab was in the background): animations carry on from where they were instead of
+// jumping to their end.
+const MAX_FRAME_DELTA: f32 = 1. / 15.;
+
+/// The time gameplay animations are driven by. It accumulates frame deltas scaled by
+/// `TimeScale`, so it stands still while paused.
+#[derive(Default)]
+pub struct AnimationClock {
+    now: f32,
+    delta: f32,
+    instant: bool,
+}
+
+impl AnimationClock {
+    pub fn now(&self) -> f32 {
+        self.now
+    }
+
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    /// Animator tracks jump to their end, see `TimeScale::is_instant`.
+    pub fn is_instant(&self) -> bool {
+        self.instant
+    }
+}
+
+pub(crate) struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayAnimation>()
+            .add_event::<AnimationFinished>()
+            .add_event::<AnimatorDone>()
+            .add_event::<SimulationDone>()
+            .add_event::<AnimationGroupDone>()
+            .init_resource::<TimeScale>()
+            .init_resource::<AnimationClock>()
+            .init_resource::<AnimationGroups>()
+            .init_resource::<SpringPresets>()
+            .add_system_to_stage(CoreStage::PreUpdate, tick_animation_clock)
+            .add_system(animate_simulations.label("animate_simulations"))
+            .add_system_to_stage(
+                "view",
+                play_animations
+                    .after("update_cursor")
+                    .after("update_tiles_position")
+                    .after("update_cards_style")
+                    .before("animate"),
+            )
+            .add_system_to_stage(
+                "view",
+                animate
+                    .label("animate")
+                    .after("update_tiles_position")
+                    .after("update_cards_style"),
+            )
+            .add_system_to_stage("view", join_animation_groups.after("animate"));
+    }
+}
+
+pub fn tick_animation_clock(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut clock: ResMut<AnimationClock>,
+) {
+    clock.delta = time.delta_seconds().min(MAX_FRAME_DELTA) * time_scale.factor();
+    clock.now += clock.delta;
+    // Paused animations stay where they are.
+    clock.instant = time_scale.is_instant() && !time_scale.paused;
+}
+
+/// The property an animator track drives. An animator has at most one track per property.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AnimatedProperty {
+    Translation,
+    Scale,
+    Tint,
+}
+
+#[derive(Clone, Copy)]
+pub enum AnimationTarget {
+    Translation {
+        from: Vec3,
+        to: Vec3,
+    },
+    Scale {
+        from: Vec3,
+        to: Vec3,
+    },
+    /// Drives the `Sprite` or `TextureAtlasSprite` colour.
+    Tint {
+        from: Color,
+        to: Color,
+    },
+}
+
+impl AnimationTarget {
+    fn property(&self) -> AnimatedProperty {
+        match self {
+            AnimationTarget::Translation { .. } => AnimatedProperty::Translation,
+            AnimationTarget::Scale { .. } => AnimatedProperty::Scale,
+            AnimationTarget::Tint { .. } => AnimatedProperty::Tint,
+        }
+    }
+
+    // All properties are animated as 4 channels, the last one is unused by transforms.
+    fn channels(&self) -> (Vec4, Vec4) {
+        match *self {
+            AnimationTarget::Translation { from, to } | AnimationTarget::Scale { from, to } => {
+                (from.extend(0.), to.extend(0.))
+            }
+            AnimationTarget::Tint { from, to } => {
+                (Vec4::from(from.as_rgba_f32()), Vec4::from(to.as_rgba_f32()))
+            }
+        }
+    }
+}
+
+/// The springs gameplay animations are tuned with, looked up in `SpringPresets`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SpringPreset {
+    CardHover,
+    CursorMove,
+    TileMove,
+    DealIn,
+}
+
+#[cfg(feature = "debug")]
+impl SpringPreset {
+    pub const ALL: [SpringPreset; 4] = [
+        SpringPreset::CardHover,
+        SpringPreset::CursorMove,
+        SpringPreset::TileMove,
+        SpringPreset::DealIn,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpringPreset::CardHover => "card_hover",
+            SpringPreset::CursorMove => "cursor_move",
+            SpringPreset::TileMove => "tile_move",
+            SpringPreset::DealIn => "deal_in",
+        }
+    }
+}
+
+pub struct SpringPresets {
+    springs: HashMap<SpringPreset, SpringDescription>,
+}
+
+impl Default for SpringPresets {
+    fn default() -> Self {
+        let mut springs = HashMap::default();
+        springs.insert(
+            SpringPreset::CardHover,
+            SpringDescription::from_damping_ratio(1., 300., 0.6),
+        );
+        springs.insert(
+            SpringPreset::CursorMove,
+            SpringDescription::from_damping_ratio(1., 400., 0.8),
+        );
+        springs.insert(
+            SpringPreset::TileMove,
+            SpringDescription::from_damping_ratio(1., 180., 0.75),
+        );
+        springs.insert(
+            SpringPreset::DealIn,
+            SpringDescription::from_damping_ratio(1., 150., 0.8),
+        );
+        Self { springs }
+    }
+}
+
+impl SpringPresets {
+    pub fn get(&self, preset: SpringPreset) -> SpringDescription {
+        self.springs[&preset]
+    }
+
+    #[cfg(feature = "debug")]
+    pub fn set(&mut self, preset: SpringPreset, spring: SpringDescription) {
+        self.springs.insert(preset, spring);
+    }
+}
+
+/// A spring animation of one property, that can be started on any entity.
+#[derive(Clone, Copy)]
+pub struct AnimationSpec {
+    pub target: AnimationTarget,
+    pub spring: SpringDescription,
+}
+
+/// Send this to start `spec` on `entity` after `delay` seconds. It replaces the track of the
+/// same property if the entity is already animating it; the other tracks keep running.
+/// If that track is in motion, the new one picks up from its current value and velocity
+/// right away instead of starting over from `from`, and `delay` is ignored.
+pub struct PlayAnimation {
+    pub entity: Entity,
+    pub spec: AnimationSpec,
+    pub delay: f32,
+    pub group: Option<AnimationGroup>,
+}
+
+/// Handle of a set of animations that are waited on together, see `AnimationGroups`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AnimationGroup(u32);
+
+/// Sent once every animation of the group is done.
+pub struct AnimationGroupDone(pub AnimationGroup);
+
+/// Animation groups still waiting on some of their tracks. Start a group, then pass its handle
+/// along with each `PlayAnimation` that belongs to it.
+#[derive(Default)]
+pub struct AnimationGroups {
+    next: u32,
+    pending: HashMap<AnimationGroup, HashSet<(Entity, AnimatedProperty)>>,
+}
+
+impl AnimationGroups {
+    pub fn start(&mut self) -> AnimationGroup {
+        self.next += 1;
+        AnimationGroup(self.next)
+    }
+
+    pub fn is_pending(&self, group: AnimationGroup) -> bool {
+        self.pending.contains_key(&group)
+    }
+}
+
+/// Sent when one track of an entity's animator is done. A track replaced by a new
+/// `PlayAnimation` before the end doesn't count as done.
+pub struct AnimationFinished {
+    pub entity: Entity,
+    pub property: AnimatedProperty,
+}
+
+/// Sent when the last running track of an entity's animator is done.
+pub struct AnimatorDone(pub Entity);
+
+#[derive(Clone, Copy)]
+pub enum StaggerEasing {
+    Linear,
+    /// The first entities start close together, the last ones trail behind.
+    EaseIn,
+    /// The first entities trail apart, the last ones bunch up.
+    EaseOut,
+}
+
+/// How start delays are spread over a group: the first entity starts right away, the last one
+/// after `total_delay`.
+#[derive(Clone, Copy)]
+pub struct Stagger {
+    pub total_delay: f32,
+    pub easing: StaggerEasing,
+}
+
+impl Stagger {
+    fn delay(&self, index: usize, count: usize) -> f32 {
+        if count < 2 {
+            return 0.;
+        }
+        let t = index as f32 / (count - 1) as f32;
+        let eased = match self.easing {
+            StaggerEasing::Linear => t,
+            StaggerEasing::EaseIn => t * t,
+            StaggerEasing::EaseOut => 1. - (1. - t) * (1. - t),
+        };
+        eased * self.total_delay
+    }
+}
+
+/// What gameplay systems need to play their animations: the player's motion settings, the
+/// tuned springs, the clock and the groups animations are waited on with.
+#[derive(SystemParam)]
+pub(crate) struct Motion<'w, 's> {
+    pub(crate) settings: Res<'w, MotionSettings>,
+    pub(crate) springs: Res<'w, SpringPresets>,
+    pub(crate) clock: Res<'w, AnimationClock>,
+    pub(crate) groups: ResMut<'w, AnimationGroups>,
+    pub(crate) ev_play_animation: EventWriter<'w, 's, PlayAnimation>,
+}
+
+impl Motion<'_, '_> {
+    /// Whether things are placed where their motion would end, with reduced motion or the
+    /// instant animation speed.
+    pub(crate) fn snaps(&self) -> bool {
+        self.settings.reduced_motion || self.clock.is_instant()
+    }
+}
+
+// Not a system! Plays each animation, in order, with a delay spread by `stagger`.
+pub fn stagger(
+    ev_play: &mut EventWriter<PlayAnimation>,
+    animations: &[(Entity, AnimationSpec)],
+    stagger: Stagger,
+    group: Option<AnimationGroup>,
+) {
+    for (i, (entity, spec)) in animations.iter().enumerate() {
+        ev_play.send(PlayAnimation {
+            entity: *entity,
+            spec: *spec,
+            delay: stagger.delay(i, animations.len()),
+            group,
+        });
+    }
+}
+
+struct Track {
+    property: AnimatedProperty,
+    from: Vec4,
+    to: Vec4,
+    channels: [SpringSimulation; 4],
+    starts_at: f32,
+}
+
+impl Track {
+    fn new(spec: &AnimationSpec, starts_at: f32) -> Self {
+        let (from, to) = spec.target.channels();
+        let channel = |from: f32, to: f32| SpringSimulation::new(spec.spring, from, to, 0., 0.5);
+        Self {
+            property: spec.target.property(),
+            from,
+            to,
+            channels: [
+                channel(from.x, to.x),
+                channel(from.y, to.y),
+                channel(from.z, to.z),
+                channel(from.w, to.w),
+            ],
+            starts_at,
+        }
+    }
+
+    fn retarget(&self, spec: &AnimationSpec, now: f32) -> Self {
+        let t = now - self.starts_at;
+        let (_, to) = spec.target.channels();
+        let [x, y, z, w] = &self.channels;
+        Self {
+            property: self.property,
+            from: self.value(now).0,
+            to,
+            channels: [
+                x.retarget(t, spec.spring, to.x),
+                y.retarget(t, spec.spring, to.y),
+                z.retarget(t, spec.spring, to.z),
+                w.retarget(t, spec.spring, to.w),
+            ],
+            starts_at: now,
+        }
+    }
+
+    // Delayed tracks hold their first frame, so that a staggered group doesn't pop in.
+    fn value(&self, now: f32) -> (Vec4, bool) {
+        let t = now - self.starts_at;
+        if t < 0. {
+            (self.from, false)
+        } else if self.channels.iter().all(|channel| channel.is_done(t)) {
+            (self.to, true)
+        } else {
+            let [x, y, z, w] = &self.channels;
+            (Vec4::new(x.x(t), y.x(t), z.x(t), w.x(t)), false)
+        }
+    }
+}
+
+/// Runs the animation tracks of an entity, each with its own simulation. Inserted on demand by
+/// `PlayAnimation`.
+#[derive(Component, Default)]
+pub struct Animator {
+    tracks: Vec<Track>,
+    // Local clock of the tracks, advanced by the `AnimationClock` delta.
+    now: f32,
+}
+
+impl Animator {
+    fn play(&mut self, spec: &AnimationSpec, delay: f32) {
+        let now = self.now;
+        let property = spec.target.property();
+        let running = self
+            .tracks
+            .iter()
+            .position(|t| t.property == property)
+            .map(|i| self.tracks.swap_remove(i));
+        let track = match running {
+            Some(running) if running.starts_at <= now => running.retarget(spec, now),
+            _ => Track::new(spec, now + delay),
+        };
+        self.tracks.push(track);
+    }
+
+    pub fn is_animating(&self, property: AnimatedProperty) -> bool {
+        self.tracks.iter().any(|t| t.property == property)
+    }
+}
+
+pub fn play_animations(
+    mut ev_play: EventReader<PlayAnimation>,
+    mut q: Query<&mut Animator>,
+    mut groups: ResMut<AnimationGroups>,
+    mut commands: Commands,
+) {
+    // Entities spawned this frame don't have their animator yet: gather all their tracks first.
+    let mut new_animators = HashMap::<Entity, Animator>::default();
+    for PlayAnimation {
+        entity,
+        spec,
+        delay,
+        group,
+    } in ev_play.iter()
+    {
+        if let Some(group) = group {
+            groups
+                .pending
+                .entry(*group)
+                .or_default()
+                .insert((*entity, spec.target.property()));
+        }
+        match q.get_mut(*entity) {
+            Ok(mut animator) => animator.play(spec, *delay),
+            Err(_) => new_animators.entry(*entity).or_default().play(spec, *delay),
+        }
+    }
+    for (entity, animator) in new_animators {
+        commands.entity(entity).insert(animator);
+    }
+}
+
+pub fn animate(
+    mut q: Query<(Entity, &mut Animator, &mut Transform, Option<&mut Sprite>)>,
+    mut q_atlas_sprites: Query<&mut TextureAtlasSprite>,
+    clock: Res<AnimationClock>,
+    mut ev_finished: EventWriter<AnimationFinished>,
+    mut ev_done: EventWriter<AnimatorDone>,
+) {
+    for (entity, mut animator, mut transform, mut sprite) in q.iter_mut() {
+        if animator.tracks.is_empty() {
+            continue;
+        }
+
+        animator.now += clock.delta();
+        let now = animator.now;
+        animator.tracks.retain(|track| {
+            let (value, is_done) = if clock.is_instant() {
+                (track.to, true)
+            } else {
+                track.value(now)
+            };
+            match track.property {
+                AnimatedProperty::Translation => transform.translation = value.truncate(),
+                AnimatedProperty::Scale => transform.scale = value.truncate(),
+                AnimatedProperty::Tint => {
+                    if let Some(sprite) = sprite.as_mut() {
+                        sprite.color = Color::rgba(value.x, value.y, value.z, value.w);
+                    }
+                    if let Ok(mut sprite) = q_atlas_sprites.get_mut(entity) {
+                        sprite.color = Color::rgba(value.x, value.y, value.z, value.w);
+                    }
+                }
+            }
+            if is_done {
+                ev_finished.send(AnimationFinished {
+                    entity,
+                    property: track.property,
+                });
+            }
+            !is_done
+        });
+
+        if animator.tracks.is_empty() {
+            ev_done.send(AnimatorDone(entity));
+        }
+    }
+}
+
+/// A `Transform` field driven by a `SimulationAnimator`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SimulatedField {
+    TranslationX,
+    TranslationY,
+    /// Uniform scale on all axes.
+    Scale,
+    /// Rotation around z, in radians.
+    Rotation,
+}
+
+pub struct SimulationChannel {
+    pub field: SimulatedField,
+    pub simulation: Box<dyn Simulation + Send + Sync>,
+}
+
+/// Drives transform fields straight from any physics simulation, for motion that isn't a spring
+/// towards a target, like a fling. Unlike `Animator` tracks these can't be retargeted. Removed
+/// once every simulation is done.
+///
+/// Something else placing the entity, like an `Animator` track on the same field, should remove
+/// this first or they will fight over the transform.
+#[derive(Component)]
+pub struct SimulationAnimator {
+    channels: Vec<SimulationChannel>,
+    started_at: f32,
+}
+
+impl SimulationAnimator {
+    /// `starts_at` is the `AnimationClock` time the simulations start at. When it's later than
+    /// now, the fields are held where the simulations start until then.
+    pub fn new(channels: Vec<SimulationChannel>, starts_at: f32) -> Self {
+        Self {
+            channels,
+            started_at: starts_at,
+        }
+    }
+}
+
+/// Sent when a `SimulationAnimator` has run to the end and is removed. One removed early by
+/// something else doesn't count.
+pub struct SimulationDone(pub Entity);
+
+pub fn animate_simulations(
+    mut q: Query<(Entity, &SimulationAnimator, &mut Transform)>,
+    clock: Res<AnimationClock>,
+    mut ev_done: EventWriter<SimulationDone>,
+    mut commands: Commands,
+) {
+    for (entity, animator, mut transform) in q.iter_mut() {
+        let t = (clock.now() - animator.started_at).max(0.);
+        for SimulationChannel { field, simulation } in animator.channels.iter() {
+            let x = simulation.x(t);
+            match field {
+                SimulatedField::TranslationX => transform.translation.x = x,
+                SimulatedField::TranslationY => transform.translation.y = x,
+                SimulatedField::Scale => transform.scale = Vec3::splat(x),
+                SimulatedField::Rotation => transform.rotation = Quat::from_rotation_z(x),
+            }
+        }
+        if animator
+            .channels
+            .iter()
+            .all(|channel| channel.simulation.is_done(t))
+        {
+            commands.entity(entity).remove::<SimulationAnimator>();
+            ev_done.send(SimulationDone(entity));
+        }
+    }
+}
+
+pub fn join_animation_groups(
+    mut ev_finished: EventReader<AnimationFinished>,
+    q: Query<(), With<Animator>>,
+    mut groups: ResMut<AnimationGroups>,
+    mut ev_group_done: EventWriter<AnimationGroupDone>,
+) {
+    let finished = ev_finished
+        .iter()
+        .map(|finished| (finished.entity, finished.property))
+        .collect::<Vec<_>>();
+    if finished.is_empty() && groups.pending.is_empty() {
+        return;
+    }
+
+    groups.pending.retain(|group, members| {
+        // Despawned entities will never finish their animations.
+        members.retain(|member| !finished.contains(member) && q.get(member.0).is_ok());
+        if members.is_empty() {
+            ev_group_done.send(AnimationGroupDone(*group));
+        }
+        !members.is_empty()
+    });
+}
@@ -0,0 +1,638 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use bevy::{
+    asset::{HandleId, LoadState},
+    ecs::system::SystemParam,
+    prelude::*,
+    render::{
+        render_resource::{
+            Extent3d, FilterMode, SamplerDescriptor, TextureDimension, TextureFormat,
+        },
+        texture::TextureFormatPixelInfo,
+    },
+};
+
+use crate::{
+    colorblind::{daltonize, ColorblindFilter},
+    controls::{InputAction, InputMap},
+    despawn_ui,
+    intro::StartIntro,
+    match_plugin::{TILES_LEFT, TILES_RIGHT},
+    theme::Theme,
+    toast::ShowToast,
+    GameState,
+};
+
+const FONT: &str = "ReadexPro-Regular.ttf";
+// Card textures other than the tiles, which share the tiles' @1x/@2x variants.
+const CARD_TEXTURES: [&str; 7] = [
+    "card_bg.png",
+    "cycle_arrow.png",
+    "swap_arrow.png",
+    "tile_any_l.png",
+    "tile_any_r.png",
+    "tile_empty_l.png",
+    "tile_empty_r.png",
+];
+const OTHER_ASSETS: [&str; 2] = [FONT, "cursor.png"];
+// Room for every tile and card texture once shrunk, tile packs included.
+const ATLAS_MAX_SIZE: f32 = 4096.;
+// Textures are shrunk to about this many pixels across before packing. Tiles and cards are
+// drawn at most 270 units wide, so this still leaves detail to spare on a @2x screen.
+const ATLAS_TEXTURE_MAX_SIZE: u32 = 640;
+const LOADING_BAR_WIDTH: f32 = 600.;
+const LOADING_BAR_HEIGHT: f32 = 16.;
+const LOADING_BAR_Y_POS: f32 = -40.;
+
+/// Which variant of the tile and card textures is loaded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Resolution {
+    X1,
+    X2,
+}
+
+impl Resolution {
+    fn for_scale_factor(scale_factor: f64) -> Self {
+        if scale_factor >= 1.5 {
+            Resolution::X2
+        } else {
+            Resolution::X1
+        }
+    }
+
+    // `tile_a_l.png` is the @1x variant, `tile_a_l@2x.png` the @2x one.
+    fn variant(self, path: &str) -> String {
+        match (self, path.rsplit_once('.')) {
+            (Resolution::X1, _) => path.to_string(),
+            (Resolution::X2, Some((stem, extension))) => format!("{}@2x.{}", stem, extension),
+            (Resolution::X2, None) => format!("{}@2x", path),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextureFiltering {
+    Smooth,
+    // Crisp texels for pixel-art textures.
+    Nearest,
+}
+
+impl TextureFiltering {
+    fn filter_mode(self) -> FilterMode {
+        match self {
+            TextureFiltering::Smooth => FilterMode::Linear,
+            TextureFiltering::Nearest => FilterMode::Nearest,
+        }
+    }
+}
+
+/// Every asset the game uses, loaded up front behind a loading screen. The intro, and then the
+/// first match, only start once all of them have loaded; if any fails, an error screen lists
+/// them instead.
+///
+/// Tile and card textures are loaded in the variant matching the window's scale factor, falling
+/// back to @1x when there is no @2x file, and should be looked up here with `texture`. Once
+/// loaded they are packed into one atlas with the tile packs' textures, so the board and the
+/// hand draw from a single texture: tiles and cards are spawned through `sheet`.
+pub struct GameAssets {
+    pub filtering: TextureFiltering,
+    textures: HashMap<&'static str, (Resolution, Handle<Image>)>,
+    others: Vec<(&'static str, HandleUntyped)>,
+    // A white patch for the plain sprites drawn on cards, so they come from the atlas too.
+    blank: Handle<Image>,
+    atlas: Handle<TextureAtlas>,
+    atlas_indices: HashMap<HandleId, usize>,
+    // The atlas texture as packed, recoloured from for each palette, and the palette it is in.
+    packed_atlas: Option<Image>,
+    palette: Option<ColorblindFilter>,
+    // How much has loaded, from 0 to 1, shown on the loading screen.
+    progress: f32,
+    done: bool,
+}
+
+impl GameAssets {
+    fn handle_ids(&self) -> impl Iterator<Item = HandleId> + '_ {
+        self.textures
+            .values()
+            .map(|(_, handle)| handle.id)
+            .chain(self.others.iter().map(|(_, handle)| handle.id))
+    }
+
+    /// The loaded variant of a tile or card texture, by its @1x path.
+    pub fn texture(&self, path: &str) -> Handle<Image> {
+        self.textures[path].1.clone()
+    }
+
+    /// Where `texture` was packed in the atlas. Textures that weren't, like the default one of
+    /// sprites without a texture, are drawn from the white patch.
+    pub fn atlas_index(&self, texture: &Handle<Image>) -> usize {
+        self.atlas_indices
+            .get(&texture.id)
+            .or_else(|| self.atlas_indices.get(&self.blank.id))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// `bundle` drawn from the atlas instead of its own texture.
+    pub fn sheet(&self, bundle: SpriteBundle) -> SpriteSheetBundle {
+        SpriteSheetBundle {
+            sprite: TextureAtlasSprite {
+                color: bundle.sprite.color,
+                index: self.atlas_index(&bundle.texture),
+                flip_x: bundle.sprite.flip_x,
+                flip_y: bundle.sprite.flip_y,
+                custom_size: bundle.sprite.custom_size,
+            },
+            texture_atlas: self.atlas.clone(),
+            transform: bundle.transform,
+            global_transform: bundle.global_transform,
+            visibility: bundle.visibility,
+        }
+    }
+}
+
+pub(crate) struct AssetsPlugin;
+
+impl Plugin for AssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_assets)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Loading).with_system(spawn_loading_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Loading)
+                    .with_system(check_assets.before("start_intro"))
+                    .with_system(update_loading_bar),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loading).with_system(despawn_ui::<LoadingUi>),
+            )
+            .add_system(toggle_texture_filtering.after("leaderboard_input"))
+            .add_system(apply_texture_filtering)
+            .add_system(apply_palette);
+    }
+}
+
+pub fn load_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    windows: Res<Windows>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    // Windows can be created after startup; without one to measure, play safe with @1x.
+    let resolution = windows.get_primary().map_or(Resolution::X1, |window| {
+        Resolution::for_scale_factor(window.scale_factor())
+    });
+    let textures = TILES_LEFT
+        .iter()
+        .chain(TILES_RIGHT.iter())
+        .chain(CARD_TEXTURES.iter())
+        .map(|path| {
+            (
+                *path,
+                (
+                    resolution,
+                    asset_server.load(resolution.variant(path).as_str()),
+                ),
+            )
+        })
+        .collect();
+    let others = OTHER_ASSETS
+        .iter()
+        .map(|path| (*path, asset_server.load_untyped(*path)))
+        .collect();
+    commands.insert_resource(GameAssets {
+        filtering: TextureFiltering::Smooth,
+        textures,
+        others,
+        blank: images.add(Image::new_fill(
+            Extent3d {
+                width: 4,
+                height: 4,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[255; 4],
+            TextureFormat::Rgba8UnormSrgb,
+        )),
+        atlas: Handle::default(),
+        atlas_indices: HashMap::new(),
+        packed_atlas: None,
+        palette: None,
+        progress: 0.,
+        done: false,
+    });
+}
+
+// A copy of `image` shrunk by a whole factor until it is at most `max_size` pixels across.
+// Each pixel averages the ones it replaces, weighted by their alpha so transparent pixels don't
+// darken the edges. Only 8-bit RGBA, what the PNG loader produces, is shrunk.
+fn shrink(image: &Image, max_size: u32) -> Image {
+    let size = image.texture_descriptor.size;
+    let factor = ((size.width.max(size.height) + max_size - 1) / max_size) as usize;
+    let is_rgba8 = matches!(
+        image.texture_descriptor.format,
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm
+    );
+    let (width, height) = (size.width as usize, size.height as usize);
+    if factor <= 1 || !is_rgba8 || image.data.len() < width * height * 4 {
+        return image.clone();
+    }
+    let (new_width, new_height) = (width / factor, height / factor);
+    let mut data = Vec::with_capacity(new_width * new_height * 4);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            let mut sum = [0u32; 4];
+            for from_y in y * factor..(y + 1) * factor {
+                for from_x in x * factor..(x + 1) * factor {
+                    let i = (from_y * width + from_x) * 4;
+                    let alpha = image.data[i + 3] as u32;
+                    for (sum, channel) in sum.iter_mut().zip(&image.data[i..i + 3]) {
+                        *sum += *channel as u32 * alpha;
+                    }
+                    sum[3] += alpha;
+                }
+            }
+            let alpha = sum[3].max(1);
+            data.extend_from_slice(&[
+                (sum[0] / alpha) as u8,
+                (sum[1] / alpha) as u8,
+                (sum[2] / alpha) as u8,
+                (sum[3] / (factor * factor) as u32) as u8,
+            ]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: new_width as u32,
+            height: new_height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        image.texture_descriptor.format,
+    )
+}
+
+// A copy of `image` with its edge pixels repeated one pixel further out. Filtering at the edge
+// of its patch of the atlas then doesn't blend in the texture next to it.
+fn extrude(image: &Image) -> Option<Image> {
+    let size = image.texture_descriptor.size;
+    let (width, height) = (size.width as usize, size.height as usize);
+    let pixel_size = image.texture_descriptor.format.pixel_size();
+    if width == 0 || height == 0 || image.data.len() < width * height * pixel_size {
+        return None;
+    }
+    let mut data = Vec::with_capacity((width + 2) * (height + 2) * pixel_size);
+    for y in 0..height + 2 {
+        let from_y = y.saturating_sub(1).min(height - 1);
+        for x in 0..width + 2 {
+            let from_x = x.saturating_sub(1).min(width - 1);
+            let i = (from_y * width + from_x) * pixel_size;
+            data.extend_from_slice(&image.data[i..i + pixel_size]);
+        }
+    }
+    Some(Image::new(
+        Extent3d {
+            width: size.width + 2,
+            height: size.height + 2,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        image.texture_descriptor.format,
+    ))
+}
+
+// Not a system! Packs the tile and card textures, and the tile packs', into one atlas. If they
+// don't fit, the atlas is left empty and the log says so.
+fn build_atlas(
+    game_assets: &mut GameAssets,
+    theme: &Theme,
+    images: &mut Assets<Image>,
+    atlases: &mut Assets<TextureAtlas>,
+) {
+    let mut builder = TextureAtlasBuilder::default().max_size(Vec2::splat(ATLAS_MAX_SIZE));
+    let handles = game_assets
+        .textures
+        .values()
+        .map(|(_, handle)| handle)
+        .chain(theme.tile_packs.iter().flat_map(|pack| pack.textures()))
+        .chain(std::iter::once(&game_assets.blank));
+    for handle in handles {
+        let image = images
+            .get(handle)
+            .map(|image| shrink(image, ATLAS_TEXTURE_MAX_SIZE))
+            .as_ref()
+            .and_then(extrude);
+        if let Some(image) = image {
+            builder.add_texture(handle.clone(), &image);
+        }
+    }
+    let mut atlas = match builder.finish(images) {
+        Ok(atlas) => atlas,
+        Err(err) => {
+            error!("Could not pack the textures into an atlas: {:?}", err);
+            return;
+        }
+    };
+    // Only the inside of each patch is drawn, the extruded edge is there for filtering.
+    for rect in atlas.textures.iter_mut() {
+        rect.min += Vec2::ONE;
+        rect.max -= Vec2::ONE;
+    }
+    game_assets.atlas_indices = atlas
+        .texture_handles
+        .iter()
+        .flatten()
+        .map(|(handle, index)| (handle.id, *index))
+        .collect();
+    game_assets.packed_atlas = images.get(&atlas.texture).cloned();
+    game_assets.palette = None;
+    game_assets.atlas = atlases.add(atlas);
+}
+
+/// What building the atlas takes besides the game assets.
+#[derive(SystemParam)]
+pub(crate) struct AtlasSources<'w, 's> {
+    theme: Res<'w, Theme>,
+    images: ResMut<'w, Assets<Image>>,
+    atlases: ResMut<'w, Assets<TextureAtlas>>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl AtlasSources<'_, '_> {
+    fn build(&mut self, game_assets: &mut GameAssets) {
+        build_atlas(
+            game_assets,
+            &self.theme,
+            &mut self.images,
+            &mut self.atlases,
+        );
+    }
+}
+
+pub(crate) fn check_assets(
+    mut game_assets: ResMut<GameAssets>,
+    asset_server: Res<AssetServer>,
+    mut atlas_sources: AtlasSources,
+    mut state: ResMut<State<GameState>>,
+    mut commands: Commands,
+    mut ev_start_intro: EventWriter<StartIntro>,
+) {
+    if game_assets.done {
+        return;
+    }
+    // Most frames nothing has failed and there is only the progress to update.
+    match asset_server.get_group_load_state(game_assets.handle_ids()) {
+        LoadState::Loaded => {
+            game_assets.done = true;
+            game_assets.progress = 1.;
+            atlas_sources.build(&mut game_assets);
+            start_intro(&mut state, &mut ev_start_intro);
+            return;
+        }
+        LoadState::Failed => (),
+        _ => {
+            let (loaded, total) = game_assets
+                .handle_ids()
+                .fold((0, 0), |(loaded, total), id| {
+                    match asset_server.get_load_state(id) {
+                        LoadState::Loaded => (loaded + 1, total + 1),
+                        _ => (loaded, total + 1),
+                    }
+                });
+            let progress = loaded as f32 / total.max(1) as f32;
+            if game_assets.progress != progress {
+                game_assets.progress = progress;
+            }
+            return;
+        }
+    }
+
+    let game_assets = &mut *game_assets;
+    let mut loading = false;
+    let mut failed = Vec::new();
+    for (path, (resolution, handle)) in game_assets.textures.iter_mut() {
+        match asset_server.get_load_state(handle.id) {
+            LoadState::Loaded => (),
+            LoadState::Failed if *resolution == Resolution::X2 => {
+                *resolution = Resolution::X1;
+                *handle = asset_server.load(*path);
+                loading = true;
+            }
+            LoadState::Failed => failed.push(*path),
+            _ => loading = true,
+        }
+    }
+    for (path, handle) in game_assets.others.iter() {
+        match asset_server.get_load_state(handle.id) {
+            LoadState::Loaded => (),
+            LoadState::Failed => failed.push(*path),
+            _ => loading = true,
+        }
+    }
+    // Check again next frame.
+    if loading {
+        return;
+    }
+    game_assets.done = true;
+
+    if failed.is_empty() {
+        atlas_sources.build(game_assets);
+        start_intro(&mut state, &mut ev_start_intro);
+        return;
+    }
+
+    failed.sort_unstable();
+    for path in failed.iter() {
+        error!("Failed to load asset: {}", path);
+    }
+    commands.spawn_bundle(SpriteBundle {
+        transform: Transform {
+            translation: Vec3::new(0., 0., 90.),
+            ..Default::default()
+        },
+        sprite: Sprite {
+            custom_size: Some(Vec2::new(4000., 4000.)),
+            color: Color::rgb(0.3, 0.08, 0.08),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    // Without the font there is nothing to write the list with, so only the log has it.
+    if failed.contains(&FONT) {
+        return;
+    }
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(
+            format!(
+                "Some game files could not be loaded:\n\n{}\n\nCheck that the assets folder is next to the game.",
+                failed.join("\n")
+            ),
+            TextStyle {
+                font: asset_server.load(FONT),
+                font_size: 26.,
+                color: Color::WHITE,
+            },
+            TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        transform: Transform {
+            translation: Vec3::new(0., 0., 91.),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+}
+
+// Not a system!
+fn start_intro(state: &mut State<GameState>, ev_start_intro: &mut EventWriter<StartIntro>) {
+    if let Err(err) = state.set(GameState::Intro) {
+        warn!("Could not start the intro: {:?}", err);
+    }
+    ev_start_intro.send(StartIntro);
+}
+
+#[derive(Component)]
+pub struct LoadingUi;
+
+#[derive(Component)]
+pub struct LoadingBarFill;
+
+// A bar filling up as the assets load. The font may not have loaded yet, so it has no text.
+pub(crate) fn spawn_loading_screen(mut commands: Commands, theme: Res<Theme>) {
+    let bar_color = theme.text_color();
+    let mut track_color = bar_color;
+    track_color.set_a(0.2);
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform::from_xyz(0., LOADING_BAR_Y_POS, 80.),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(LOADING_BAR_WIDTH, LOADING_BAR_HEIGHT)),
+                color: track_color,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(LoadingUi);
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform::from_xyz(-LOADING_BAR_WIDTH / 2., LOADING_BAR_Y_POS, 81.),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(0., LOADING_BAR_HEIGHT)),
+                color: bar_color,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(LoadingUi)
+        .insert(LoadingBarFill);
+}
+
+pub(crate) fn update_loading_bar(
+    game_assets: Res<GameAssets>,
+    mut q: Query<(&mut Sprite, &mut Transform), With<LoadingBarFill>>,
+) {
+    if !game_assets.is_changed() {
+        return;
+    }
+    // Grows from the left end of the track.
+    let width = LOADING_BAR_WIDTH * game_assets.progress;
+    for (mut sprite, mut transform) in q.iter_mut() {
+        sprite.custom_size = Some(Vec2::new(width, LOADING_BAR_HEIGHT));
+        transform.translation.x = (width - LOADING_BAR_WIDTH) / 2.;
+    }
+}
+
+// Keeps the samplers of the atlas and of the tile and card textures, tile packs included, in
+// line with `GameAssets::filtering`, both when the option changes and when a texture finishes
+// loading.
+fn toggle_texture_filtering(
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut game_assets: ResMut<GameAssets>,
+    mut ev_toast: EventWriter<ShowToast>,
+) {
+    if input_map.just_pressed(InputAction::TogglePixelArtFiltering, &keyboard_input) {
+        game_assets.filtering = match game_assets.filtering {
+            TextureFiltering::Smooth => TextureFiltering::Nearest,
+            TextureFiltering::Nearest => TextureFiltering::Smooth,
+        };
+        ev_toast.send(ShowToast(
+            match game_assets.filtering {
+                TextureFiltering::Nearest => "Pixel-art filtering on",
+                TextureFiltering::Smooth => "Pixel-art filtering off",
+            }
+            .to_string(),
+        ));
+    }
+}
+
+pub fn apply_texture_filtering(
+    game_assets: Res<GameAssets>,
+    theme: Res<Theme>,
+    atlases: Res<Assets<TextureAtlas>>,
+    mut ev_image: EventReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let created = ev_image
+        .iter()
+        .any(|ev| matches!(ev, AssetEvent::Created { .. }));
+    if !created && !game_assets.is_changed() {
+        return;
+    }
+
+    let filter_mode = game_assets.filtering.filter_mode();
+    let pack_textures = theme.tile_packs.iter().flat_map(|pack| pack.textures());
+    let atlas_texture = atlases.get(&game_assets.atlas).map(|atlas| &atlas.texture);
+    for handle in game_assets
+        .textures
+        .values()
+        .map(|(_, handle)| handle)
+        .chain(pack_textures)
+        .chain(atlas_texture)
+    {
+        let needs_update = match images.get(handle) {
+            Some(image) => image.sampler_descriptor.mag_filter != filter_mode,
+            None => false,
+        };
+        if needs_update {
+            if let Some(image) = images.get_mut(handle) {
+                image.sampler_descriptor = SamplerDescriptor {
+                    mag_filter: filter_mode,
+                    min_filter: filter_mode,
+                    ..Default::default()
+                };
+            }
+        }
+    }
+}
+
+// Recolours the atlas for the palette in use, from the copy of it kept as packed. Only the
+// texture's pixels change, so sprites keep their patches. Until the atlas is built this waits.
+pub fn apply_palette(
+    mut game_assets: ResMut<GameAssets>,
+    theme: Res<Theme>,
+    atlases: Res<Assets<TextureAtlas>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if game_assets.palette == theme.palette {
+        return;
+    }
+    let (texture, packed) = match (atlases.get(&game_assets.atlas), &game_assets.packed_atlas) {
+        (Some(atlas), Some(packed)) => (&atlas.texture, packed),
+        _ => return,
+    };
+    let recoloured = match theme.palette {
+        Some(filter) => daltonize(packed, filter),
+        None => Some(packed.clone()),
+    };
+    if let (Some(image), Some(recoloured)) = (images.get_mut(texture), recoloured) {
+        image.data = recoloured.data;
+    }
+    game_assets.palette = theme.palette;
+}
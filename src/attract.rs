@@ -0,0 +1,199 @@
+//! Attract mode: after a while on the main menu with nothing pressed, a demo match deals itself
+//! and plays the solver's moves at a relaxed pace, with every animation. Pressing anything goes
+//! back to the main menu, and so does the end of the demo.
+
+use bevy::{ecs::system::SystemParam, input::InputSystem, prelude::*};
+
+use crate::{
+    animation::AnimationClock,
+    controls::AnyPress,
+    core::{first_play, TileNature, TileSide},
+    credits::Credits,
+    match_plugin::{
+        tile_columns, CardAction, CardClicked, InputMode, MatchEnding, MatchState, Slot, Tile,
+        TileData, UpdateCardsStyle, Used,
+    },
+    menu::MenuScreens,
+    DespawnAll, GameMode, GameState,
+};
+
+// Seconds on the main menu without a press before the demo starts.
+const IDLE_TIME: f64 = 30.;
+// Leaves the deal time to land before the first move.
+const FIRST_STEP_DELAY: f32 = 1.5;
+// Each card is hovered for a step, then played on the next one.
+const STEP_TIME: f32 = 0.9;
+
+#[derive(Default)]
+pub(crate) struct Attract {
+    // When the main menu last saw the player, in seconds since startup.
+    idle_since: f64,
+    // The `AnimationClock` time of the demo's next move.
+    next_step_at: f32,
+}
+
+pub(crate) struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Attract>()
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(reset_idle_time))
+            .add_system_set(
+                SystemSet::on_update(GameState::Menu)
+                    .with_system(start_demo_when_idle.after("main_menu_input")),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame).with_system(
+                    play_demo
+                        .after("pick_card_with_mouse")
+                        .after("pick_tiles")
+                        .before("handle_input"),
+                ),
+            )
+            // Before anything else sees the press that ends the demo.
+            .add_system_to_stage(CoreStage::PreUpdate, leave_demo.after(InputSystem));
+    }
+}
+
+fn reset_idle_time(time: Res<Time>, mut attract: ResMut<Attract>) {
+    attract.idle_since = time.seconds_since_startup();
+}
+
+/// The mode and state a demo runs in, and the match it plays.
+#[derive(SystemParam)]
+struct DemoMatch<'w, 's> {
+    mode: ResMut<'w, GameMode>,
+    state: ResMut<'w, State<GameState>>,
+    match_state: ResMut<'w, MatchState>,
+    q_ending: Query<'w, 's, (), With<MatchEnding>>,
+    ev_despawn_all: EventWriter<'w, 's, DespawnAll>,
+}
+
+impl DemoMatch<'_, '_> {
+    fn is_running(&self) -> bool {
+        *self.mode == GameMode::Demo && *self.state.current() == GameState::InGame
+    }
+
+    // Once the last card is played the match is left to end on its own.
+    fn is_over(&self) -> bool {
+        !self.q_ending.is_empty()
+    }
+
+    fn start(&mut self) {
+        *self.mode = GameMode::Demo;
+        if let Err(err) = self.state.set(GameState::InGame) {
+            warn!("Could not start the demo: {:?}", err);
+        }
+    }
+
+    // Drops the match and goes back to the main menu.
+    fn leave(&mut self) {
+        self.ev_despawn_all.send(DespawnAll);
+        *self.match_state = MatchState::Ready;
+        if let Err(err) = self.state.set(GameState::Menu) {
+            warn!("Could not end the demo: {:?}", err);
+        }
+    }
+}
+
+// The screens opened from the main menu keep it from idling, even when nothing is pressed.
+fn start_demo_when_idle(
+    time: Res<Time>,
+    press: AnyPress,
+    screens: MenuScreens,
+    credits: Res<Credits>,
+    clock: Res<AnimationClock>,
+    mut attract: ResMut<Attract>,
+    mut demo: DemoMatch,
+) {
+    let now = time.seconds_since_startup();
+    if press.any_just_pressed() || screens.any_open() || credits.open {
+        attract.idle_since = now;
+        return;
+    }
+    if now - attract.idle_since < IDLE_TIME {
+        return;
+    }
+    attract.idle_since = now;
+    attract.next_step_at = clock.now() + FIRST_STEP_DELAY;
+    demo.start();
+}
+
+// Plays the first card of a shortest way to win, the way the player would: the card is hovered,
+// then clicked, and a wildcard is given the swap to play it as. A hand with no way to win found
+// ends the demo.
+fn play_demo(
+    clock: Res<AnimationClock>,
+    mut attract: ResMut<Attract>,
+    mut demo: DemoMatch,
+    q_tiles: Query<(Entity, &TileSide, &Slot, &TileNature), With<Tile>>,
+    q_cards: Query<(&CardAction, Option<&Used>)>,
+    mut ev_card_clicked: EventWriter<CardClicked>,
+    mut ev_update_cards_style: EventWriter<UpdateCardsStyle>,
+) {
+    if !demo.is_running() || demo.is_over() || clock.now() < attract.next_step_at {
+        return;
+    }
+    let playing = match demo.match_state.as_ref() {
+        MatchState::Playing(playing) => playing,
+        _ => return,
+    };
+    attract.next_step_at = clock.now() + STEP_TIME;
+
+    let (left_col, right_col) = tile_columns(
+        q_tiles
+            .iter()
+            .map(|(id, side, slot, nature)| (id, *side, *slot, *nature)),
+    );
+    let natures = |col: &[TileData]| col.iter().map(|tile| tile.nature).collect::<Vec<_>>();
+    let hand = playing
+        .hand_order
+        .iter()
+        .filter_map(|card| {
+            q_cards
+                .get(*card)
+                .ok()
+                .map(|(action, used)| (*card, action, used))
+        })
+        .collect::<Vec<_>>();
+    let cards = hand
+        .iter()
+        .map(|(_, CardAction(action), _)| *action)
+        .collect::<Vec<_>>();
+    let used = hand
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, _, used))| used.is_some())
+        .fold(0u32, |used, (i, _)| used | (1 << i));
+
+    let (card, action) = match first_play(&natures(&left_col), &natures(&right_col), &cards, used) {
+        Some((i, action)) => (hand[i].0, action),
+        None => {
+            demo.leave();
+            return;
+        }
+    };
+    let playing = match demo.match_state.as_mut() {
+        MatchState::Playing(playing) => playing,
+        _ => return,
+    };
+    if playing.hovered_card != Some(card) {
+        playing.hovered_card = Some(card);
+        playing.input_mode = InputMode::Hand;
+        ev_update_cards_style.send(UpdateCardsStyle);
+        return;
+    }
+    if playing.picking_tiles() {
+        playing.input_mode = InputMode::Picked(action);
+    }
+    ev_card_clicked.send(CardClicked);
+}
+
+// Any press ends the demo, and is used up so it doesn't also play or pick anything.
+fn leave_demo(mut press: AnyPress, mut demo: DemoMatch) {
+    if !demo.is_running() || !press.any_just_pressed() {
+        return;
+    }
+    press.clear();
+    demo.leave();
+}
@@ -0,0 +1,324 @@
+use std::{marker::PhantomData, time::Duration};
+
+use bevy::{
+    audio::{AudioOutput, Decodable},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypeUuid,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::AnimationGroupDone,
+    controls::{InputAction, InputMap},
+    match_plugin::{CardPlayed, MatchEnding, MatchState, TileMovement},
+    settings::{PendingSave, Settings},
+    toast::ShowToast,
+};
+
+const SAMPLE_RATE: u32 = 44100;
+const VOLUME_STEP: f32 = 0.1;
+// A slow pentatonic line in A minor, one note per bar, looped under the whole game.
+const MUSIC_NOTES: [f32; 8] = [
+    220.00, 261.63, 293.66, 329.63, 392.00, 329.63, 293.66, 261.63,
+];
+const MUSIC_NOTE_STEP: f64 = 1.6;
+const MUSIC_VOLUME: f32 = 0.08;
+
+/// How a `Tone` sounds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Voice {
+    /// Quick attack and a ringing decay, for the tile notes.
+    Pluck,
+    /// A short click, for the interface.
+    Blip,
+    /// Slow to fade in and out, for the music.
+    Pad,
+}
+
+impl Voice {
+    fn duration(self) -> f32 {
+        match self {
+            Voice::Pluck => 0.6,
+            Voice::Blip => 0.08,
+            Voice::Pad => 2.4,
+        }
+    }
+
+    fn sample(self, frequency: f32, t: f32) -> f32 {
+        let phase = std::f32::consts::TAU * frequency * t;
+        match self {
+            // With a quieter octave on top to make it ring.
+            Voice::Pluck => {
+                (t / 0.005).min(1.) * (-6. * t).exp() * (phase.sin() + 0.3 * (2. * phase).sin())
+            }
+            Voice::Blip => (t / 0.002).min(1.) * (-50. * t).exp() * phase.sin(),
+            Voice::Pad => {
+                let envelope = (t / 0.6).min(1.) * (1. - t / self.duration()).max(0.);
+                envelope * (phase.sin() + 0.4 * (0.5 * phase).sin())
+            }
+        }
+    }
+}
+
+/// A short synthesized sound. Nothing is loaded from files: every sound in the game is one of
+/// these, made when it is played.
+#[derive(TypeUuid, Clone, Copy)]
+#[uuid = "06bc055d-4448-4f0a-8bc8-d99ddd443527"]
+pub struct Tone {
+    pub frequency: f32,
+    // -1 is all left, 1 all right.
+    pub pan: f32,
+    pub voice: Voice,
+    pub volume: f32,
+}
+
+impl Tone {
+    pub fn new(voice: Voice, frequency: f32, volume: f32) -> Self {
+        Self {
+            frequency,
+            pan: 0.,
+            voice,
+            volume,
+        }
+    }
+}
+
+pub struct ToneDecoder {
+    tone: Tone,
+    // Index of the next sample, counting both channels.
+    sample: u32,
+}
+
+impl Iterator for ToneDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let frame = self.sample / 2;
+        let t = frame as f32 / SAMPLE_RATE as f32;
+        if t >= self.tone.voice.duration() {
+            return None;
+        }
+        let right = self.sample % 2 == 1;
+        self.sample += 1;
+
+        // Equal power panning.
+        let angle = (self.tone.pan + 1.) * std::f32::consts::FRAC_PI_4;
+        let gain = if right { angle.sin() } else { angle.cos() };
+        Some(self.tone.voice.sample(self.tone.frequency, t) * gain * self.tone.volume)
+    }
+}
+
+impl rodio::Source for ToneDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.tone.voice.duration()))
+    }
+}
+
+impl Decodable for Tone {
+    type Decoder = ToneDecoder;
+    type DecoderItem = f32;
+
+    fn decoder(&self) -> ToneDecoder {
+        ToneDecoder {
+            tone: *self,
+            sample: 0,
+        }
+    }
+}
+
+/// Master volume, saved with the other settings. Bevy can't change the volume of a sound once
+/// it plays, so it is applied to each `Tone` as it starts.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct SoundSettings {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            volume: 0.8,
+            muted: false,
+        }
+    }
+}
+
+impl SoundSettings {
+    /// Turns the volume up by `steps` steps, or down when negative, and unmutes.
+    pub fn step_volume(&mut self, steps: f32) {
+        let volume = self.volume + VOLUME_STEP * steps;
+        // Steps are rounded so the volume doesn't show as 69%.
+        self.volume = ((volume / VOLUME_STEP).round() * VOLUME_STEP).clamp(0., 1.);
+        self.muted = false;
+    }
+
+    pub fn label(&self) -> String {
+        if self.muted {
+            "Muted".to_string()
+        } else {
+            format!("{}%", (self.volume * 100.).round())
+        }
+    }
+
+    fn gain(&self) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            self.volume
+        }
+    }
+}
+
+/// Plays tones at the volume in `SoundSettings`.
+#[derive(SystemParam)]
+pub struct Sounds<'w, 's> {
+    audio: Res<'w, Audio<Tone>>,
+    tones: ResMut<'w, Assets<Tone>>,
+    settings: Res<'w, SoundSettings>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl Sounds<'_, '_> {
+    pub fn play(&mut self, tone: Tone) {
+        let gain = self.settings.gain();
+        if gain <= 0. {
+            return;
+        }
+        // The asset goes away with the handle once it has played.
+        let handle = self.tones.add(Tone {
+            volume: tone.volume * gain,
+            ..tone
+        });
+        self.audio.play(handle);
+    }
+}
+
+// M mutes, - and = turn the volume down and up.
+fn adjust_volume(
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut sound_settings: ResMut<SoundSettings>,
+    mut ev_toast: EventWriter<ShowToast>,
+) {
+    if input_map.just_pressed(InputAction::ToggleMute, &keyboard_input) {
+        sound_settings.muted = !sound_settings.muted;
+        ev_toast.send(ShowToast(
+            if sound_settings.muted {
+                "Sound muted"
+            } else {
+                "Sound on"
+            }
+            .to_string(),
+        ));
+        return;
+    } else if input_map.just_pressed(InputAction::VolumeDown, &keyboard_input) {
+        sound_settings.step_volume(-1.);
+    } else if input_map.just_pressed(InputAction::VolumeUp, &keyboard_input) {
+        sound_settings.step_volume(1.);
+    } else {
+        return;
+    }
+    ev_toast.send(ShowToast(format!("Volume {}", sound_settings.label())));
+}
+
+fn save_sound_settings(
+    sound_settings: Res<SoundSettings>,
+    time: Res<Time>,
+    mut settings: ResMut<Settings>,
+    mut pending_save: ResMut<PendingSave>,
+) {
+    if sound_settings.is_changed() && settings.sound != *sound_settings {
+        settings.sound = *sound_settings;
+        pending_save.request(time.seconds_since_startup());
+    }
+}
+
+pub(crate) fn play_sound_effects(
+    mut sounds: Sounds,
+    match_state: Res<MatchState>,
+    tile_movement: Res<TileMovement>,
+    mut hovered_card: Local<Option<Entity>>,
+    mut ev_card_played: EventReader<CardPlayed>,
+    mut ev_group_done: EventReader<AnimationGroupDone>,
+    q_ending: Query<&MatchEnding, Added<MatchEnding>>,
+) {
+    if let MatchState::Playing(match_state) = match_state.as_ref() {
+        if match_state.hovered_card != *hovered_card {
+            // Not when the hand is dealt.
+            if hovered_card.is_some() {
+                sounds.play(Tone::new(Voice::Blip, 880., 0.15));
+            }
+            *hovered_card = match_state.hovered_card;
+        }
+    } else {
+        *hovered_card = None;
+    }
+
+    for _ in ev_card_played.iter() {
+        sounds.play(Tone::new(Voice::Blip, 440., 0.3));
+    }
+    for AnimationGroupDone(group) in ev_group_done.iter() {
+        if tile_movement.group == Some(*group) {
+            sounds.play(Tone::new(Voice::Blip, 660., 0.2));
+        }
+    }
+    // Victory is the solved column, played by `melody`.
+    for ending in q_ending.iter() {
+        if !ending.result.won {
+            sounds.play(Tone::new(Voice::Pluck, 196., 0.3));
+            sounds.play(Tone::new(Voice::Pluck, 185., 0.3));
+        }
+    }
+}
+
+// Plays the next note of the background line when it is due.
+fn play_music(time: Res<Time>, mut sounds: Sounds, mut next: Local<(f64, usize)>) {
+    let now = time.seconds_since_startup();
+    if now < next.0 {
+        return;
+    }
+    let (_, note) = *next;
+    sounds.play(Tone::new(Voice::Pad, MUSIC_NOTES[note], MUSIC_VOLUME));
+    *next = (now + MUSIC_NOTE_STEP, (note + 1) % MUSIC_NOTES.len());
+}
+
+/// Registers `Tone` as an audio source next to the `AudioSource` files Bevy plays, and plays
+/// the music and the interface sounds.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        let sound_settings = app
+            .world
+            .get_resource::<Settings>()
+            .map(|settings| settings.sound)
+            .unwrap_or_default();
+        app.add_asset::<Tone>()
+            .init_non_send_resource::<AudioOutput<Tone>>()
+            .init_resource::<Audio<Tone>>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                bevy::audio::play_queued_audio_system::<Tone>.exclusive_system(),
+            )
+            .insert_resource(sound_settings)
+            .add_system(adjust_volume.after("leaderboard_input"))
+            .add_system(save_sound_settings.before("save_settings"))
+            .add_system(play_music)
+            .add_system_to_stage("view", play_sound_effects);
+    }
+}
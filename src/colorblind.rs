@@ -0,0 +1,282 @@
+use bevy::{ecs::system::SystemParam, prelude::*, render::render_resource::TextureFormat};
+
+use crate::{
+    assets::GameAssets,
+    controls::{InputAction, InputMap},
+    core::{nature_count, TileNature, TileSide},
+    theme::Theme,
+};
+use serde::{Deserialize, Serialize};
+
+/// A kind of colour blindness, to simulate or to correct tiles for.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorblindFilter {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl ColorblindFilter {
+    pub const ALL: [ColorblindFilter; 3] = [
+        ColorblindFilter::Protanopia,
+        ColorblindFilter::Deuteranopia,
+        ColorblindFilter::Tritanopia,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorblindFilter::Protanopia => "Protanopia",
+            ColorblindFilter::Deuteranopia => "Deuteranopia",
+            ColorblindFilter::Tritanopia => "Tritanopia",
+        }
+    }
+
+    // Machado, Oliveira and Fernandes (2009) at full severity, applied to linear RGB.
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorblindFilter::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorblindFilter::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorblindFilter::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    // How the colour lost to this kind of colour blindness is spread over the channels still
+    // seen, from Fidaner, Lin and Ozguven's daltonization: red-green losses move to green and
+    // blue, blue-yellow ones to red and green.
+    fn correction(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorblindFilter::Protanopia | ColorblindFilter::Deuteranopia => {
+                [[0., 0., 0.], [0.7, 1., 0.], [0.7, 0., 1.]]
+            }
+            ColorblindFilter::Tritanopia => [[1., 0., 0.7], [0., 1., 0.7], [0., 0., 0.]],
+        }
+    }
+}
+
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = c.clamp(0., 1.);
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    };
+    (c * 255.).round() as u8
+}
+
+// Not a system! A copy of `image` as seen with `filter`, or `None` for pixel formats other than
+// the 8-bit RGBA the PNG loader produces.
+fn simulate(image: &Image, filter: ColorblindFilter) -> Option<Image> {
+    match image.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => (),
+        _ => return None,
+    }
+    let m = filter.matrix();
+    let mut simulated = image.clone();
+    for pixel in simulated.data.chunks_exact_mut(4) {
+        let rgb = [
+            srgb_to_linear(pixel[0]),
+            srgb_to_linear(pixel[1]),
+            srgb_to_linear(pixel[2]),
+        ];
+        for (channel, row) in pixel.iter_mut().zip(m.iter()) {
+            *channel = linear_to_srgb(row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]);
+        }
+    }
+    Some(simulated)
+}
+
+// Not a system! A copy of `image` with the colour lost to `filter` moved to channels that are
+// still seen, or `None` for pixel formats other than 8-bit RGBA.
+pub(crate) fn daltonize(image: &Image, filter: ColorblindFilter) -> Option<Image> {
+    let simulated = simulate(image, filter)?;
+    let correction = filter.correction();
+    let mut daltonized = image.clone();
+    for (pixel, seen) in daltonized
+        .data
+        .chunks_exact_mut(4)
+        .zip(simulated.data.chunks_exact(4))
+    {
+        let error = [
+            srgb_to_linear(pixel[0]) - srgb_to_linear(seen[0]),
+            srgb_to_linear(pixel[1]) - srgb_to_linear(seen[1]),
+            srgb_to_linear(pixel[2]) - srgb_to_linear(seen[2]),
+        ];
+        for (channel, row) in pixel.iter_mut().zip(correction.iter()) {
+            let shift = row[0] * error[0] + row[1] * error[1] + row[2] * error[2];
+            *channel = linear_to_srgb(srgb_to_linear(*channel) + shift);
+        }
+    }
+    Some(daltonized)
+}
+
+// Not a system!
+fn spawn_text(commands: &mut Commands, font: &Handle<Font>, text: &str, font_size: f32, pos: Vec2) {
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                text,
+                TextStyle {
+                    font: font.clone(),
+                    font_size,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: pos.extend(91.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ColorblindPreviewUi);
+}
+
+#[derive(Default)]
+pub struct ColorblindPreview {
+    open: bool,
+}
+
+#[derive(Component)]
+pub struct ColorblindPreviewUi;
+
+const PREVIEW_TILE_SIZE: f32 = 80.;
+const PREVIEW_TILE_GAP: f32 = 90.;
+const PREVIEW_ROW_GAP: f32 = 120.;
+
+/// The tile textures of the tile pack in use, and somewhere to put the simulated ones.
+#[derive(SystemParam)]
+pub struct PreviewTextures<'w, 's> {
+    game_assets: Res<'w, GameAssets>,
+    theme: Res<'w, Theme>,
+    images: ResMut<'w, Assets<Image>>,
+    #[system_param(ignore)]
+    _marker: std::marker::PhantomData<&'s ()>,
+}
+
+impl PreviewTextures<'_, '_> {
+    // The left tile of a nature, as seen with the filter. Without a filter, or when the texture
+    // can't be simulated, it is the texture itself.
+    fn tile(&mut self, nature: TileNature, filter: Option<ColorblindFilter>) -> Handle<Image> {
+        let texture = self
+            .theme
+            .tile_texture(TileSide::Left, nature, &self.game_assets);
+        match filter {
+            Some(filter) => match self
+                .images
+                .get(&texture)
+                .and_then(|image| simulate(image, filter))
+            {
+                Some(simulated) => self.images.add(simulated),
+                None => texture,
+            },
+            None => texture,
+        }
+    }
+}
+
+/// Shows the tiles of the tile pack in use as they are seen with each kind of colour blindness,
+/// so players can tell whether the natures are still distinguishable for them.
+pub fn colorblind_preview(
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut preview: ResMut<ColorblindPreview>,
+    q_ui: Query<Entity, With<ColorblindPreviewUi>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut textures: PreviewTextures,
+) {
+    if !input_map.just_pressed(InputAction::ColorblindPreview, &keyboard_input) {
+        return;
+    }
+    preview.open = !preview.open;
+    if !preview.open {
+        for e in q_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 90.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: Color::rgba(0., 0., 0., 0.85),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ColorblindPreviewUi);
+
+    let font = asset_server.load("ReadexPro-Regular.ttf");
+    let first_row_y = PREVIEW_ROW_GAP * ColorblindFilter::ALL.len() as f32 / 2.;
+    spawn_text(
+        &mut commands,
+        &font,
+        "Colour blindness preview",
+        30.,
+        Vec2::new(0., first_row_y + PREVIEW_ROW_GAP),
+    );
+
+    let rows_width = PREVIEW_TILE_GAP * (nature_count() - 1) as f32;
+    let label_x = -rows_width / 2. - 150.;
+    let rows = std::iter::once(None).chain(ColorblindFilter::ALL.iter().copied().map(Some));
+    for (row, filter) in rows.enumerate() {
+        let y = first_row_y - PREVIEW_ROW_GAP * row as f32;
+        spawn_text(
+            &mut commands,
+            &font,
+            filter.map_or("Normal vision", |filter| filter.label()),
+            22.,
+            Vec2::new(label_x, y),
+        );
+        for nature in 0..nature_count() {
+            let texture = textures.tile(TileNature(nature), filter);
+            commands
+                .spawn_bundle(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(
+                            -rows_width / 2. + PREVIEW_TILE_GAP * nature as f32,
+                            y,
+                            91.,
+                        ),
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(PREVIEW_TILE_SIZE, PREVIEW_TILE_SIZE)),
+                        ..Default::default()
+                    },
+                    texture,
+                    ..Default::default()
+                })
+                .insert(ColorblindPreviewUi);
+        }
+    }
+}
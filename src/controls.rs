@@ -0,0 +1,639 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::modal::Modal;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputContext {
+    Match,
+    Display,
+    Sound,
+    Menus,
+}
+
+impl InputContext {
+    pub const ALL: [InputContext; 4] = [
+        InputContext::Match,
+        InputContext::Display,
+        InputContext::Sound,
+        InputContext::Menus,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputContext::Match => "Match",
+            InputContext::Display => "Display",
+            InputContext::Sound => "Sound",
+            InputContext::Menus => "Menus",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputAction {
+    PreviousCard,
+    NextCard,
+    PlayCard,
+    PinCard,
+    InspectCard,
+    DiscardCard,
+    Undo,
+    Hint,
+    Restart,
+    AbandonMatch,
+    ToggleHighContrast,
+    ToggleMirroredLayout,
+    ToggleMinimalHud,
+    ToggleReducedMotion,
+    TogglePixelArtFiltering,
+    CycleTilePack,
+    ColorblindPreview,
+    ToggleMute,
+    VolumeDown,
+    VolumeUp,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 20] = [
+        InputAction::PreviousCard,
+        InputAction::NextCard,
+        InputAction::PlayCard,
+        InputAction::PinCard,
+        InputAction::InspectCard,
+        InputAction::DiscardCard,
+        InputAction::Undo,
+        InputAction::Hint,
+        InputAction::Restart,
+        InputAction::AbandonMatch,
+        InputAction::ToggleHighContrast,
+        InputAction::ToggleMirroredLayout,
+        InputAction::ToggleMinimalHud,
+        InputAction::ToggleReducedMotion,
+        InputAction::TogglePixelArtFiltering,
+        InputAction::CycleTilePack,
+        InputAction::ColorblindPreview,
+        InputAction::ToggleMute,
+        InputAction::VolumeDown,
+        InputAction::VolumeUp,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::PreviousCard => "Previous card",
+            InputAction::NextCard => "Next card",
+            InputAction::PlayCard => "Play card",
+            InputAction::PinCard => "Pin card",
+            InputAction::InspectCard => "Inspect card",
+            InputAction::DiscardCard => "Discard card",
+            InputAction::Undo => "Undo",
+            InputAction::Hint => "Hint",
+            InputAction::Restart => "Restart",
+            InputAction::AbandonMatch => "New match",
+            InputAction::ToggleHighContrast => "High contrast",
+            InputAction::ToggleMirroredLayout => "Mirrored layout",
+            InputAction::ToggleMinimalHud => "Minimal HUD",
+            InputAction::ToggleReducedMotion => "Reduced motion",
+            InputAction::TogglePixelArtFiltering => "Pixel-art filtering",
+            InputAction::CycleTilePack => "Tile pack",
+            InputAction::ColorblindPreview => "Colour blindness preview",
+            InputAction::ToggleMute => "Mute",
+            InputAction::VolumeDown => "Volume down",
+            InputAction::VolumeUp => "Volume up",
+        }
+    }
+
+    pub fn context(&self) -> InputContext {
+        match self {
+            InputAction::PreviousCard
+            | InputAction::NextCard
+            | InputAction::PlayCard
+            | InputAction::PinCard
+            | InputAction::InspectCard
+            | InputAction::DiscardCard
+            | InputAction::Undo
+            | InputAction::Hint
+            | InputAction::Restart
+            | InputAction::AbandonMatch => InputContext::Match,
+            InputAction::ToggleHighContrast
+            | InputAction::ToggleMirroredLayout
+            | InputAction::ToggleMinimalHud
+            | InputAction::ToggleReducedMotion
+            | InputAction::TogglePixelArtFiltering
+            | InputAction::CycleTilePack
+            | InputAction::ColorblindPreview => InputContext::Display,
+            InputAction::ToggleMute | InputAction::VolumeDown | InputAction::VolumeUp => {
+                InputContext::Sound
+            }
+        }
+    }
+
+    // The gamepad layout is fixed: the D-pad moves the hover, A plays, B undoes, Y shows a hint
+    // and Start restarts.
+    fn gamepad_button(&self) -> Option<GamepadButtonType> {
+        match self {
+            InputAction::PreviousCard => Some(GamepadButtonType::DPadLeft),
+            InputAction::NextCard => Some(GamepadButtonType::DPadRight),
+            InputAction::PlayCard => Some(GamepadButtonType::South),
+            InputAction::Undo => Some(GamepadButtonType::East),
+            InputAction::Hint => Some(GamepadButtonType::North),
+            InputAction::Restart => Some(GamepadButtonType::Start),
+            _ => None,
+        }
+    }
+}
+
+// Keys that open/close menus and overlays, navigate and type in them, move cards in hand, or
+// that the second co-op player plays with (`coop::SECOND_PLAYER_KEYS`), and therefore can't be
+// bound. See `is_reserved` for the ones an action has by default.
+const RESERVED_KEYS: [KeyCode; 14] = [
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F12,
+    KeyCode::Tab,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Return,
+    KeyCode::Back,
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::J,
+    KeyCode::L,
+    KeyCode::K,
+];
+
+// Bindings that are not remappable, listed alongside the InputMap ones in the shortcut overlay.
+const FIXED_BINDINGS: [(InputContext, &str, &str); 8] = [
+    (
+        InputContext::Match,
+        "Shift + card keys",
+        "Move card in hand",
+    ),
+    (InputContext::Match, "J/L/K", "Second player (co-op)"),
+    (
+        InputContext::Match,
+        "Gamepad",
+        "D-pad move · A play · B undo · Y hint · Start restart",
+    ),
+    (InputContext::Menus, "F1/Tab", "Shortcuts"),
+    (InputContext::Menus, "F2", "Controls"),
+    (InputContext::Menus, "F12", "Credits"),
+    (InputContext::Menus, "Esc", "Close / pause"),
+    (InputContext::Menus, "Up/Down/Return", "Navigate menus"),
+];
+
+pub struct InputMap {
+    bindings: Vec<(InputAction, Vec<KeyCode>)>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (InputAction::PreviousCard, vec![KeyCode::Left]),
+                (InputAction::NextCard, vec![KeyCode::Right]),
+                (InputAction::PlayCard, vec![KeyCode::Space, KeyCode::Return]),
+                (InputAction::PinCard, vec![KeyCode::P]),
+                (InputAction::InspectCard, vec![KeyCode::Z]),
+                (InputAction::DiscardCard, vec![KeyCode::D]),
+                (InputAction::Undo, vec![KeyCode::U]),
+                (InputAction::Hint, vec![KeyCode::H]),
+                (InputAction::Restart, vec![KeyCode::R]),
+                (InputAction::AbandonMatch, vec![KeyCode::N]),
+                (InputAction::ToggleHighContrast, vec![KeyCode::F3]),
+                (InputAction::ToggleMirroredLayout, vec![KeyCode::F4]),
+                (InputAction::ToggleMinimalHud, vec![KeyCode::F5]),
+                (InputAction::ToggleReducedMotion, vec![KeyCode::F6]),
+                (InputAction::TogglePixelArtFiltering, vec![KeyCode::F7]),
+                (InputAction::CycleTilePack, vec![KeyCode::F8]),
+                (InputAction::ColorblindPreview, vec![KeyCode::F10]),
+                (InputAction::ToggleMute, vec![KeyCode::M]),
+                (InputAction::VolumeDown, vec![KeyCode::Minus]),
+                (InputAction::VolumeUp, vec![KeyCode::Equals]),
+            ],
+        }
+    }
+}
+
+impl InputMap {
+    pub fn keys(&self, action: InputAction) -> &[KeyCode] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn action_for(&self, key: KeyCode) -> Option<InputAction> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(a, _)| *a)
+    }
+
+    pub fn just_pressed(&self, action: InputAction, keyboard_input: &Input<KeyCode>) -> bool {
+        self.keys(action)
+            .iter()
+            .any(|key| keyboard_input.just_pressed(*key))
+    }
+
+    /// Human readable list of the keys bound to `action`, e.g. "Space/Return".
+    pub fn keys_label(&self, action: InputAction) -> String {
+        self.keys(action)
+            .iter()
+            .map(|key| format!("{:?}", key))
+            .collect::<Vec<String>>()
+            .join("/")
+    }
+
+    /// Binds `key` as the only key for `action`. Fails with the conflicting action if `key` is
+    /// already used by another one.
+    pub fn rebind(&mut self, action: InputAction, key: KeyCode) -> Result<(), InputAction> {
+        match self.action_for(key) {
+            Some(other) if other != action => Err(other),
+            _ => {
+                if let Some((_, keys)) = self.bindings.iter_mut().find(|(a, _)| *a == action) {
+                    *keys = vec![key];
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The bindings that differ from the defaults, which is what gets saved with the settings.
+    pub fn changed_bindings(&self) -> Vec<(InputAction, Vec<KeyCode>)> {
+        let defaults = InputMap::default();
+        self.bindings
+            .iter()
+            .filter(|(action, keys)| defaults.keys(*action) != keys.as_slice())
+            .cloned()
+            .collect()
+    }
+
+    /// Applies bindings saved by `changed_bindings` on top of the defaults. The saved actions
+    /// are cleared first, so keys swapped between them come back. A saved binding with no keys,
+    /// a reserved key or a key already taken is skipped, and its action keeps whichever of its
+    /// default keys are still free.
+    pub fn restore(&mut self, saved: &[(InputAction, Vec<KeyCode>)]) {
+        *self = InputMap::default();
+        for (action, _) in saved {
+            self.bindings_mut(*action).clear();
+        }
+        for (action, keys) in saved {
+            let usable = !keys.is_empty()
+                && keys
+                    .iter()
+                    .all(|key| !is_reserved(*action, *key) && self.action_for(*key).is_none());
+            if !usable {
+                warn!("Ignoring the saved keys {:?} for {:?}", keys, action);
+                let free = InputMap::default()
+                    .keys(*action)
+                    .iter()
+                    .copied()
+                    .filter(|key| self.action_for(*key).is_none())
+                    .collect();
+                *self.bindings_mut(*action) = free;
+                continue;
+            }
+            *self.bindings_mut(*action) = keys.clone();
+        }
+    }
+
+    fn bindings_mut(&mut self, action: InputAction) -> &mut Vec<KeyCode> {
+        let index = self
+            .bindings
+            .iter()
+            .position(|(a, _)| *a == action)
+            .expect("every action has a binding");
+        &mut self.bindings[index].1
+    }
+}
+
+/// Actions from the keyboard, through the `InputMap`, and from any connected gamepad.
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    pub keyboard: Res<'w, Input<KeyCode>>,
+    pub input_map: Res<'w, InputMap>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    gamepads: Res<'w, Gamepads>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl ActionInput<'_, '_> {
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.input_map.just_pressed(action, &self.keyboard)
+            || action.gamepad_button().map_or(false, |button_type| {
+                self.gamepads.iter().any(|gamepad| {
+                    self.gamepad_buttons
+                        .just_pressed(GamepadButton(*gamepad, button_type))
+                })
+            })
+    }
+}
+
+/// Any key, mouse button, gamepad button or touch, for screens that only wait for the player.
+#[derive(SystemParam)]
+pub struct AnyPress<'w, 's> {
+    keyboard_input: ResMut<'w, Input<KeyCode>>,
+    mouse_input: ResMut<'w, Input<MouseButton>>,
+    gamepad_input: ResMut<'w, Input<GamepadButton>>,
+    touches: Res<'w, Touches>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl AnyPress<'_, '_> {
+    pub fn any_just_pressed(&self) -> bool {
+        self.keyboard_input.get_just_pressed().next().is_some()
+            || self.mouse_input.get_just_pressed().next().is_some()
+            || self.gamepad_input.get_just_pressed().next().is_some()
+            || self.touches.iter_just_pressed().next().is_some()
+    }
+
+    /// Uses up the presses, so nothing running after sees them. Touches can't be taken back.
+    pub fn clear(&mut self) {
+        self.keyboard_input.clear();
+        self.mouse_input.clear();
+        self.gamepad_input.clear();
+    }
+}
+
+// Not a system! Return plays a card by default, as well as confirming in menus, so an action
+// may keep a reserved key it has by default. No other action can be given one.
+pub(crate) fn is_reserved(action: InputAction, key: KeyCode) -> bool {
+    RESERVED_KEYS.contains(&key) && !InputMap::default().keys(action).contains(&key)
+}
+
+#[derive(Default)]
+pub struct ControlsMenu {
+    pub open: bool,
+    selected: usize,
+    listening: bool,
+    message: Option<String>,
+}
+
+impl ControlsMenu {
+    /// Opens the menu from elsewhere than F2; send `UpdateControlsMenu` to draw it.
+    pub fn show(&mut self) {
+        *self = ControlsMenu {
+            open: true,
+            ..Default::default()
+        };
+    }
+}
+
+pub struct UpdateControlsMenu;
+
+#[derive(Component)]
+pub struct ControlsMenuUi;
+
+#[derive(Component)]
+pub struct ControlsMenuText;
+
+pub fn controls_menu_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut controls_menu: ResMut<ControlsMenu>,
+    mut input_map: ResMut<InputMap>,
+    modal: Res<Modal>,
+    mut ev_update: EventWriter<UpdateControlsMenu>,
+) {
+    if modal.is_open() {
+        return;
+    }
+
+    if !controls_menu.open {
+        if keyboard_input.just_pressed(KeyCode::F2) {
+            controls_menu.show();
+            ev_update.send(UpdateControlsMenu);
+        }
+        return;
+    }
+
+    if controls_menu.listening {
+        if keyboard_input.just_pressed(KeyCode::Escape) {
+            controls_menu.listening = false;
+            controls_menu.message = None;
+            ev_update.send(UpdateControlsMenu);
+        } else if let Some(key) = keyboard_input.get_just_pressed().next() {
+            let action = InputAction::ALL[controls_menu.selected];
+            if is_reserved(action, *key) {
+                controls_menu.message = Some(format!("{:?} is reserved", key));
+            } else {
+                match input_map.rebind(action, *key) {
+                    Ok(()) => {
+                        controls_menu.listening = false;
+                        controls_menu.message = None;
+                    }
+                    Err(other) => {
+                        controls_menu.message =
+                            Some(format!("{:?} is already bound to {}", key, other.label()));
+                    }
+                }
+            }
+            ev_update.send(UpdateControlsMenu);
+        }
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) || keyboard_input.just_pressed(KeyCode::F2) {
+        controls_menu.open = false;
+        ev_update.send(UpdateControlsMenu);
+    } else if keyboard_input.just_pressed(KeyCode::Up) {
+        controls_menu.selected = if controls_menu.selected == 0 {
+            InputAction::ALL.len() - 1
+        } else {
+            controls_menu.selected - 1
+        };
+        ev_update.send(UpdateControlsMenu);
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        controls_menu.selected = (controls_menu.selected + 1) % InputAction::ALL.len();
+        ev_update.send(UpdateControlsMenu);
+    } else if keyboard_input.just_pressed(KeyCode::Return) {
+        controls_menu.listening = true;
+        controls_menu.message = None;
+        ev_update.send(UpdateControlsMenu);
+    } else if keyboard_input.just_pressed(KeyCode::Back) {
+        *input_map = InputMap::default();
+        controls_menu.message = Some("Controls reset to default".to_string());
+        ev_update.send(UpdateControlsMenu);
+    }
+}
+
+fn controls_menu_text(controls_menu: &ControlsMenu, input_map: &InputMap) -> String {
+    let mut lines = vec!["Controls".to_string(), String::new()];
+    for (i, action) in InputAction::ALL.iter().enumerate() {
+        let keys = if controls_menu.listening && i == controls_menu.selected {
+            "press a key...".to_string()
+        } else {
+            input_map.keys_label(*action)
+        };
+        lines.push(format!(
+            "{} {}: {}",
+            if i == controls_menu.selected {
+                ">"
+            } else {
+                " "
+            },
+            action.label(),
+            keys
+        ));
+    }
+    lines.push(String::new());
+    if let Some(message) = &controls_menu.message {
+        lines.push(message.clone());
+    }
+    lines.push("Up/Down select · Return rebind · Backspace reset · Esc close".to_string());
+    lines.join("\n")
+}
+
+pub fn update_controls_menu(
+    mut ev_update: EventReader<UpdateControlsMenu>,
+    controls_menu: Res<ControlsMenu>,
+    input_map: Res<InputMap>,
+    q_ui: Query<Entity, With<ControlsMenuUi>>,
+    mut q_text: Query<&mut Text, With<ControlsMenuText>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if ev_update.iter().count() == 0 {
+        return;
+    }
+
+    if !controls_menu.open {
+        for e in q_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+
+    let value = controls_menu_text(&controls_menu, &input_map);
+    if let Ok(mut text) = q_text.get_single_mut() {
+        text.sections[0].value = value;
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 80.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: Color::rgba(0., 0., 0., 0.8),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ControlsMenuUi);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                value,
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 30.,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., 0., 81.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ControlsMenuUi)
+        .insert(ControlsMenuText);
+}
+
+#[derive(Default)]
+pub struct ShortcutOverlay {
+    toggled: bool,
+}
+
+#[derive(Component)]
+pub struct ShortcutOverlayUi;
+
+fn shortcut_overlay_text(input_map: &InputMap) -> String {
+    let mut lines = vec!["Shortcuts".to_string()];
+    for context in InputContext::ALL.iter() {
+        lines.push(String::new());
+        lines.push(context.label().to_string());
+        for action in InputAction::ALL.iter().filter(|a| a.context() == *context) {
+            lines.push(format!(
+                "{}: {}",
+                action.label(),
+                input_map.keys_label(*action)
+            ));
+        }
+        for (_, keys, label) in FIXED_BINDINGS.iter().filter(|(c, _, _)| c == context) {
+            lines.push(format!("{}: {}", label, keys));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Shows every binding while Tab is held, or until F1 is pressed again.
+pub fn shortcut_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut overlay: ResMut<ShortcutOverlay>,
+    q_ui: Query<Entity, With<ShortcutOverlayUi>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F1) {
+        overlay.toggled = !overlay.toggled;
+    }
+    let visible = overlay.toggled || keyboard_input.pressed(KeyCode::Tab);
+    let is_shown = !q_ui.is_empty();
+
+    if !visible && is_shown {
+        for e in q_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+    } else if visible && !is_shown {
+        commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., 0., 90.),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(4000., 4000.)),
+                    color: Color::rgba(0., 0., 0., 0.6),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(ShortcutOverlayUi);
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    shortcut_overlay_text(&input_map),
+                    TextStyle {
+                        font: asset_server.load("ReadexPro-Regular.ttf"),
+                        font_size: 26.,
+                        color: Color::WHITE,
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Center,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                ),
+                transform: Transform {
+                    translation: Vec3::new(0., 0., 91.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(ShortcutOverlayUi);
+    }
+}
@@ -0,0 +1,363 @@
+//! Co-op mode: two players at one keyboard share the board and take turns playing cards. Each has
+//! a cursor over the hand and can move it at any time, but only the player whose turn it is can
+//! play. The first player uses the regular controls, the second J and L to move and K to play.
+//! It runs on top of the regular match systems, which only know about the cursor of the player
+//! whose turn it is: that one is the hovered card.
+
+use bevy::{
+    ecs::{schedule::ShouldRun, system::SystemParam},
+    prelude::*,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    controls::{InputAction, InputMap},
+    layout::Layout,
+    match_plugin::{
+        Card, CardClicked, InputLock, MatchState, StartMatchEvent, UpdateCardsStyle, Used,
+        CARDS_Y_POS,
+    },
+    toast::ShowToast,
+    GameMode, GameState,
+};
+
+const PLAYER_COLORS: [Color; 2] = [Color::rgb(0.45, 0.75, 1.), Color::rgb(0.95, 0.4, 0.35)];
+// The second player's keys: previous card, next card and play.
+const SECOND_PLAYER_KEYS: [KeyCode; 3] = [KeyCode::J, KeyCode::L, KeyCode::K];
+const TURN_Y_POS: f32 = 460.;
+// Above the hand, clear of a hovered card.
+const CURSOR_MARKER_Y_OFFSET: f32 = 175.;
+// The cursor of the player waiting for their turn is drawn faded.
+const WAITING_ALPHA: f32 = 0.4;
+
+/// Whose turn it is, and where the cursor of the other player is.
+#[derive(Default)]
+pub(crate) struct Coop {
+    /// 0 for the first player, 1 for the second.
+    turn: usize,
+    cursors: [Option<Entity>; 2],
+}
+
+#[derive(Component)]
+struct TurnText;
+
+#[derive(Component)]
+struct CursorMarker(usize);
+
+pub(crate) struct CoopPlugin;
+
+impl Plugin for CoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Coop>()
+            .add_startup_system(setup_coop_hud)
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(coop_match)
+                    .with_system(
+                        reset_cursors
+                            .label("reset_coop_cursors")
+                            .after("start_match"),
+                    )
+                    .with_system(
+                        pass_turn
+                            .label("pass_turn")
+                            .after("reset_coop_cursors")
+                            .before("handle_input"),
+                    )
+                    .with_system(
+                        coop_input
+                            .after("pass_turn")
+                            .after("pick_card_with_mouse")
+                            .before("handle_input"),
+                    ),
+            )
+            .add_system_to_stage("view", update_coop_hud);
+    }
+}
+
+// The co-op systems only run in game, and only in co-op mode.
+fn coop_match(mode: Res<GameMode>, state: Res<State<GameState>>) -> ShouldRun {
+    if *mode == GameMode::Coop && *state.current() == GameState::InGame {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+// The first player starts on the leftmost card, the second on the rightmost.
+fn reset_cursors(
+    mut start_match_event: EventReader<StartMatchEvent>,
+    match_state: Res<MatchState>,
+    mut coop: ResMut<Coop>,
+) {
+    if start_match_event.iter().count() == 0 {
+        return;
+    }
+    if let MatchState::Playing(playing) = match_state.as_ref() {
+        *coop = Coop {
+            turn: 0,
+            cursors: [
+                playing.hand_order.first().copied(),
+                playing.hand_order.last().copied(),
+            ],
+        };
+    }
+}
+
+// Not a system! The neighbouring card of `card` in the hand, wrapping around.
+fn step_cursor(hand_order: &[Entity], card: Entity, forward: bool) -> Entity {
+    let slot = hand_order.iter().position(|c| *c == card).unwrap_or(0);
+    let len = hand_order.len();
+    hand_order[if forward {
+        (slot + 1) % len
+    } else {
+        (slot + len - 1) % len
+    }]
+}
+
+// Turns follow the cards played, so undoing or restarting hands the turn back too. When the
+// turn passes, the hovered card becomes the cursor of the player whose turn it is.
+fn pass_turn(
+    mut coop: ResMut<Coop>,
+    mut match_state: ResMut<MatchState>,
+    q_cards: Query<Option<&Used>, With<Card>>,
+    mut event_update_cards_style: EventWriter<UpdateCardsStyle>,
+) {
+    let turn = q_cards.iter().filter(|used| used.is_some()).count() % 2;
+    if coop.turn == turn {
+        return;
+    }
+    let playing = match match_state.as_mut() {
+        MatchState::Playing(playing) => playing,
+        _ => return,
+    };
+    let waiting = coop.turn;
+    coop.cursors[waiting] = playing.hovered_card;
+    let unused = |card: &Entity| matches!(q_cards.get(*card), Ok(None));
+    playing.hovered_card = coop.cursors[turn]
+        .filter(unused)
+        .or_else(|| playing.hand_order.iter().copied().find(unused))
+        .or(playing.hovered_card);
+    coop.cursors[turn] = playing.hovered_card;
+    coop.turn = turn;
+    event_update_cards_style.send(UpdateCardsStyle);
+}
+
+/// The keys and buttons both players play with. Those of the player waiting for their turn are
+/// taken away, so they need to be mutable.
+#[derive(SystemParam)]
+struct CoopKeys<'w, 's> {
+    keyboard_input: ResMut<'w, Input<KeyCode>>,
+    gamepad_input: ResMut<'w, Input<GamepadButton>>,
+    gamepads: Res<'w, Gamepads>,
+    input_map: Res<'w, InputMap>,
+    layout: Res<'w, Layout>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+// The player waiting for their turn can move their cursor, but their play key is taken away
+// before `handle_input` sees it. The gamepad belongs to the first player.
+fn coop_input(
+    lock: InputLock,
+    mut keys: CoopKeys,
+    mut coop: ResMut<Coop>,
+    mut match_state: ResMut<MatchState>,
+    mut event_update_cards_style: EventWriter<UpdateCardsStyle>,
+    mut ev_card_clicked: EventWriter<CardClicked>,
+    mut ev_toast: EventWriter<ShowToast>,
+) {
+    if lock.is_locked() {
+        return;
+    }
+    let hand_order = match match_state.as_ref() {
+        MatchState::Playing(playing)
+            if playing.inspected_card.is_none() && !playing.picking_tiles() =>
+        {
+            playing.hand_order.clone()
+        }
+        _ => return,
+    };
+
+    // Previous card, next card and play, for each player.
+    let CoopKeys {
+        keyboard_input,
+        gamepad_input,
+        gamepads,
+        input_map,
+        layout,
+        ..
+    } = &mut keys;
+    let keys = [
+        [
+            InputAction::PreviousCard,
+            InputAction::NextCard,
+            InputAction::PlayCard,
+        ]
+        .map(|action| input_map.keys(action).to_vec()),
+        SECOND_PLAYER_KEYS.map(|key| vec![key]),
+    ];
+    let buttons = [
+        GamepadButtonType::DPadLeft,
+        GamepadButtonType::DPadRight,
+        GamepadButtonType::South,
+    ];
+    let waiting = 1 - coop.turn;
+    let mut pressed = [0, 1, 2].map(|i| {
+        if waiting == 0 {
+            // Taken away from the first player, so `handle_input` doesn't see them.
+            let keys_pressed = keys[0][i]
+                .iter()
+                .any(|key| keyboard_input.clear_just_pressed(*key));
+            let buttons_pressed = gamepads.iter().any(|gamepad| {
+                gamepad_input.clear_just_pressed(GamepadButton(*gamepad, buttons[i]))
+            });
+            keys_pressed || buttons_pressed
+        } else {
+            keys[1][i]
+                .iter()
+                .any(|key| keyboard_input.just_pressed(*key))
+        }
+    });
+    // Cards are laid out right to left when mirrored, so swap the keys to keep them matching
+    // the on-screen direction.
+    if layout.mirrored {
+        pressed.swap(0, 1);
+    }
+    let [previous, next, play] = pressed;
+    if play {
+        ev_toast.send(ShowToast(format!("Player {}'s turn", coop.turn + 1)));
+    }
+    if previous || next {
+        if let Some(cursor) = coop.cursors[waiting] {
+            coop.cursors[waiting] = Some(step_cursor(&hand_order, cursor, next));
+        }
+    }
+
+    // The first player's keys are left for `handle_input`, the second's are played here.
+    if coop.turn == 1 {
+        let [mut previous, mut next, play] =
+            [0, 1, 2].map(|i| keyboard_input.just_pressed(SECOND_PLAYER_KEYS[i]));
+        if layout.mirrored {
+            std::mem::swap(&mut previous, &mut next);
+        }
+        if previous || next {
+            if let MatchState::Playing(playing) = match_state.as_mut() {
+                playing.step_hover(next, false);
+            }
+            event_update_cards_style.send(UpdateCardsStyle);
+        }
+        if play {
+            ev_card_clicked.send(CardClicked);
+        }
+    }
+}
+
+fn setup_coop_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("ReadexPro-Regular.ttf");
+    let alignment = TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Center,
+    };
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 30.,
+                    color: Color::WHITE,
+                },
+                alignment,
+            ),
+            transform: Transform::from_xyz(0., TURN_Y_POS, 50.),
+            ..Default::default()
+        })
+        .insert(TurnText);
+    for (player, color) in PLAYER_COLORS.iter().enumerate() {
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    format!("P{}", player + 1),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 28.,
+                        color: *color,
+                    },
+                    alignment,
+                ),
+                transform: Transform::from_xyz(0., CARDS_Y_POS + CURSOR_MARKER_Y_OFFSET, 60.),
+                ..Default::default()
+            })
+            .insert(CursorMarker(player));
+    }
+}
+
+// Shown through a co-op match: whose turn it is, and a marker above each player's card.
+fn update_coop_hud(
+    mode: Res<GameMode>,
+    state: Res<State<GameState>>,
+    coop: Res<Coop>,
+    match_state: Res<MatchState>,
+    q_cards: Query<&GlobalTransform, With<Card>>,
+    mut q_turn: Query<(&mut Text, &mut Visibility), With<TurnText>>,
+    mut q_markers: Query<
+        (&CursorMarker, &mut Text, &mut Transform, &mut Visibility),
+        Without<TurnText>,
+    >,
+) {
+    // The cursor of the player whose turn it is is the hovered card.
+    let cursors = match match_state.as_ref() {
+        MatchState::Playing(playing)
+            if *mode == GameMode::Coop
+                && matches!(state.current(), GameState::InGame | GameState::Paused) =>
+        {
+            let mut cursors = coop.cursors;
+            cursors[coop.turn] = playing.hovered_card;
+            Some(cursors)
+        }
+        _ => None,
+    };
+
+    for (mut text, mut visibility) in q_turn.iter_mut() {
+        if visibility.is_visible != cursors.is_some() {
+            visibility.is_visible = cursors.is_some();
+        }
+        let value = format!("Player {}'s turn", coop.turn + 1);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+            text.sections[0].style.color = PLAYER_COLORS[coop.turn];
+        }
+    }
+
+    for (CursorMarker(player), mut text, mut transform, mut visibility) in q_markers.iter_mut() {
+        let card = cursors
+            .and_then(|cursors| cursors[*player])
+            .and_then(|card| q_cards.get(card).ok());
+        if visibility.is_visible != card.is_some() {
+            visibility.is_visible = card.is_some();
+        }
+        let (card, cursors) = match (card, cursors) {
+            (Some(card), Some(cursors)) => (card, cursors),
+            _ => continue,
+        };
+        // Both cursors on one card are drawn side by side.
+        let nudge = if cursors[0] == cursors[1] {
+            (*player as f32 - 0.5) * 50.
+        } else {
+            0.
+        };
+        let x = card.translation.x + nudge;
+        if transform.translation.x != x {
+            transform.translation.x = x;
+        }
+        let alpha = if *player == coop.turn {
+            1.
+        } else {
+            WAITING_ALPHA
+        };
+        if text.sections[0].style.color.a() != alpha {
+            text.sections[0].style.color.set_a(alpha);
+        }
+    }
+}
@@ -0,0 +1,1129 @@
+//! The rules of the puzzle, without Bevy: the columns and the actions that rearrange them,
+//! dealing and solving. Everything here works on plain data, so it can be used without an `App`,
+//! like `--generate` does.
+
+use std::collections::HashSet;
+
+use rand::{distributions::WeightedIndex, prelude::*};
+use serde::Serialize;
+
+/// How many kinds of tile there are, one per texture pair in `match_plugin::TILES_LEFT` and
+/// `TILES_RIGHT`.
+pub(crate) const fn nature_count() -> usize {
+    8
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct TileNature(pub(crate) usize);
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TileSide {
+    Left,
+    Right,
+}
+
+#[derive(Serialize, Clone, Copy)]
+pub(crate) enum CycleDirection {
+    Up,
+    Down,
+}
+
+#[derive(Serialize, Clone, Copy)]
+pub(crate) enum Action {
+    SwapFirstAndLast {
+        side: TileSide,
+    },
+    SwapTwoAdjacent {
+        top: usize,
+        side: TileSide,
+    },
+    SwapTwoNatures {
+        nature_a: TileNature,
+        nature_b: TileNature,
+        side: TileSide,
+    },
+    // Cycling by the column length or more wraps around, so `times` is taken modulo it.
+    Cycle {
+        times: usize,
+        direction: CycleDirection,
+        side: TileSide,
+    },
+    // Turns the column upside down. Playing it twice puts the column back.
+    Reverse {
+        side: TileSide,
+    },
+    // Swaps the left and the right tile in a row, the only action that moves tiles between the
+    // columns.
+    SwapAcross {
+        row: usize,
+    },
+    // Takes the tile out of `from` and puts it back in at `to`, the tiles in between shifting
+    // over to make room.
+    MoveTo {
+        from: usize,
+        to: usize,
+        side: TileSide,
+    },
+    // Swaps any two tiles of a column. Only dealt as a wildcard, which is resolved into one of
+    // these once the player has picked the tiles.
+    SwapRows {
+        a: usize,
+        b: usize,
+        side: TileSide,
+    },
+    // Stands for whichever `SwapRows` the player picks, and does nothing until resolved.
+    Wildcard,
+}
+
+// Every swap undoes itself, and a cycle is undone by cycling the other way.
+pub(crate) fn inverse_action(action: &Action) -> Action {
+    match *action {
+        Action::Cycle {
+            times,
+            direction,
+            side,
+        } => Action::Cycle {
+            times,
+            direction: match direction {
+                CycleDirection::Up => CycleDirection::Down,
+                CycleDirection::Down => CycleDirection::Up,
+            },
+            side,
+        },
+        Action::MoveTo { from, to, side } => Action::MoveTo {
+            from: to,
+            to: from,
+            side,
+        },
+        action => action,
+    }
+}
+
+pub(crate) fn apply_inverse_action<'a, T>(
+    action: &Action,
+    left_col: &'a mut Vec<T>,
+    right_col: &'a mut Vec<T>,
+    get_nature: Box<dyn Fn(&T) -> TileNature>,
+) {
+    apply_action(&inverse_action(action), left_col, right_col, get_nature);
+}
+
+pub(crate) fn apply_action<'a, T>(
+    action: &Action,
+    mut left_col: &'a mut Vec<T>,
+    mut right_col: &'a mut Vec<T>,
+    get_nature: Box<dyn Fn(&T) -> TileNature>,
+) {
+    match action {
+        Action::SwapFirstAndLast { side } => {
+            let col = match side {
+                TileSide::Left => &mut left_col,
+                TileSide::Right => &mut right_col,
+            };
+            let col_len = col.len();
+            col.swap(0, col_len - 1);
+        }
+        Action::SwapTwoAdjacent { top, side } => {
+            let bottom = top + 1;
+            let col = match side {
+                TileSide::Left => &mut left_col,
+                TileSide::Right => &mut right_col,
+            };
+            col.swap(*top, bottom);
+        }
+        Action::SwapTwoNatures {
+            nature_a,
+            nature_b,
+            side,
+        } => {
+            let col = match side {
+                TileSide::Left => &mut left_col,
+                TileSide::Right => &mut right_col,
+            };
+            let index_a = col.iter().position(|x| get_nature(x) == *nature_a).unwrap();
+            let index_b = col.iter().position(|x| get_nature(x) == *nature_b).unwrap();
+            col.swap(index_a, index_b);
+        }
+        Action::Cycle {
+            times,
+            direction,
+            side,
+        } => {
+            let col = match side {
+                TileSide::Left => &mut left_col,
+                TileSide::Right => &mut right_col,
+            };
+            // Index 0 is the bottom row, so moving down shifts every tile to a lower index.
+            let times = times.checked_rem(col.len()).unwrap_or(0);
+            match direction {
+                CycleDirection::Down => col.rotate_left(times),
+                CycleDirection::Up => col.rotate_right(times),
+            }
+        }
+        Action::Reverse { side } => {
+            let col = match side {
+                TileSide::Left => &mut left_col,
+                TileSide::Right => &mut right_col,
+            };
+            col.reverse();
+        }
+        Action::SwapAcross { row } => {
+            std::mem::swap(&mut left_col[*row], &mut right_col[*row]);
+        }
+        Action::MoveTo { from, to, side } => {
+            let col = match side {
+                TileSide::Left => &mut left_col,
+                TileSide::Right => &mut right_col,
+            };
+            let tile = col.remove(*from);
+            col.insert(*to, tile);
+        }
+        Action::SwapRows { a, b, side } => {
+            let col = match side {
+                TileSide::Left => &mut left_col,
+                TileSide::Right => &mut right_col,
+            };
+            col.swap(*a, *b);
+        }
+        Action::Wildcard => (),
+    }
+}
+
+// The column an action rearranges, if it only touches one.
+pub(crate) fn action_side(action: &Action) -> Option<TileSide> {
+    match action {
+        Action::SwapFirstAndLast { side }
+        | Action::SwapTwoAdjacent { side, .. }
+        | Action::SwapTwoNatures { side, .. }
+        | Action::Cycle { side, .. }
+        | Action::Reverse { side }
+        | Action::MoveTo { side, .. }
+        | Action::SwapRows { side, .. } => Some(*side),
+        Action::SwapAcross { .. } | Action::Wildcard => None,
+    }
+}
+
+pub(crate) fn side_name(side: &TileSide) -> &'static str {
+    match side {
+        TileSide::Left => "left",
+        TileSide::Right => "right",
+    }
+}
+
+pub(crate) fn nature_name(nature: &TileNature) -> char {
+    (b'A' + nature.0 as u8) as char
+}
+
+// Long explanation of an action, shown when a card is inspected. Rows are counted from the
+// bottom, like the column indices.
+pub(crate) fn action_description(action: &Action) -> String {
+    match action {
+        Action::SwapFirstAndLast { side } => format!(
+            "Swap the bottom tile and the top tile of the {} column.\nThe tiles in between stay where they are.",
+            side_name(side)
+        ),
+        Action::SwapTwoAdjacent { top, side } => format!(
+            "Swap the tiles in rows {} and {} of the {} column, counting from the bottom.\nThe other tiles stay where they are.",
+            top + 1,
+            top + 2,
+            side_name(side)
+        ),
+        Action::SwapTwoNatures {
+            nature_a,
+            nature_b,
+            side,
+        } => format!(
+            "Swap tile {} and tile {} in the {} column, wherever they are.\nThe other tiles stay where they are.",
+            nature_name(nature_a),
+            nature_name(nature_b),
+            side_name(side)
+        ),
+        Action::Cycle {
+            times,
+            direction,
+            side,
+        } => format!(
+            "Move every tile of the {} column {} by {} row(s).\nTiles pushed off the {} come back in from the {}.",
+            side_name(side),
+            match direction {
+                CycleDirection::Up => "up",
+                CycleDirection::Down => "down",
+            },
+            times,
+            match direction {
+                CycleDirection::Up => "top",
+                CycleDirection::Down => "bottom",
+            },
+            match direction {
+                CycleDirection::Up => "bottom",
+                CycleDirection::Down => "top",
+            },
+        ),
+        Action::Reverse { side } => format!(
+            "Turn the {} column upside down.\nThe bottom tile goes to the top, the top tile to the bottom, and so on.",
+            side_name(side)
+        ),
+        Action::SwapAcross { row } => format!(
+            "Swap the left and the right tile in row {}, counting from the bottom.\nThe other tiles stay where they are.",
+            row + 1
+        ),
+        Action::MoveTo { from, to, side } => format!(
+            "Move the tile in row {} of the {} column to row {}, counting from the bottom.\nThe tiles in between shift {} by one row to make room.",
+            from + 1,
+            side_name(side),
+            to + 1,
+            if from < to { "down" } else { "up" }
+        ),
+        Action::SwapRows { a, b, side } => format!(
+            "Swap the tiles in rows {} and {} of the {} column, counting from the bottom.\nThe other tiles stay where they are.",
+            a + 1,
+            b + 1,
+            side_name(side)
+        ),
+        Action::Wildcard => "Swap any two tiles in one column.\nPick the tiles after playing the card.".to_string(),
+    }
+}
+
+// One line summary of an action, shown in the caption bar under the hand.
+pub(crate) fn action_caption(action: &Action) -> String {
+    match action {
+        Action::SwapFirstAndLast { side } => format!(
+            "Swap the top and bottom tiles of the {} column",
+            side_name(side)
+        ),
+        Action::SwapTwoAdjacent { top, side } => format!(
+            "Swap the tiles in rows {} and {} of the {} column",
+            top + 1,
+            top + 2,
+            side_name(side)
+        ),
+        Action::SwapTwoNatures {
+            nature_a,
+            nature_b,
+            side,
+        } => format!(
+            "Swap tiles {} and {} in the {} column",
+            nature_name(nature_a),
+            nature_name(nature_b),
+            side_name(side)
+        ),
+        Action::Cycle {
+            times,
+            direction,
+            side,
+        } => format!(
+            "Move every tile of the {} column {} by {}",
+            side_name(side),
+            match direction {
+                CycleDirection::Up => "up",
+                CycleDirection::Down => "down",
+            },
+            times
+        ),
+        Action::Reverse { side } => {
+            format!("Turn the {} column upside down", side_name(side))
+        }
+        Action::SwapAcross { row } => {
+            format!("Swap the left and right tiles in row {}", row + 1)
+        }
+        Action::MoveTo { from, to, side } => format!(
+            "Move the tile in row {} of the {} column to row {}",
+            from + 1,
+            side_name(side),
+            to + 1
+        ),
+        Action::SwapRows { a, b, side } => format!(
+            "Swap the tiles in rows {} and {} of the {} column",
+            a + 1,
+            b + 1,
+            side_name(side)
+        ),
+        Action::Wildcard => "Swap any two tiles in one column".to_string(),
+    }
+}
+
+// Which tiles (side and index) an action would move if applied to these columns.
+pub(crate) fn affected_tiles<T>(
+    action: &Action,
+    left_col: &[T],
+    right_col: &[T],
+    get_nature: Box<dyn Fn(&T) -> TileNature>,
+) -> Vec<(TileSide, usize)> {
+    match action {
+        Action::SwapFirstAndLast { side } => {
+            let col_len = match side {
+                TileSide::Left => left_col.len(),
+                TileSide::Right => right_col.len(),
+            };
+            vec![(*side, 0), (*side, col_len - 1)]
+        }
+        Action::SwapTwoAdjacent { top, side } => vec![(*side, *top), (*side, top + 1)],
+        Action::SwapTwoNatures {
+            nature_a,
+            nature_b,
+            side,
+        } => {
+            let col = match side {
+                TileSide::Left => left_col,
+                TileSide::Right => right_col,
+            };
+            col.iter()
+                .enumerate()
+                .filter(|(_, x)| get_nature(x) == *nature_a || get_nature(x) == *nature_b)
+                .map(|(i, _)| (*side, i))
+                .collect()
+        }
+        Action::Cycle { side, .. } | Action::Reverse { side } => {
+            let col_len = match side {
+                TileSide::Left => left_col.len(),
+                TileSide::Right => right_col.len(),
+            };
+            (0..col_len).map(|i| (*side, i)).collect()
+        }
+        Action::SwapAcross { row } => vec![(TileSide::Left, *row), (TileSide::Right, *row)],
+        Action::MoveTo { from, to, side } => (*from.min(to)..=*from.max(to))
+            .map(|i| (*side, i))
+            .collect(),
+        Action::SwapRows { a, b, side } => vec![(*side, *a), (*side, *b)],
+        // Nothing is known to move until the player picks the tiles.
+        Action::Wildcard => Vec::new(),
+    }
+}
+
+/// The fewest rows a player can pick for the board, which can have up to `nature_count()`.
+pub(crate) const MIN_BOARD_ROWS: usize = 3;
+
+// How matches are generated. Tutorial levels can go as low as a single tile or card.
+#[derive(Clone)]
+pub(crate) struct MatchConfig {
+    pub(crate) tiles_count: usize,
+    pub(crate) card_count: usize,
+    // How many of the dealt cards are applied in reverse to scramble the columns.
+    pub(crate) applied_card_count: usize,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            tiles_count: 4,
+            card_count: 5,
+            applied_card_count: 3,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum MatchConfigError {
+    NoTiles,
+    TooManyTiles { count: usize, max: usize },
+    NoCards,
+    TooManyAppliedCards { applied: usize, cards: usize },
+}
+
+impl std::fmt::Display for MatchConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MatchConfigError::NoTiles => write!(f, "a match needs at least one tile per column"),
+            MatchConfigError::TooManyTiles { count, max } => write!(
+                f,
+                "{} tiles per column requested, but there are only {} tile natures",
+                count, max
+            ),
+            MatchConfigError::NoCards => write!(f, "a match needs at least one card"),
+            MatchConfigError::TooManyAppliedCards { applied, cards } => write!(
+                f,
+                "{} cards should be applied to scramble the columns, but only {} are dealt",
+                applied, cards
+            ),
+        }
+    }
+}
+
+impl MatchConfig {
+    pub(crate) fn validate(&self) -> Result<(), MatchConfigError> {
+        if self.tiles_count == 0 {
+            Err(MatchConfigError::NoTiles)
+        } else if self.tiles_count > nature_count() {
+            Err(MatchConfigError::TooManyTiles {
+                count: self.tiles_count,
+                max: nature_count(),
+            })
+        } else if self.card_count == 0 {
+            Err(MatchConfigError::NoCards)
+        } else if self.applied_card_count > self.card_count {
+            Err(MatchConfigError::TooManyAppliedCards {
+                applied: self.applied_card_count,
+                cards: self.card_count,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// How often each kind of card is dealt, in the order `deal` numbers them. Reversing a whole
+// column moves every tile at once and a wildcard can be any swap, so they are dealt half as
+// often as the others.
+const CARD_KIND_WEIGHTS: [usize; 8] = [2, 2, 2, 2, 1, 2, 2, 1];
+
+/// A dealt match: the scrambled columns, bottom to top, and the hand.
+#[derive(Serialize, Clone)]
+pub(crate) struct Puzzle {
+    pub(crate) seed: u64,
+    pub(crate) left_col: Vec<TileNature>,
+    pub(crate) right_col: Vec<TileNature>,
+    pub(crate) cards: Vec<Action>,
+    // How many cards were played backwards to scramble the columns, which is how many it takes
+    // to solve the puzzle by playing them forwards.
+    pub(crate) par: usize,
+}
+
+fn rand_tile_side(rng: &mut impl Rng) -> TileSide {
+    match rng.gen_range(0usize, 2usize) {
+        0 => TileSide::Left,
+        1 => TileSide::Right,
+        _ => unreachable!(),
+    }
+}
+
+// A column of `tiles_count` different tiles.
+fn random_tiles(tiles_count: usize, rng: &mut impl Rng) -> Vec<TileNature> {
+    let mut pool = (0..nature_count()).collect::<Vec<usize>>();
+    let mut tiles = Vec::new();
+    for _ in 0..tiles_count {
+        tiles.push(TileNature(pool.swap_remove(rng.gen_range(0, pool.len()))));
+    }
+    tiles
+}
+
+// A card of a kind picked by `card_kind_weights`, for columns of `tiles_order`. The weights are
+// updated so the kinds that can't be in the same hand as it are never picked after it.
+fn random_card(
+    tiles_order: &[TileNature],
+    card_kind_weights: &mut [usize; 8],
+    rng: &mut impl Rng,
+) -> Action {
+    let tiles_count = tiles_order.len();
+    // Swapping two tiles needs at least two of them in a column.
+    let kind = if tiles_count < 2 {
+        [0, 3][rng.gen_range(0usize, 2usize)]
+    } else {
+        WeightedIndex::new(&card_kind_weights[..])
+            .unwrap()
+            .sample(rng)
+    };
+    // Swapping natures finds them in their column, which swapping across can break by
+    // putting two tiles of a kind in one column. A hand never has both.
+    match kind {
+        2 => card_kind_weights[5] = 0,
+        5 => card_kind_weights[2] = 0,
+        _ => (),
+    }
+    match kind {
+        0 => Action::SwapFirstAndLast {
+            side: rand_tile_side(rng),
+        },
+        1 => Action::SwapTwoAdjacent {
+            top: rng.gen_range(0, tiles_count - 1),
+            side: rand_tile_side(rng),
+        },
+        2 => {
+            let (nature_a, nature_b) = random_nature_pair(tiles_order, rng);
+            Action::SwapTwoNatures {
+                nature_a,
+                nature_b,
+                side: rand_tile_side(rng),
+            }
+        }
+        3 => Action::Cycle {
+            // Cycling a whole column round would leave it as it was.
+            times: rng.gen_range(1, tiles_count.max(2)),
+            direction: match rng.gen_range(0usize, 2usize) {
+                0 => CycleDirection::Up,
+                1 => CycleDirection::Down,
+                _ => unreachable!(),
+            },
+            side: rand_tile_side(rng),
+        },
+        4 => Action::Reverse {
+            side: rand_tile_side(rng),
+        },
+        5 => Action::SwapAcross {
+            row: rng.gen_range(0, tiles_count),
+        },
+        6 => {
+            let from = rng.gen_range(0, tiles_count);
+            // Any other row, so the card always moves something.
+            let to = rng.gen_range(0, tiles_count - 1);
+            Action::MoveTo {
+                from,
+                to: if to >= from { to + 1 } else { to },
+                side: rand_tile_side(rng),
+            }
+        }
+        7 => Action::Wildcard,
+        _ => unreachable!(),
+    }
+}
+
+fn random_nature_pair(tiles_order: &[TileNature], rng: &mut impl Rng) -> (TileNature, TileNature) {
+    let mut pool = tiles_order.to_vec();
+    let nature_a = pool.swap_remove(rng.gen_range(0, pool.len()));
+    let nature_b = pool.swap_remove(rng.gen_range(0, pool.len()));
+    (nature_a, nature_b)
+}
+
+// Solved columns of `tiles_order`, scrambled by playing `count` of `cards` backwards.
+fn scramble(
+    tiles_order: Vec<TileNature>,
+    cards: &[Action],
+    count: usize,
+    rng: &mut impl Rng,
+) -> (Vec<TileNature>, Vec<TileNature>) {
+    let tiles_count = tiles_order.len();
+    let mut left_col = tiles_order.clone();
+    let mut right_col = tiles_order;
+    let mut cards_to_apply_pool = cards.to_vec();
+    for _ in 0..count {
+        let mut card_to_apply =
+            cards_to_apply_pool.swap_remove(rng.gen_range(0, cards_to_apply_pool.len()));
+        if let Action::Wildcard = card_to_apply {
+            let mut rows = (0..tiles_count).collect::<Vec<_>>();
+            card_to_apply = Action::SwapRows {
+                a: rows.swap_remove(rng.gen_range(0, rows.len())),
+                b: rows.swap_remove(rng.gen_range(0, rows.len())),
+                side: rand_tile_side(rng),
+            };
+        }
+        apply_inverse_action(
+            &card_to_apply,
+            &mut left_col,
+            &mut right_col,
+            Box::new(|x| *x),
+        );
+    }
+    (left_col, right_col)
+}
+
+/// Everything random about a match comes from the seed, so the same seed and config always
+/// deal the same puzzle.
+pub(crate) fn deal(seed: u64, config: &MatchConfig) -> Puzzle {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let tiles_order = random_tiles(config.tiles_count, &mut rng);
+    let mut card_kind_weights = CARD_KIND_WEIGHTS;
+    let card_actions = (0..config.card_count)
+        .map(|_| random_card(&tiles_order, &mut card_kind_weights, &mut rng))
+        .collect::<Vec<_>>();
+    let (left_col, right_col) = scramble(
+        tiles_order,
+        &card_actions,
+        config.applied_card_count,
+        &mut rng,
+    );
+
+    Puzzle {
+        seed,
+        left_col,
+        right_col,
+        cards: card_actions,
+        par: config.applied_card_count,
+    }
+}
+
+/// A card to replace one discarded from a match of `tiles`. `hand` holds the cards kept, and the
+/// new card never clashes with them.
+pub(crate) fn redraw_card(seed: u64, tiles: &[TileNature], hand: &[Action]) -> Action {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut card_kind_weights = CARD_KIND_WEIGHTS;
+    for card in hand {
+        match card {
+            Action::SwapTwoNatures { .. } => card_kind_weights[5] = 0,
+            Action::SwapAcross { .. } => card_kind_weights[2] = 0,
+            _ => (),
+        }
+    }
+    random_card(tiles, &mut card_kind_weights, &mut rng)
+}
+
+/// `count` new cards for decks dealt from with `config`, e.g. to start a deck with.
+pub(crate) fn random_cards(seed: u64, count: usize, config: &MatchConfig) -> Vec<Action> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    // The natures of a card are picked among some tiles, checked again when it is drawn.
+    let tiles_order = random_tiles(config.tiles_count, &mut rng);
+    let mut card_kind_weights = CARD_KIND_WEIGHTS;
+    (0..count)
+        .map(|_| random_card(&tiles_order, &mut card_kind_weights, &mut rng))
+        .collect()
+}
+
+// A card kept from an earlier match, made to fit columns of `tiles_order`: the board may have
+// had other tiles or more rows then. What it names that this board doesn't have is picked again
+// the way `random_card` picks it; everything else is kept.
+fn fit_card(card: Action, tiles_order: &[TileNature], rng: &mut impl Rng) -> Action {
+    let tiles_count = tiles_order.len();
+    match card {
+        Action::SwapTwoNatures {
+            nature_a,
+            nature_b,
+            side,
+        } if !tiles_order.contains(&nature_a) || !tiles_order.contains(&nature_b) => {
+            if tiles_count < 2 {
+                return Action::SwapFirstAndLast { side };
+            }
+            let (nature_a, nature_b) = random_nature_pair(tiles_order, rng);
+            Action::SwapTwoNatures {
+                nature_a,
+                nature_b,
+                side,
+            }
+        }
+        Action::SwapTwoAdjacent { top, side } if top + 1 >= tiles_count => {
+            if tiles_count < 2 {
+                return Action::SwapFirstAndLast { side };
+            }
+            Action::SwapTwoAdjacent {
+                top: rng.gen_range(0, tiles_count - 1),
+                side,
+            }
+        }
+        Action::Cycle {
+            times,
+            direction,
+            side,
+        } if times >= tiles_count => Action::Cycle {
+            times: rng.gen_range(1, tiles_count.max(2)),
+            direction,
+            side,
+        },
+        Action::SwapAcross { row } if row >= tiles_count => Action::SwapAcross {
+            row: rng.gen_range(0, tiles_count),
+        },
+        Action::MoveTo { from, to, side } if from >= tiles_count || to >= tiles_count => {
+            if tiles_count < 2 {
+                return Action::SwapFirstAndLast { side };
+            }
+            let from = rng.gen_range(0, tiles_count);
+            let to = rng.gen_range(0, tiles_count - 1);
+            Action::MoveTo {
+                from,
+                to: if to >= from { to + 1 } else { to },
+                side,
+            }
+        }
+        Action::SwapRows { a, b, side } if a >= tiles_count || b >= tiles_count => {
+            if tiles_count < 2 {
+                return Action::SwapFirstAndLast { side };
+            }
+            let mut rows = (0..tiles_count).collect::<Vec<_>>();
+            Action::SwapRows {
+                a: rows.swap_remove(rng.gen_range(0, rows.len())),
+                b: rows.swap_remove(rng.gen_range(0, rows.len())),
+                side,
+            }
+        }
+        card => card,
+    }
+}
+
+/// Like `deal`, but the hand is drawn from `deck` instead of made up. The puzzle is scrambled
+/// with the cards drawn, so it can still be solved.
+pub(crate) fn deal_from_deck(seed: u64, config: &MatchConfig, deck: &[Action]) -> Puzzle {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let tiles_order = random_tiles(config.tiles_count, &mut rng);
+    let mut pool = deck.to_vec();
+    let mut cards = Vec::new();
+    while cards.len() < config.card_count && !pool.is_empty() {
+        let card = pool.swap_remove(rng.gen_range(0, pool.len()));
+        let card = fit_card(card, &tiles_order, &mut rng);
+        // The same rule as `random_card`, the hand can't have both.
+        let clashes = cards.iter().any(|drawn| {
+            matches!(
+                (drawn, &card),
+                (Action::SwapTwoNatures { .. }, Action::SwapAcross { .. })
+                    | (Action::SwapAcross { .. }, Action::SwapTwoNatures { .. })
+            )
+        });
+        if !clashes {
+            cards.push(card);
+        }
+    }
+    let par = config.applied_card_count.min(cards.len());
+    let (left_col, right_col) = scramble(tiles_order, &cards, par, &mut rng);
+
+    Puzzle {
+        seed,
+        left_col,
+        right_col,
+        cards,
+        par,
+    }
+}
+
+/// The victory check: the match is won once both columns hold the same tiles in the same
+/// order.
+pub(crate) fn columns_match(left_col: &[TileNature], right_col: &[TileNature]) -> bool {
+    left_col == right_col
+}
+
+/// The fewest cards that need to be played, in some order, to win. Each card can only be
+/// played once.
+pub(crate) fn solve(puzzle: &Puzzle) -> Option<usize> {
+    shortest_solution(&puzzle.left_col, &puzzle.right_col, &puzzle.cards, 0)
+        .map(|solution| solution.len())
+}
+
+/// Every action a card can be played as: a wildcard can be any swap in either column of
+/// `tiles_count` tiles, any other card only itself.
+pub(crate) fn resolutions(action: &Action, tiles_count: usize) -> Vec<Action> {
+    match action {
+        Action::Wildcard => [TileSide::Left, TileSide::Right]
+            .iter()
+            .flat_map(|side| {
+                (0..tiles_count).flat_map(move |a| {
+                    (a + 1..tiles_count).map(move |b| Action::SwapRows { a, b, side: *side })
+                })
+            })
+            .collect(),
+        action => vec![*action],
+    }
+}
+
+/// How many positions `shortest_solution` looks at before giving up, so a hint never stalls a
+/// frame for long. Dealt hands take at most around 10,000; only hands of many wildcards, which
+/// can reach most orders of the columns, get near it.
+const MAX_SEARCH_STATES: usize = 50_000;
+
+/// The indices in `cards`, in playing order, of a shortest way to make the columns match
+/// without the cards whose bit is set in `used`. `None` when there is none, or when finding out
+/// would take more than `MAX_SEARCH_STATES` positions.
+pub(crate) fn shortest_solution(
+    left_col: &[TileNature],
+    right_col: &[TileNature],
+    cards: &[Action],
+    used: u32,
+) -> Option<Vec<usize>> {
+    // Breadth first over the sequences of played cards, so the first win found is the shortest.
+    // Playing the same cards in a different order often leads to the same columns, and from
+    // there on to the same wins, so each position is only searched from once.
+    let mut visited = HashSet::new();
+    visited.insert((used, left_col.to_vec(), right_col.to_vec()));
+    let mut frontier = vec![(used, Vec::new(), left_col.to_vec(), right_col.to_vec())];
+    for _ in 0..=cards.len() {
+        if let Some((_, played, _, _)) = frontier
+            .iter()
+            .find(|(_, _, left_col, right_col)| columns_match(left_col, right_col))
+        {
+            return Some(played.clone());
+        }
+        let mut next = Vec::new();
+        for (used, played, left_col, right_col) in frontier.iter() {
+            for (i, action) in cards.iter().enumerate() {
+                if used & (1 << i) != 0 {
+                    continue;
+                }
+                for action in resolutions(action, left_col.len()) {
+                    let mut left_col = left_col.clone();
+                    let mut right_col = right_col.clone();
+                    apply_action(&action, &mut left_col, &mut right_col, Box::new(|x| *x));
+                    let used = used | (1 << i);
+                    if !visited.insert((used, left_col.clone(), right_col.clone())) {
+                        continue;
+                    }
+                    if visited.len() > MAX_SEARCH_STATES {
+                        return None;
+                    }
+                    let mut played = played.clone();
+                    played.push(i);
+                    next.push((used, played, left_col, right_col));
+                }
+            }
+        }
+        frontier = next;
+    }
+    None
+}
+
+/// The index in `cards` of the first card of `shortest_solution`, with the action to play it as:
+/// a wildcard is played as a swap that keeps the rest of the way as short.
+pub(crate) fn first_play(
+    left_col: &[TileNature],
+    right_col: &[TileNature],
+    cards: &[Action],
+    used: u32,
+) -> Option<(usize, Action)> {
+    let solution = shortest_solution(left_col, right_col, cards, used)?;
+    let (card, rest) = solution.split_first()?;
+    let used = used | (1 << card);
+    resolutions(&cards[*card], left_col.len())
+        .into_iter()
+        .find(|action| {
+            let (mut left_col, mut right_col) = (left_col.to_vec(), right_col.to_vec());
+            apply_action(action, &mut left_col, &mut right_col, Box::new(|x| *x));
+            shortest_solution(&left_col, &right_col, cards, used).map(|tail| tail.len())
+                == Some(rest.len())
+        })
+        .map(|action| (*card, action))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two columns of the same `tiles_count` natures, in different orders.
+    fn columns(tiles_count: usize) -> (Vec<TileNature>, Vec<TileNature>) {
+        let left_col = (0..tiles_count).map(TileNature).collect::<Vec<_>>();
+        let mut right_col = left_col.clone();
+        right_col.rotate_left(tiles_count / 2);
+        (left_col, right_col)
+    }
+
+    // Every action that can be played on columns of `tiles_count` tiles, cycles of a whole
+    // column round or more included.
+    fn every_action(tiles_count: usize) -> Vec<Action> {
+        let mut actions = vec![Action::Wildcard];
+        for row in 0..tiles_count {
+            actions.push(Action::SwapAcross { row });
+        }
+        for side in [TileSide::Left, TileSide::Right] {
+            actions.push(Action::SwapFirstAndLast { side });
+            actions.push(Action::Reverse { side });
+            for top in 0..tiles_count.saturating_sub(1) {
+                actions.push(Action::SwapTwoAdjacent { top, side });
+            }
+            for times in 0..=tiles_count * 2 + 1 {
+                for direction in [CycleDirection::Up, CycleDirection::Down] {
+                    actions.push(Action::Cycle {
+                        times,
+                        direction,
+                        side,
+                    });
+                }
+            }
+            for a in 0..tiles_count {
+                for b in 0..tiles_count {
+                    actions.push(Action::MoveTo {
+                        from: a,
+                        to: b,
+                        side,
+                    });
+                    if a < b {
+                        actions.push(Action::SwapRows { a, b, side });
+                        actions.push(Action::SwapTwoNatures {
+                            nature_a: TileNature(a),
+                            nature_b: TileNature(b),
+                            side,
+                        });
+                    }
+                }
+            }
+        }
+        actions
+    }
+
+    fn apply(action: &Action, left_col: &mut Vec<TileNature>, right_col: &mut Vec<TileNature>) {
+        apply_action(action, left_col, right_col, Box::new(|x| *x));
+    }
+
+    fn apply_inverse(
+        action: &Action,
+        left_col: &mut Vec<TileNature>,
+        right_col: &mut Vec<TileNature>,
+    ) {
+        apply_inverse_action(action, left_col, right_col, Box::new(|x| *x));
+    }
+
+    #[test]
+    fn inverse_undoes_every_action() {
+        for tiles_count in 1..=nature_count() {
+            let (left_col, right_col) = columns(tiles_count);
+            for action in every_action(tiles_count) {
+                let (mut left, mut right) = (left_col.clone(), right_col.clone());
+                apply(&action, &mut left, &mut right);
+                apply_inverse(&action, &mut left, &mut right);
+                assert_eq!((&left, &right), (&left_col, &right_col));
+                assert_eq!(left.len(), tiles_count);
+            }
+        }
+    }
+
+    #[test]
+    fn action_undoes_every_inverse() {
+        for tiles_count in 1..=nature_count() {
+            let (left_col, right_col) = columns(tiles_count);
+            for action in every_action(tiles_count) {
+                let (mut left, mut right) = (left_col.clone(), right_col.clone());
+                apply_inverse(&action, &mut left, &mut right);
+                apply(&action, &mut left, &mut right);
+                assert_eq!((&left, &right), (&left_col, &right_col));
+            }
+        }
+    }
+
+    #[test]
+    fn cycling_a_whole_column_round_wraps() {
+        let (left_col, right_col) = columns(5);
+        for direction in [CycleDirection::Up, CycleDirection::Down] {
+            let (mut left, mut right) = (left_col.clone(), right_col.clone());
+            let (mut wrapped_left, mut wrapped_right) = (left_col.clone(), right_col.clone());
+            apply(
+                &Action::Cycle {
+                    times: 2,
+                    direction,
+                    side: TileSide::Left,
+                },
+                &mut left,
+                &mut right,
+            );
+            apply(
+                &Action::Cycle {
+                    times: 12,
+                    direction,
+                    side: TileSide::Left,
+                },
+                &mut wrapped_left,
+                &mut wrapped_right,
+            );
+            assert_eq!(left, wrapped_left);
+            assert_ne!(left, left_col);
+        }
+    }
+
+    #[test]
+    fn random_sequences_are_undone_backwards() {
+        let mut rng = StdRng::seed_from_u64(2273);
+        for _ in 0..500 {
+            let tiles_count = rng.gen_range(1, nature_count() + 1);
+            let tiles_order = random_tiles(tiles_count, &mut rng);
+            let mut card_kind_weights = CARD_KIND_WEIGHTS;
+            let sequence = (0..rng.gen_range(1, 12))
+                .map(|_| {
+                    let card = random_card(&tiles_order, &mut card_kind_weights, &mut rng);
+                    let mut actions = resolutions(&card, tiles_count);
+                    if actions.is_empty() {
+                        card
+                    } else {
+                        actions.swap_remove(rng.gen_range(0, actions.len()))
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let (mut left, mut right) = (tiles_order.clone(), tiles_order.clone());
+            for action in sequence.iter() {
+                apply(action, &mut left, &mut right);
+            }
+            for action in sequence.iter().rev() {
+                apply_inverse(action, &mut left, &mut right);
+            }
+            assert_eq!(left, tiles_order);
+            assert_eq!(right, tiles_order);
+        }
+    }
+
+    #[test]
+    fn dealt_puzzles_are_solved_within_par() {
+        for tiles_count in MIN_BOARD_ROWS..=6 {
+            let config = MatchConfig {
+                tiles_count,
+                ..Default::default()
+            };
+            for seed in 0..20 {
+                let puzzle = deal(seed, &config);
+                let solution =
+                    shortest_solution(&puzzle.left_col, &puzzle.right_col, &puzzle.cards, 0)
+                        .expect("a dealt puzzle can be solved");
+                assert!(solution.len() <= puzzle.par);
+                assert_eq!(solve(&puzzle), Some(solution.len()));
+
+                // Wildcards are played as whichever swap the search picked, so replay it.
+                let (mut left, mut right) = (puzzle.left_col.clone(), puzzle.right_col.clone());
+                let mut remaining = solution.as_slice();
+                while let Some((card, rest)) = remaining.split_first() {
+                    let played = resolutions(&puzzle.cards[*card], left.len())
+                        .into_iter()
+                        .find(|action| {
+                            let (mut l, mut r) = (left.clone(), right.clone());
+                            apply(action, &mut l, &mut r);
+                            let used = solution[..solution.len() - rest.len()]
+                                .iter()
+                                .fold(0, |used, i| used | (1 << i));
+                            shortest_solution(&l, &r, &puzzle.cards, used)
+                                .map_or(false, |tail| tail.len() == rest.len())
+                        })
+                        .expect("the solution can be replayed");
+                    apply(&played, &mut left, &mut right);
+                    remaining = rest;
+                }
+                assert!(columns_match(&left, &right));
+            }
+        }
+    }
+
+    #[test]
+    fn an_8_row_deck_fits_a_3_row_board() {
+        let deck = random_cards(
+            2291,
+            60,
+            &MatchConfig {
+                tiles_count: 8,
+                ..Default::default()
+            },
+        );
+        let config = MatchConfig {
+            tiles_count: 3,
+            ..Default::default()
+        };
+        for seed in 0..50 {
+            let puzzle = deal_from_deck(seed, &config, &deck);
+            assert_eq!(puzzle.left_col.len(), 3);
+            for card in puzzle.cards.iter() {
+                for action in resolutions(card, 3) {
+                    let (mut left, mut right) = (puzzle.left_col.clone(), puzzle.right_col.clone());
+                    apply(&action, &mut left, &mut right);
+                    apply_inverse(&action, &mut left, &mut right);
+                    assert_eq!(
+                        (left, right),
+                        (puzzle.left_col.clone(), puzzle.right_col.clone())
+                    );
+                }
+            }
+            let solution = solve(&puzzle).expect("a puzzle dealt from a deck can be solved");
+            assert!(solution <= puzzle.par);
+        }
+    }
+
+    #[test]
+    fn hopeless_hands_of_wildcards_are_given_up_on() {
+        // The columns hold different tiles, so no swaps can make them match, and a dozen
+        // wildcards could be played in more orders than can be searched.
+        let left_col = (0..8).map(TileNature).collect::<Vec<_>>();
+        let mut right_col = left_col.clone();
+        right_col[0] = TileNature(1);
+        let cards = vec![Action::Wildcard; 12];
+        assert_eq!(shortest_solution(&left_col, &right_col, &cards, 0), None);
+    }
+
+    #[test]
+    fn first_plays_win_in_the_fewest_cards() {
+        let (left_col, right_col) = columns(4);
+        let puzzles = (0..20)
+            .map(|seed| deal(seed, &MatchConfig::default()))
+            .map(|puzzle| (puzzle.left_col, puzzle.right_col, puzzle.cards))
+            .chain([(left_col, right_col, vec![Action::Wildcard; 3])]);
+        for (mut left_col, mut right_col, cards) in puzzles {
+            let shortest = shortest_solution(&left_col, &right_col, &cards, 0)
+                .expect("the puzzle can be solved")
+                .len();
+            let mut used = 0;
+            for _ in 0..shortest {
+                let (card, action) = first_play(&left_col, &right_col, &cards, used)
+                    .expect("a play is found until the columns match");
+                assert_eq!(used & (1 << card), 0);
+                assert!(!matches!(action, Action::Wildcard));
+                apply(&action, &mut left_col, &mut right_col);
+                used |= 1 << card;
+            }
+            assert!(columns_match(&left_col, &right_col));
+            assert!(first_play(&left_col, &right_col, &cards, used).is_none());
+        }
+    }
+}
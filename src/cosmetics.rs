@@ -0,0 +1,391 @@
+//! Cosmetics the player unlocks by winning: card backs, and tile packs whose manifest asks for
+//! an unlock. They are picked from the customization screen, opened from the main menu.
+
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    match_plugin::MatchEnding, modal::Modal, profile::PlayerProfile, settings::Settings,
+    theme::Theme, toast::ShowToast,
+};
+
+/// What the player's profile needs for a cosmetic to be unlocked.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Unlock {
+    /// Matches won, over every match played.
+    Wins(u32),
+    /// Matches won in a row, at best.
+    WinStreak(u32),
+}
+
+impl Unlock {
+    pub fn is_met(self, profile: &PlayerProfile) -> bool {
+        match self {
+            Unlock::Wins(wins) => profile.wins >= wins,
+            Unlock::WinStreak(streak) => profile.best_win_streak >= streak,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Unlock::Wins(1) => "win a match".to_string(),
+            Unlock::Wins(wins) => format!("win {} matches", wins),
+            Unlock::WinStreak(streak) => format!("win {} matches in a row", streak),
+        }
+    }
+}
+
+/// A tint of the card background.
+pub struct CardBack {
+    pub name: &'static str,
+    pub color: Color,
+    pub unlock: Option<Unlock>,
+}
+
+impl CardBack {
+    pub fn is_unlocked(&self, profile: &PlayerProfile) -> bool {
+        self.unlock.iter().all(|unlock| unlock.is_met(profile))
+    }
+}
+
+/// The classic card back comes first, and is always unlocked.
+pub const CARD_BACKS: [CardBack; 4] = [
+    CardBack {
+        name: "Classic",
+        color: Color::WHITE,
+        unlock: None,
+    },
+    CardBack {
+        name: "Sage",
+        color: Color::rgb(0.78, 0.9, 0.76),
+        unlock: Some(Unlock::Wins(5)),
+    },
+    CardBack {
+        name: "Dusk",
+        color: Color::rgb(0.78, 0.76, 0.95),
+        unlock: Some(Unlock::Wins(25)),
+    },
+    CardBack {
+        name: "Gold",
+        color: Color::rgb(1., 0.86, 0.45),
+        unlock: Some(Unlock::WinStreak(5)),
+    },
+];
+
+// Not a system! The next of `count` entries after `current`, or the previous one, going round
+// and skipping the locked ones. Stays on `current` when every other entry is locked.
+pub(crate) fn step_unlocked(
+    current: usize,
+    count: usize,
+    forward: bool,
+    is_unlocked: impl Fn(usize) -> bool,
+) -> usize {
+    let mut next = current;
+    loop {
+        next = if forward {
+            (next + 1) % count
+        } else {
+            (next + count - 1) % count
+        };
+        if next == current || is_unlocked(next) {
+            return next;
+        }
+    }
+}
+
+// The cosmetics unlocked so far, as the unlock toast names them.
+fn unlocked_names(theme: &Theme, profile: &PlayerProfile) -> Vec<String> {
+    let card_backs = CARD_BACKS
+        .iter()
+        .filter(|back| back.unlock.is_some() && back.is_unlocked(profile))
+        .map(|back| format!("the {} card back", back.name));
+    let tile_packs = theme
+        .tile_packs
+        .iter()
+        .filter(|pack| pack.unlock.is_some() && pack.is_unlocked(profile))
+        .map(|pack| format!("the {} tiles", pack.name));
+    card_backs.chain(tile_packs).collect()
+}
+
+// Runs after the match result is recorded, and compares against the cosmetics unlocked before
+// it, so the ones unlocked in earlier sessions aren't announced again.
+pub(crate) fn announce_unlocks(
+    profile: Res<PlayerProfile>,
+    theme: Res<Theme>,
+    q_ending: Query<(), Added<MatchEnding>>,
+    mut known: Local<Option<Vec<String>>>,
+    mut ev_toast: EventWriter<ShowToast>,
+) {
+    if known.is_some() && q_ending.is_empty() {
+        return;
+    }
+    let unlocked = unlocked_names(&theme, &profile);
+    if let Some(known) = known.as_ref() {
+        for name in unlocked.iter().filter(|name| !known.contains(name)) {
+            ev_toast.send(ShowToast(format!("Unlocked {}, see Customize", name)));
+        }
+    }
+    *known = Some(unlocked);
+}
+
+// Runs after the tile packs are loaded, so the saved one can be found by name. A hand-edited
+// settings file doesn't unlock anything.
+pub(crate) fn apply_saved_cosmetics(
+    settings: Res<Settings>,
+    profile: Res<PlayerProfile>,
+    mut theme: ResMut<Theme>,
+) {
+    theme.tile_pack = settings.tile_pack.as_ref().and_then(|name| {
+        match theme.tile_packs.iter().position(|pack| &pack.name == name) {
+            Some(i) if theme.tile_packs[i].is_unlocked(&profile) => return Some(i),
+            Some(_) => warn!("Tile pack {:?} is locked, using the built-in tiles", name),
+            None => warn!("Tile pack {:?} is gone, using the built-in tiles", name),
+        }
+        None
+    });
+    theme.card_back = settings.card_back.as_ref().map_or(0, |name| {
+        match CARD_BACKS.iter().position(|back| back.name == *name) {
+            Some(i) if CARD_BACKS[i].is_unlocked(&profile) => return i,
+            Some(_) => warn!("Card back {:?} is locked, using the classic one", name),
+            None => warn!("Card back {:?} is gone, using the classic one", name),
+        }
+        0
+    });
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum CustomizeRow {
+    TilePack,
+    CardBack,
+}
+
+impl CustomizeRow {
+    const ALL: [CustomizeRow; 2] = [CustomizeRow::TilePack, CustomizeRow::CardBack];
+
+    fn label(self) -> &'static str {
+        match self {
+            CustomizeRow::TilePack => "Tiles",
+            CustomizeRow::CardBack => "Card back",
+        }
+    }
+}
+
+/// The customization screen, opened from the main menu. Only unlocked cosmetics can be picked,
+/// the locked ones are listed with what unlocks them.
+#[derive(Default)]
+pub struct CustomizeScreen {
+    pub open: bool,
+    selected: usize,
+}
+
+impl CustomizeScreen {
+    /// Send `UpdateCustomizeScreen` to draw it.
+    pub fn show(&mut self) {
+        *self = CustomizeScreen {
+            open: true,
+            ..Default::default()
+        };
+    }
+}
+
+pub struct UpdateCustomizeScreen;
+
+#[derive(Component)]
+pub struct CustomizeScreenUi;
+
+#[derive(Component)]
+pub struct CustomizeScreenText;
+
+/// The cosmetics in use, and the profile unlocking the others.
+#[derive(SystemParam)]
+pub(crate) struct Cosmetics<'w, 's> {
+    theme: Res<'w, Theme>,
+    profile: Res<'w, PlayerProfile>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+fn customize_screen_text(customize_screen: &CustomizeScreen, cosmetics: &Cosmetics) -> String {
+    let Cosmetics { theme, profile, .. } = cosmetics;
+    let mut lines = vec!["Customize".to_string(), String::new()];
+    for (i, row) in CustomizeRow::ALL.iter().enumerate() {
+        let value = match row {
+            CustomizeRow::TilePack => format!("{}, from the next match", theme.tile_pack_name()),
+            CustomizeRow::CardBack => theme.card_back().name.to_string(),
+        };
+        lines.push(format!(
+            "{} {}: {}",
+            if i == customize_screen.selected {
+                ">"
+            } else {
+                " "
+            },
+            row.label(),
+            value
+        ));
+    }
+
+    let locked_card_backs = CARD_BACKS.iter().filter_map(|back| match back.unlock {
+        Some(unlock) if !unlock.is_met(profile) => {
+            Some(format!("{} card back: {}", back.name, unlock.label()))
+        }
+        _ => None,
+    });
+    let locked_tile_packs = theme
+        .tile_packs
+        .iter()
+        .filter_map(|pack| match pack.unlock {
+            Some(unlock) if !unlock.is_met(profile) => {
+                Some(format!("{} tiles: {}", pack.name, unlock.label()))
+            }
+            _ => None,
+        });
+    let locked = locked_card_backs
+        .chain(locked_tile_packs)
+        .collect::<Vec<_>>();
+    if !locked.is_empty() {
+        lines.push(String::new());
+        lines.push("Locked".to_string());
+        lines.extend(locked);
+    }
+
+    lines.push(String::new());
+    lines.push("Up/Down select · Left/Right change · Esc close".to_string());
+    lines.join("\n")
+}
+
+// Runs before the main menu, so the keys it uses up aren't seen by it.
+pub(crate) fn customize_screen_input(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut customize_screen: ResMut<CustomizeScreen>,
+    mut theme: ResMut<Theme>,
+    profile: Res<PlayerProfile>,
+    modal: Res<Modal>,
+    mut ev_update: EventWriter<UpdateCustomizeScreen>,
+) {
+    if !customize_screen.open || modal.is_open() {
+        return;
+    }
+
+    let row = CustomizeRow::ALL[customize_screen.selected];
+    let forward = if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        customize_screen.open = false;
+        None
+    } else if keyboard_input.just_pressed(KeyCode::Up) {
+        customize_screen.selected =
+            (customize_screen.selected + CustomizeRow::ALL.len() - 1) % CustomizeRow::ALL.len();
+        None
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        customize_screen.selected = (customize_screen.selected + 1) % CustomizeRow::ALL.len();
+        None
+    } else if keyboard_input.just_pressed(KeyCode::Left) {
+        Some(false)
+    } else if keyboard_input.just_pressed(KeyCode::Right)
+        || keyboard_input.clear_just_pressed(KeyCode::Return)
+    {
+        Some(true)
+    } else {
+        return;
+    };
+    match (row, forward) {
+        (CustomizeRow::TilePack, Some(forward)) => theme.step_tile_pack(forward, &profile),
+        (CustomizeRow::CardBack, Some(forward)) => theme.step_card_back(forward, &profile),
+        (_, None) => (),
+    }
+    ev_update.send(UpdateCustomizeScreen);
+}
+
+pub(crate) fn update_customize_screen(
+    mut ev_update: EventReader<UpdateCustomizeScreen>,
+    customize_screen: Res<CustomizeScreen>,
+    cosmetics: Cosmetics,
+    q_ui: Query<Entity, With<CustomizeScreenUi>>,
+    mut q_text: Query<&mut Text, With<CustomizeScreenText>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if ev_update.iter().count() == 0 {
+        return;
+    }
+
+    if !customize_screen.open {
+        for e in q_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+
+    let value = customize_screen_text(&customize_screen, &cosmetics);
+    if let Ok(mut text) = q_text.get_single_mut() {
+        text.sections[0].value = value;
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 78.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: Color::rgba(0., 0., 0., 0.8),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(CustomizeScreenUi);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                value,
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 30.,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., 0., 79.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(CustomizeScreenUi)
+        .insert(CustomizeScreenText);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stepping_skips_locked_entries() {
+        let unlocked = [true, false, true, false];
+        let is_unlocked = |i: usize| unlocked[i];
+        assert_eq!(step_unlocked(0, 4, true, is_unlocked), 2);
+        assert_eq!(step_unlocked(2, 4, true, is_unlocked), 0);
+        assert_eq!(step_unlocked(0, 4, false, is_unlocked), 2);
+        assert_eq!(step_unlocked(0, 4, true, |i| i == 0), 0);
+    }
+
+    #[test]
+    fn streaks_unlock_from_the_best_one() {
+        let profile = PlayerProfile {
+            wins: 7,
+            win_streak: 0,
+            best_win_streak: 5,
+            ..Default::default()
+        };
+        assert!(Unlock::Wins(5).is_met(&profile));
+        assert!(!Unlock::Wins(25).is_met(&profile));
+        assert!(Unlock::WinStreak(5).is_met(&profile));
+        assert!(!Unlock::WinStreak(6).is_met(&profile));
+    }
+}
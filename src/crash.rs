@@ -0,0 +1,229 @@
+use std::{
+    collections::VecDeque,
+    panic,
+    sync::{Arc, Mutex, TryLockError},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    core::{action_caption, nature_name, TileNature, TileSide},
+    match_plugin::{
+        tile_columns, Card, CardPlayed, MatchState, MatchWon, RestartRequest, Slot,
+        StartMatchEvent, Tile, TileData, Used,
+    },
+    modal::{ModalAction, ModalButton, ModalResult, OpenModal},
+    storage,
+};
+
+const CRASH_LOG_PATH: &str = "crash.log";
+// Where a crash log the player chose to keep is moved, so it isn't offered again on every launch.
+const SEEN_CRASH_LOG_PATH: &str = "crash.seen.log";
+// How many of the latest gameplay events end up in the crash log.
+const RECENT_EVENTS: usize = 30;
+
+#[derive(Default)]
+struct CrashContext {
+    match_summary: String,
+    recent_events: VecDeque<String>,
+}
+
+/// What the panic hook writes to the crash log besides the panic itself. Systems keep it up to
+/// date while the game runs.
+pub struct CrashReporter(Arc<Mutex<CrashContext>>);
+
+impl CrashReporter {
+    /// Installs the panic hook. The default hook still runs afterwards, so panics are printed as
+    /// usual.
+    pub fn install() -> Self {
+        let context = Arc::new(Mutex::new(CrashContext::default()));
+        let hook_context = context.clone();
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let mut report = format!("{}\n", info);
+            // The panic may have happened while a system was holding the lock.
+            match hook_context.try_lock() {
+                Ok(context) => write_context(&mut report, &context),
+                Err(TryLockError::Poisoned(poisoned)) => {
+                    write_context(&mut report, &poisoned.into_inner())
+                }
+                Err(TryLockError::WouldBlock) => report.push_str("\n(match state unavailable)\n"),
+            }
+            if let Err(err) = storage::write(CRASH_LOG_PATH, &report) {
+                eprintln!("Could not write {}: {}", CRASH_LOG_PATH, err);
+            }
+            default_hook(info);
+        }));
+        Self(context)
+    }
+
+    fn record_event(&self, event: String) {
+        if let Ok(mut context) = self.0.lock() {
+            if context.recent_events.len() == RECENT_EVENTS {
+                context.recent_events.pop_front();
+            }
+            context.recent_events.push_back(event);
+        }
+    }
+}
+
+fn write_context(report: &mut String, context: &CrashContext) {
+    report.push_str("\nMatch:\n");
+    report.push_str(&context.match_summary);
+    report.push_str("\nRecent events:\n");
+    for event in context.recent_events.iter() {
+        report.push_str(event);
+        report.push('\n');
+    }
+}
+
+/// Keeps the crash log's context up to date, and offers the log left by a crash on the next
+/// launch. The panic hook itself is installed before the app is built.
+pub(crate) struct CrashPlugin;
+
+impl Plugin for CrashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(report_previous_crash)
+            .add_system(record_events)
+            .add_system(handle_crash_log)
+            .add_system_to_stage("view", record_match_summary);
+    }
+}
+
+pub(crate) fn record_match_summary(
+    match_state: Res<MatchState>,
+    reporter: Res<CrashReporter>,
+    q_tiles: Query<(Entity, &TileSide, &Slot, &TileNature), With<Tile>>,
+    q_used: Query<(), (With<Card>, With<Used>)>,
+) {
+    if !match_state.is_changed() {
+        return;
+    }
+
+    let summary = match match_state.as_ref() {
+        MatchState::Ready => "Not playing\n".to_string(),
+        MatchState::Finished(result) => format!(
+            "Finished, {} with {} cards\n",
+            if result.won { "won" } else { "out of cards" },
+            result.cards_used
+        ),
+        MatchState::Playing(match_state) => {
+            let (left_col, right_col) = tile_columns(
+                q_tiles
+                    .iter()
+                    .map(|(id, side, slot, nature)| (id, *side, *slot, *nature)),
+            );
+            let col_text = |col: &[TileData]| {
+                col.iter()
+                    .map(|tile| nature_name(&tile.nature))
+                    .collect::<String>()
+            };
+            format!(
+                "Seed: {}\nLeft column: {}\nRight column: {}\nUsed cards: {}/{}\nHovered card slot: {:?}\n",
+                match_state.seed,
+                col_text(&left_col),
+                col_text(&right_col),
+                q_used.iter().count(),
+                match_state.hand_order.len(),
+                match_state.hovered_card.map(|card| match_state.hand_slot(card)),
+            )
+        }
+    };
+    if let Ok(mut context) = reporter.0.lock() {
+        context.match_summary = summary;
+    }
+}
+
+pub(crate) fn record_events(
+    reporter: Res<CrashReporter>,
+    time: Res<Time>,
+    mut ev_start_match: EventReader<StartMatchEvent>,
+    mut ev_card_played: EventReader<CardPlayed>,
+    mut ev_restart: EventReader<RestartRequest>,
+    mut ev_match_won: EventReader<MatchWon>,
+    mut ev_modal_result: EventReader<ModalResult>,
+) {
+    let now = time.seconds_since_startup();
+    let record = |event: String| reporter.record_event(format!("[{:.2}] {}", now, event));
+    for _ in ev_start_match.iter() {
+        record("Match started".to_string());
+    }
+    for CardPlayed { action, order, .. } in ev_card_played.iter() {
+        record(format!(
+            "Card {} played: {}",
+            order + 1,
+            action_caption(action)
+        ));
+    }
+    for _ in ev_restart.iter() {
+        record("Match restarted".to_string());
+    }
+    for _ in ev_match_won.iter() {
+        record("Match won".to_string());
+    }
+    for ModalResult(action) in ev_modal_result.iter() {
+        record(format!("Modal closed: {:?}", action));
+    }
+}
+
+// Where the player can find a file, in full so it can be copied from the dialog.
+#[cfg(not(target_arch = "wasm32"))]
+fn full_path(path: &str) -> String {
+    std::env::current_dir()
+        .map(|dir| dir.join(path).display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn full_path(path: &str) -> String {
+    format!("the page's LocalStorage, under {}", path)
+}
+
+// If the last run left a crash log behind, tell the player where it is. The report also goes to
+// the log, to be copied from the console.
+pub fn report_previous_crash(mut ev_open_modal: EventWriter<OpenModal>) {
+    let report = match storage::read(CRASH_LOG_PATH) {
+        Ok(report) => report,
+        Err(_) => return,
+    };
+    info!("Crash report from the last run:\n{}", report);
+    ev_open_modal.send(OpenModal {
+        title: "The game crashed last time".to_string(),
+        body: format!(
+            "A crash report was saved to {}.\nPlease attach it when reporting the problem.\nIf kept, it is moved to {}.",
+            full_path(CRASH_LOG_PATH),
+            SEEN_CRASH_LOG_PATH
+        ),
+        buttons: vec![
+            ModalButton {
+                label: "Delete report".to_string(),
+                action: ModalAction::DeleteCrashLog,
+            },
+            ModalButton {
+                label: "Keep".to_string(),
+                action: ModalAction::KeepCrashLog,
+            },
+        ],
+    });
+}
+
+// Either way the report has been seen, so it isn't offered again on the next launch.
+pub fn handle_crash_log(mut ev_result: EventReader<ModalResult>) {
+    for ModalResult(action) in ev_result.iter() {
+        match action {
+            ModalAction::DeleteCrashLog => {
+                if let Err(err) = storage::remove(CRASH_LOG_PATH) {
+                    warn!("Could not delete {}: {}", CRASH_LOG_PATH, err);
+                }
+            }
+            ModalAction::KeepCrashLog => {
+                if let Err(err) = storage::rename(CRASH_LOG_PATH, SEEN_CRASH_LOG_PATH) {
+                    warn!("Could not move {}: {}", CRASH_LOG_PATH, err);
+                } else {
+                    info!("Crash report kept at {}", full_path(SEEN_CRASH_LOG_PATH));
+                }
+            }
+            ModalAction::Quit | ModalAction::AbandonMatch | ModalAction::Dismiss => (),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use bevy::{
+    ecs::system::SystemParam,
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+use jam_motion::{
+    friction::{FrictionDescription, FrictionSimulation},
+    Simulation,
+};
+
+use crate::{controls::ControlsMenu, modal::Modal};
+
+const CREDITS: &str = "ggj-2022
+
+Made for Global Game Jam 2022
+
+Design, code and art
+Marco Moroni
+
+Font
+Readex Pro
+Copyright 2020 The Readex Pro Project Authors
+SIL Open Font License 1.1
+
+Built with
+Bevy (MIT or Apache-2.0)
+rand (MIT or Apache-2.0)
+serde and RON (MIT or Apache-2.0)
+
+Thanks for playing!";
+
+const CREDITS_FONT_SIZE: f32 = 26.;
+// Rough height of a line of text, used to know when the credits have scrolled past.
+const CREDITS_LINE_HEIGHT: f32 = 34.;
+// Where the top of the text starts, just below the bottom of the screen.
+const CREDITS_START_Y: f32 = -700.;
+const CREDITS_END_Y: f32 = 700.;
+const AUTO_SCROLL_SPEED: f32 = 40.;
+const KEY_SCROLL_SPEED: f32 = 400.;
+const WHEEL_LINE_HEIGHT: f32 = 40.;
+// Share of a fling's velocity left after a second.
+const FLING_FRICTION: f32 = 0.05;
+// Slower flings than this, in units per second, just stop where they are.
+const FLING_MIN_SPEED: f32 = 50.;
+
+/// The credits screen. The text scrolls up on its own and can be dragged and flung with the
+/// mouse or a finger, the fling slowing down with friction.
+#[derive(Default)]
+pub struct Credits {
+    pub open: bool,
+    scroll: f32,
+    dragging: bool,
+    // Smoothed drag velocity, handed to the fling on release.
+    drag_velocity: f32,
+    fling: Option<(FrictionSimulation, f32)>,
+}
+
+#[derive(Component)]
+pub struct CreditsUi;
+
+#[derive(Component)]
+pub struct CreditsText;
+
+fn credits_height() -> f32 {
+    CREDITS.lines().count() as f32 * CREDITS_LINE_HEIGHT
+}
+
+/// F12 opens the credits, Esc or F12 closes them.
+pub fn toggle_credits(
+    keyboard_input: Res<Input<KeyCode>>,
+    controls_menu: Res<ControlsMenu>,
+    modal: Res<Modal>,
+    mut credits: ResMut<Credits>,
+    q_ui: Query<Entity, With<CreditsUi>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    let toggle = keyboard_input.just_pressed(KeyCode::F12);
+    let close = keyboard_input.just_pressed(KeyCode::Escape);
+    if credits.open && (toggle || close) {
+        *credits = Credits::default();
+        for e in q_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+    } else if !credits.open && toggle && !controls_menu.open && !modal.is_open() {
+        *credits = Credits {
+            open: true,
+            ..Default::default()
+        };
+        commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., 0., 90.),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(4000., 4000.)),
+                    color: Color::rgba(0., 0., 0., 0.9),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(CreditsUi);
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    CREDITS,
+                    TextStyle {
+                        font: asset_server.load("ReadexPro-Regular.ttf"),
+                        font_size: CREDITS_FONT_SIZE,
+                        color: Color::WHITE,
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Top,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                ),
+                transform: Transform {
+                    translation: Vec3::new(0., CREDITS_START_Y, 91.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(CreditsUi)
+            .insert(CreditsText);
+    }
+}
+
+/// Dragging up and down with the mouse or a finger.
+#[derive(SystemParam)]
+pub struct VerticalDrag<'w, 's> {
+    mouse_input: Res<'w, Input<MouseButton>>,
+    touches: Res<'w, Touches>,
+    ev_cursor_moved: EventReader<'w, 's, CursorMoved>,
+    last_cursor_y: Local<'s, Option<f32>>,
+}
+
+impl VerticalDrag<'_, '_> {
+    // How far the pointer held down moved this frame. Called every frame, to keep track of the
+    // cursor even when there is nothing to drag.
+    fn delta(&mut self) -> Option<f32> {
+        let cursor_y = self.ev_cursor_moved.iter().last().map(|ev| ev.position.y);
+
+        // Dragging follows the pointer exactly. Window and touch positions are in logical
+        // pixels, which the 2D camera maps one to one, but touch positions grow downwards.
+        let mut drag = None;
+        if self.mouse_input.pressed(MouseButton::Left) {
+            if let (Some(from), Some(to)) = (*self.last_cursor_y, cursor_y) {
+                drag = Some(to - from);
+            } else if !self.mouse_input.just_pressed(MouseButton::Left) {
+                drag = Some(0.);
+            }
+        }
+        if let Some(touch) = self.touches.iter().next() {
+            drag = Some(drag.unwrap_or(0.) - touch.delta().y);
+        }
+        *self.last_cursor_y = cursor_y.or(*self.last_cursor_y);
+        drag
+    }
+}
+
+pub fn scroll_credits(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut dragging: VerticalDrag,
+    mut ev_mouse_wheel: EventReader<MouseWheel>,
+    mut credits: ResMut<Credits>,
+    mut q_text: Query<&mut Transform, With<CreditsText>>,
+) {
+    let drag = dragging.delta();
+    if !credits.open {
+        return;
+    }
+
+    let now = time.seconds_since_startup() as f32;
+    let dt = time.delta_seconds();
+
+    let credits = credits.as_mut();
+    match drag {
+        Some(delta) => {
+            credits.dragging = true;
+            credits.fling = None;
+            credits.scroll += delta;
+            if dt > 0. {
+                credits.drag_velocity = credits.drag_velocity * 0.5 + delta / dt * 0.5;
+            }
+        }
+        None if credits.dragging => {
+            credits.dragging = false;
+            if credits.drag_velocity.abs() > FLING_MIN_SPEED {
+                credits.fling = Some((
+                    FrictionSimulation::new(
+                        FrictionDescription::from(FLING_FRICTION),
+                        credits.scroll,
+                        credits.drag_velocity,
+                        FLING_MIN_SPEED,
+                    ),
+                    now,
+                ));
+            }
+            credits.drag_velocity = 0.;
+        }
+        None => {
+            let mut speed = AUTO_SCROLL_SPEED;
+            if keyboard_input.pressed(KeyCode::Up) {
+                speed = -KEY_SCROLL_SPEED;
+            } else if keyboard_input.pressed(KeyCode::Down) {
+                speed = KEY_SCROLL_SPEED;
+            }
+            for ev in ev_mouse_wheel.iter() {
+                let lines = match ev.unit {
+                    MouseScrollUnit::Line => ev.y * WHEEL_LINE_HEIGHT,
+                    MouseScrollUnit::Pixel => ev.y,
+                };
+                credits.scroll -= lines;
+                credits.fling = None;
+            }
+
+            match &credits.fling {
+                Some((fling, start)) if !fling.is_done(now - start) => {
+                    credits.scroll = fling.x(now - start);
+                }
+                _ => {
+                    credits.fling = None;
+                    credits.scroll += speed * dt;
+                }
+            }
+        }
+    }
+
+    // Start over once the text has scrolled off the top, and don't drag it off the bottom.
+    let loop_length = CREDITS_END_Y - CREDITS_START_Y + credits_height();
+    if credits.scroll > loop_length {
+        credits.scroll -= loop_length;
+        credits.fling = None;
+    } else if credits.scroll < 0. {
+        credits.scroll = 0.;
+        credits.fling = None;
+    }
+
+    for mut transform in q_text.iter_mut() {
+        transform.translation.y = CREDITS_START_Y + credits.scroll;
+    }
+}
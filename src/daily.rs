@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+use crate::{GameMode, GameState};
+
+const SEED_DIGITS: usize = 9;
+
+// Not a system! The date in UTC as a number like 20220130, which is the seed of the day's
+// puzzle. Typing it in as a seed plays that day's puzzle again.
+pub(crate) fn daily_seed() -> u64 {
+    let (year, month, day) = civil_from_days(days_since_epoch());
+    year as u64 * 10_000 + month as u64 * 100 + day as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn days_since_epoch() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| (since.as_secs() / 86_400) as i64)
+}
+
+// `SystemTime::now` panics in the browser, which keeps the clock.
+#[cfg(target_arch = "wasm32")]
+fn days_since_epoch() -> i64 {
+    (js_sys::Date::now() / 86_400_000.).floor() as i64
+}
+
+// The proleptic Gregorian date `days` after 1970-01-01, from Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March, so the leap day is the last day of the year.
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month as u32, day as u32)
+}
+
+/// The prompt to type in the seed of a puzzle, opened from the main menu.
+#[derive(Default)]
+pub struct SeedEntry {
+    pub open: bool,
+    digits: String,
+}
+
+impl SeedEntry {
+    /// Send `UpdateSeedEntry` to draw it.
+    pub fn show(&mut self) {
+        *self = SeedEntry {
+            open: true,
+            ..Default::default()
+        };
+    }
+}
+
+pub struct UpdateSeedEntry;
+
+#[derive(Component)]
+pub struct SeedEntryUi;
+
+#[derive(Component)]
+pub struct SeedEntryText;
+
+fn seed_entry_text(seed_entry: &SeedEntry) -> String {
+    format!("Seed\n\n{}_\n\nReturn play · Esc close", seed_entry.digits)
+}
+
+// Digits type the seed in, Return deals it. Runs before the main menu, so the keys it uses up
+// aren't seen by it.
+pub(crate) fn seed_entry_input(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut ev_char: EventReader<ReceivedCharacter>,
+    mut seed_entry: ResMut<SeedEntry>,
+    mut mode: ResMut<GameMode>,
+    mut state: ResMut<State<GameState>>,
+    mut ev_update: EventWriter<UpdateSeedEntry>,
+) {
+    let typed = ev_char
+        .iter()
+        .filter(|ev| ev.char.is_ascii_digit())
+        .map(|ev| ev.char)
+        .collect::<String>();
+    if !seed_entry.open {
+        return;
+    }
+
+    if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        seed_entry.open = false;
+    } else if keyboard_input.clear_just_pressed(KeyCode::Return) {
+        let seed = match seed_entry.digits.parse::<u64>() {
+            Ok(seed) => seed,
+            Err(_) => return,
+        };
+        seed_entry.open = false;
+        *mode = GameMode::Seeded(seed);
+        if let Err(err) = state.set(GameState::InGame) {
+            warn!("Could not start the game: {:?}", err);
+        }
+    } else if keyboard_input.just_pressed(KeyCode::Back) {
+        seed_entry.digits.pop();
+    } else if !typed.is_empty() {
+        seed_entry.digits.push_str(&typed);
+        seed_entry.digits.truncate(SEED_DIGITS);
+    } else {
+        return;
+    }
+    ev_update.send(UpdateSeedEntry);
+}
+
+pub(crate) fn update_seed_entry(
+    mut ev_update: EventReader<UpdateSeedEntry>,
+    seed_entry: Res<SeedEntry>,
+    q_ui: Query<Entity, With<SeedEntryUi>>,
+    mut q_text: Query<&mut Text, With<SeedEntryText>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if ev_update.iter().count() == 0 {
+        return;
+    }
+
+    if !seed_entry.open {
+        for e in q_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+
+    let value = seed_entry_text(&seed_entry);
+    if let Ok(mut text) = q_text.get_single_mut() {
+        text.sections[0].value = value;
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 78.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: Color::rgba(0., 0., 0., 0.8),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(SeedEntryUi);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                value,
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 30.,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., 0., 79.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(SeedEntryUi)
+        .insert(SeedEntryText);
+}
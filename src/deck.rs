@@ -0,0 +1,219 @@
+//! Deck mode: the cards of each match are drawn from the player's deck, which grows over a run.
+//! A win offers three new cards and the one picked joins the deck; a loss ends the run and the
+//! deck with it. There is no campaign, so each match of the run is its next level.
+
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+use rand::prelude::*;
+
+use crate::{
+    core::{action_caption, random_cards, Action, MatchConfig},
+    match_plugin::{GameRng, MatchEnding, VictoryScreen, MAX_RANDOM_SEED},
+    GameMode, GameState,
+};
+
+// A few more than a hand, so the first matches aren't all dealt the same cards.
+const STARTER_DECK_SIZE: usize = 8;
+const OFFER_SIZE: usize = 3;
+const OFFER_KEYS: [KeyCode; OFFER_SIZE] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3];
+
+/// The cards a Deck run draws its matches from, and the cards offered after a win.
+#[derive(Default)]
+pub(crate) struct PlayerDeck {
+    /// Empty between runs. The first match of a run deals a starter deck.
+    pub(crate) cards: Vec<Action>,
+    offer: Vec<Action>,
+    // The offered card under the cursor.
+    selected: usize,
+}
+
+impl PlayerDeck {
+    // Not a system!
+    pub(crate) fn deal_starter_if_empty(&mut self, rng: &mut GameRng, config: &MatchConfig) {
+        if self.cards.is_empty() {
+            let seed = rng.0.gen_range(0, MAX_RANDOM_SEED);
+            self.cards = random_cards(seed, STARTER_DECK_SIZE, config);
+        }
+    }
+
+    pub(crate) fn is_offering(&self) -> bool {
+        !self.offer.is_empty()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[derive(Component)]
+struct OfferUi;
+
+#[derive(Component)]
+struct OfferText;
+
+pub(crate) struct DeckPlugin;
+
+impl Plugin for DeckPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerDeck>()
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(end_deck_run))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(deck_run)
+                    .with_system(offer_cards.after("handle_input"))
+                    .with_system(offer_input.label("deck_offer_input")),
+            )
+            .add_system(update_offer_ui);
+    }
+}
+
+fn deck_run(mode: Res<GameMode>) -> ShouldRun {
+    if *mode == GameMode::Deck {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+fn end_deck_run(mut deck: ResMut<PlayerDeck>) {
+    if !deck.cards.is_empty() || deck.is_offering() {
+        deck.reset();
+    }
+}
+
+// A win offers new cards, a loss throws the deck away.
+fn offer_cards(
+    config: Res<MatchConfig>,
+    q_ending: Query<Option<&VictoryScreen>, Added<MatchEnding>>,
+    mut rng: ResMut<GameRng>,
+    mut deck: ResMut<PlayerDeck>,
+) {
+    for victory in q_ending.iter() {
+        if victory.is_some() {
+            let seed = rng.0.gen_range(0, MAX_RANDOM_SEED);
+            deck.offer = random_cards(seed, OFFER_SIZE, &config);
+            deck.selected = 0;
+        } else {
+            deck.reset();
+        }
+    }
+}
+
+// Left and Right pick a card and Return takes it, or 1 to 3 take one straight away. Esc takes
+// none. Runs before the end-of-match screen's input, so the keys it uses up aren't seen by it.
+fn offer_input(
+    state: Res<State<GameState>>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut deck: ResMut<PlayerDeck>,
+) {
+    if *state.current() != GameState::GameOver || !deck.as_ref().is_offering() {
+        return;
+    }
+    let count = deck.as_ref().offer.len();
+    let mut taken = OFFER_KEYS
+        .iter()
+        .take(count)
+        .position(|key| keyboard_input.clear_just_pressed(*key));
+    if keyboard_input.clear_just_pressed(KeyCode::Return)
+        | keyboard_input.clear_just_pressed(KeyCode::Space)
+    {
+        taken = Some(deck.selected);
+    }
+    if let Some(taken) = taken {
+        let card = deck.offer[taken];
+        deck.cards.push(card);
+        deck.offer.clear();
+    } else if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        deck.offer.clear();
+    } else if keyboard_input.clear_just_pressed(KeyCode::Left) {
+        deck.selected = (deck.selected + count - 1) % count;
+    } else if keyboard_input.clear_just_pressed(KeyCode::Right) {
+        deck.selected = (deck.selected + 1) % count;
+    }
+}
+
+fn offer_text(deck: &PlayerDeck) -> String {
+    let cards = deck
+        .offer
+        .iter()
+        .enumerate()
+        .map(|(i, card)| {
+            let caption = format!("{}. {}", i + 1, action_caption(card));
+            if i == deck.selected {
+                format!("[ {} ]", caption)
+            } else {
+                caption
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "Add a card to your deck ({} cards)\n\n{}\n\n← → pick · Return take · Esc skip",
+        deck.cards.len(),
+        cards
+    )
+}
+
+// Shown over the end-of-match screen while cards are on offer.
+fn update_offer_ui(
+    state: Res<State<GameState>>,
+    deck: Res<PlayerDeck>,
+    q_ui: Query<Entity, With<OfferUi>>,
+    mut q_text: Query<&mut Text, With<OfferText>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if !deck.is_changed() && !state.is_changed() {
+        return;
+    }
+
+    if *state.current() != GameState::GameOver || !deck.is_offering() {
+        for e in q_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+    let value = offer_text(&deck);
+    if let Ok(mut text) = q_text.get_single_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 93.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: Color::rgba(0., 0., 0., 0.8),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OfferUi);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                value,
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 30.,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., 0., 94.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OfferUi)
+        .insert(OfferText);
+}
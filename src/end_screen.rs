@@ -0,0 +1,199 @@
+//! The screen over the board once a match is over: the result, the stars earned and how to go
+//! on from there.
+
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    controls::{ActionInput, InputAction, InputMap},
+    core::MatchConfig,
+    deck, despawn_ui,
+    match_plugin::MatchState,
+    profile, timed,
+    timed::Countdown,
+    GameMode, GameState, OpenMenus,
+};
+
+pub(crate) struct EndScreenPlugin;
+
+impl Plugin for EndScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::GameOver).with_system(spawn_end_of_match_ui),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::GameOver).with_system(
+                restart_finished_match
+                    .after("leaderboard_input")
+                    .after("deck_offer_input")
+                    .before("credits_input"),
+            ),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::GameOver).with_system(despawn_ui::<EndOfMatchUi>),
+        );
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct EndOfMatchUi;
+
+/// Everything the end screen tells about the match that just finished.
+#[derive(SystemParam)]
+struct MatchSummary<'w, 's> {
+    match_state: Res<'w, MatchState>,
+    config: Res<'w, MatchConfig>,
+    profile: Res<'w, profile::PlayerProfile>,
+    mode: Res<'w, GameMode>,
+    countdown: Res<'w, Countdown>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+fn spawn_end_of_match_ui(
+    summary: MatchSummary,
+    mut commands: Commands,
+    input_map: Res<InputMap>,
+    asset_server: Res<AssetServer>,
+) {
+    let MatchSummary {
+        match_state,
+        config,
+        profile,
+        mode,
+        countdown,
+        ..
+    } = &summary;
+    let result = match match_state.as_ref() {
+        MatchState::Finished(result) => *result,
+        _ => return,
+    };
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 90.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: Color::rgba(0., 0., 0., 0.8),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(EndOfMatchUi);
+    let font = asset_server.load("ReadexPro-Regular.ttf");
+    let lines = [
+        (
+            match (result.won, **mode) {
+                (true, GameMode::Timed) => {
+                    format!("You won! +{}s", timed::win_bonus(&result) as u32)
+                }
+                (false, GameMode::Timed) if countdown.expired() => "Out of time".to_string(),
+                (false, GameMode::Deck) => "Out of cards · The run is over".to_string(),
+                (true, _) => "You won!".to_string(),
+                (false, _) => "Out of cards".to_string(),
+            },
+            50.,
+        ),
+        (
+            match profile.best_moves(&mode.match_config(config)) {
+                Some(best) => format!(
+                    "Cards used: {} · Moves: {} · Par: {} · Best: {}",
+                    result.cards_used, result.moves, result.par, best
+                ),
+                None => format!(
+                    "Cards used: {} · Moves: {} · Par: {}",
+                    result.cards_used, result.moves, result.par
+                ),
+            },
+            30.,
+        ),
+        (
+            format!(
+                "Press {} to restart, Esc for the menu",
+                input_map.keys_label(InputAction::Restart)
+            ),
+            24.,
+        ),
+    ];
+    for (i, (text, font_size)) in lines.iter().enumerate() {
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    text.as_str(),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: *font_size,
+                        color: Color::WHITE,
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Center,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                ),
+                transform: Transform {
+                    translation: Vec3::new(0., 80. - 80. * i as f32, 91.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(EndOfMatchUi);
+    }
+
+    // The star rating, as diamonds above the result, lit for each star earned.
+    if result.won {
+        for star in 0..3 {
+            commands
+                .spawn_bundle(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(-70. + 70. * star as f32, 170., 91.),
+                        rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_4),
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(36., 36.)),
+                        color: if star < result.stars() {
+                            Color::rgb(1., 0.8, 0.2)
+                        } else {
+                            Color::rgba(1., 1., 1., 0.2)
+                        },
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(EndOfMatchUi);
+        }
+    }
+}
+
+// Deals a new match from the end-of-match screen, or goes back to the main menu.
+fn restart_finished_match(
+    menus: OpenMenus,
+    deck: Res<deck::PlayerDeck>,
+    input: ActionInput,
+    mut mode: ResMut<GameMode>,
+    mut match_state: ResMut<MatchState>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if menus.any_open() || deck.is_offering() {
+        return;
+    }
+    let next = if input.just_pressed(InputAction::Restart) {
+        GameState::InGame
+    } else if input.keyboard.just_pressed(KeyCode::Escape) {
+        GameState::Menu
+    } else {
+        return;
+    };
+    // The tutorial is played once through, what comes after it is a regular match.
+    if *mode == GameMode::Tutorial {
+        *mode = GameMode::Classic;
+    }
+    *match_state = MatchState::Ready;
+    if let Err(err) = state.set(next) {
+        warn!("Could not leave the end-of-match screen: {:?}", err);
+    }
+}
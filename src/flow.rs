@@ -0,0 +1,164 @@
+//! Moving between the menus and the match: pausing, abandoning and dealing new matches.
+
+use bevy::{app::AppExit, prelude::*, window::WindowFocused};
+
+use crate::{
+    animation::TimeScale,
+    controls::{InputAction, InputMap},
+    match_plugin::{MatchState, StartMatchEvent},
+    modal::{ModalAction, ModalButton, ModalResult, OpenModal},
+    settings::Settings,
+    DespawnAll, GameMode, GameState, OpenMenus,
+};
+
+pub(crate) struct FlowPlugin;
+
+impl Plugin for FlowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(GameState::Loading)
+            .add_event::<DespawnAll>()
+            .init_resource::<GameMode>()
+            .init_resource::<FocusPause>()
+            .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(enter_game))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(
+                        pause_game
+                            .before("modal_input")
+                            .before("controls_menu_input")
+                            .before("inspect_card")
+                            .before("pick_tiles")
+                            .before("credits_input"),
+                    )
+                    .with_system(request_modals.before("modal_input")),
+            )
+            .add_system(
+                handle_modal_results
+                    .label("match_flow")
+                    .after("modal_input"),
+            )
+            .add_system(pause_on_focus_loss.before("tick_match_clock"))
+            .add_system(pause_animations_in_menus.after("tick_match_clock"));
+    }
+}
+
+/// Set while the game is paused because its window is in the background.
+#[derive(Default)]
+pub(crate) struct FocusPause(pub(crate) bool);
+
+fn pause_on_focus_loss(
+    windows: Res<Windows>,
+    settings: Res<Settings>,
+    mut ev_focused: EventReader<WindowFocused>,
+    mut focus_pause: ResMut<FocusPause>,
+) {
+    let window_id = match windows.get_primary() {
+        Some(window) => window.id(),
+        None => return,
+    };
+    for ev in ev_focused.iter().filter(|ev| ev.id == window_id) {
+        focus_pause.0 = !ev.focused && settings.pause_on_focus_loss;
+    }
+}
+
+// Gameplay animations freeze while a menu is open; the menu's own animations keep running.
+fn pause_animations_in_menus(
+    menus: OpenMenus,
+    focus_pause: Res<FocusPause>,
+    state: Res<State<GameState>>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    let paused = menus.any_open() || focus_pause.0 || *state.current() == GameState::Paused;
+    if time_scale.paused != paused {
+        time_scale.paused = paused;
+    }
+}
+
+// Opens the abandon-match confirmation.
+fn request_modals(
+    menus: OpenMenus,
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    match_state: Res<MatchState>,
+    mut ev_open_modal: EventWriter<OpenModal>,
+) {
+    if menus.any_open() {
+        return;
+    }
+    let playing = match match_state.as_ref() {
+        MatchState::Playing(match_state) => match_state,
+        _ => return,
+    };
+    if playing.inspected_card.is_some() {
+        return;
+    }
+
+    if input_map.just_pressed(InputAction::AbandonMatch, &keyboard_input) {
+        ev_open_modal.send(OpenModal {
+            title: "New match".to_string(),
+            body: "Abandon this match and deal a new one?".to_string(),
+            buttons: vec![
+                ModalButton {
+                    label: "Abandon".to_string(),
+                    action: ModalAction::AbandonMatch,
+                },
+                ModalButton {
+                    label: "Keep playing".to_string(),
+                    action: ModalAction::Dismiss,
+                },
+            ],
+        });
+    }
+}
+
+// Esc pauses. Runs before the other Esc handlers, and only when none of them would use it, so
+// that closing a menu or the inspect view with Esc doesn't also pause.
+fn pause_game(
+    menus: OpenMenus,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    match_state: Res<MatchState>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if menus.any_open() || !keyboard_input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    if let MatchState::Playing(match_state) = match_state.as_ref() {
+        // Esc closes the inspected card, or stops picking tiles for a wildcard.
+        if match_state.inspected_card.is_some() || match_state.picking_tiles() {
+            return;
+        }
+    }
+    // The pause menu closes with Esc too, and would otherwise see it in the same frame.
+    keyboard_input.clear_just_pressed(KeyCode::Escape);
+    if let Err(err) = state.set(GameState::Paused) {
+        warn!("Could not pause: {:?}", err);
+    }
+}
+
+// Deals a match when entering the game from the menus; coming back from the pause menu, the
+// match is still there.
+fn enter_game(match_state: Res<MatchState>, mut ev_start_match: EventWriter<StartMatchEvent>) {
+    if let MatchState::Ready = match_state.as_ref() {
+        ev_start_match.send(StartMatchEvent);
+    }
+}
+
+fn handle_modal_results(
+    mut ev_result: EventReader<ModalResult>,
+    mut ev_exit: EventWriter<AppExit>,
+    mut ev_start_match: EventWriter<StartMatchEvent>,
+    mut ev_despawn_all: EventWriter<DespawnAll>,
+    mut match_state: ResMut<MatchState>,
+) {
+    for ModalResult(action) in ev_result.iter() {
+        match action {
+            ModalAction::Quit => ev_exit.send(AppExit),
+            ModalAction::AbandonMatch => {
+                ev_despawn_all.send(DespawnAll);
+                *match_state = MatchState::Ready;
+                ev_start_match.send(StartMatchEvent);
+            }
+            ModalAction::DeleteCrashLog | ModalAction::KeepCrashLog | ModalAction::Dismiss => (),
+        }
+    }
+}
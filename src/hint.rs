@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+use jam_motion::spring::{SpringDescription, SpringSimulation};
+
+use crate::{
+    animation::{AnimationClock, SimulatedField, SimulationAnimator, SimulationChannel},
+    controls::InputAction,
+    core::{shortest_solution, TileNature, TileSide},
+    match_plugin::{
+        CardAction, CardPlayed, MatchInput, MatchState, RestartRequest, Slot, Tile, UndoRequest,
+        Used,
+    },
+    redraw::CardRedrawn,
+    toast::ShowToast,
+    DespawnAll, GameState,
+};
+
+const HINTS_PER_MATCH: usize = 3;
+const GLOW_SIZE: f32 = 310.;
+const GLOW_COLOR: Color = Color::rgba(1., 0.85, 0.3, 0.7);
+// How hard each pulse kicks the glow's scale.
+const GLOW_PULSE_VELOCITY: f32 = 1.5;
+
+/// Sent when the player is shown a hint, with the card it suggests.
+pub struct HintUsed(pub Entity);
+
+/// Glows behind the card a hint suggests playing next, until the board changes.
+#[derive(Component)]
+pub struct HintGlow;
+
+pub(crate) struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HintUsed>()
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(show_hint.label("show_hint").after("handle_input")),
+            )
+            .add_system(spawn_hint_glow.after("show_hint"))
+            // Before the glow can be despawned, inserting into it would panic.
+            .add_system(pulse_hint_glow.before("clear_hint"))
+            .add_system(clear_hint.label("clear_hint").after("handle_input"));
+    }
+}
+
+// A loose spring, so each kick wobbles a few times before settling.
+fn glow_pulse(now: f32) -> SimulationAnimator {
+    SimulationAnimator::new(
+        vec![SimulationChannel {
+            field: SimulatedField::Scale,
+            simulation: Box::new(SpringSimulation::new(
+                SpringDescription::from_damping_ratio(1., 120., 0.15),
+                1.,
+                1.,
+                GLOW_PULSE_VELOCITY,
+                0.01,
+            )),
+        }],
+        now,
+    )
+}
+
+// Finds a shortest way to win with the cards left and lights up its first card.
+pub(crate) fn show_hint(
+    input: MatchInput,
+    mut match_state: ResMut<MatchState>,
+    q_tiles: Query<(&TileSide, &Slot, &TileNature), With<Tile>>,
+    q_cards: Query<(&CardAction, Option<&Used>)>,
+    q_glows: Query<(), With<HintGlow>>,
+    mut ev_hint_used: EventWriter<HintUsed>,
+    mut ev_toast: EventWriter<ShowToast>,
+) {
+    if input.lock.is_locked() || !input.just_pressed(InputAction::Hint) {
+        return;
+    }
+    let match_state = match match_state.as_mut() {
+        MatchState::Playing(match_state)
+            if match_state.inspected_card.is_none() && !match_state.picking_tiles() =>
+        {
+            match_state
+        }
+        _ => return,
+    };
+    if !q_glows.is_empty() {
+        return;
+    }
+    if match_state.hints_used >= HINTS_PER_MATCH {
+        ev_toast.send(ShowToast("No hints left this match".to_string()));
+        return;
+    }
+
+    let mut tiles = q_tiles.iter().collect::<Vec<_>>();
+    tiles.sort_by_key(|(_, slot, _)| slot.0);
+    let column = |side: TileSide| {
+        tiles
+            .iter()
+            .filter(|(s, _, _)| **s == side)
+            .map(|(_, _, nature)| **nature)
+            .collect::<Vec<_>>()
+    };
+    let hand = match_state
+        .hand_order
+        .iter()
+        .filter_map(|card| {
+            q_cards
+                .get(*card)
+                .ok()
+                .map(|(action, used)| (*card, action, used))
+        })
+        .collect::<Vec<_>>();
+    let cards = hand
+        .iter()
+        .map(|(_, CardAction(action), _)| *action)
+        .collect::<Vec<_>>();
+    let used = hand
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, _, used))| used.is_some())
+        .fold(0u32, |used, (i, _)| used | (1 << i));
+
+    let first = shortest_solution(
+        &column(TileSide::Left),
+        &column(TileSide::Right),
+        &cards,
+        used,
+    )
+    .and_then(|solution| solution.first().copied());
+    let card = match first {
+        Some(i) => hand[i].0,
+        None => {
+            ev_toast.send(ShowToast(
+                "No way to win with the cards left was found, try undoing".to_string(),
+            ));
+            return;
+        }
+    };
+
+    match_state.hints_used += 1;
+    ev_hint_used.send(HintUsed(card));
+    ev_toast.send(ShowToast(format!(
+        "Hint: play this card ({} left)",
+        HINTS_PER_MATCH - match_state.hints_used
+    )));
+}
+
+// Lights up the card a hint suggests.
+pub(crate) fn spawn_hint_glow(
+    clock: Res<AnimationClock>,
+    mut ev_hint_used: EventReader<HintUsed>,
+    mut commands: Commands,
+) {
+    for HintUsed(card) in ev_hint_used.iter() {
+        let glow = commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform::from_xyz(0., 0., -0.05),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(GLOW_SIZE, GLOW_SIZE)),
+                    color: GLOW_COLOR,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(HintGlow)
+            .insert(glow_pulse(clock.now()))
+            .id();
+        commands.entity(*card).push_children(&[glow]);
+    }
+}
+
+// Kicks the glow again each time it settles.
+pub(crate) fn pulse_hint_glow(
+    clock: Res<AnimationClock>,
+    q: Query<Entity, (With<HintGlow>, Without<SimulationAnimator>)>,
+    mut commands: Commands,
+) {
+    for entity in q.iter() {
+        commands.entity(entity).insert(glow_pulse(clock.now()));
+    }
+}
+
+// The hint is only good for the board and hand it was given on.
+pub(crate) fn clear_hint(
+    mut ev_card_played: EventReader<CardPlayed>,
+    mut ev_card_redrawn: EventReader<CardRedrawn>,
+    mut ev_undo: EventReader<UndoRequest>,
+    mut ev_restart: EventReader<RestartRequest>,
+    mut ev_despawn_all: EventReader<DespawnAll>,
+    q: Query<Entity, With<HintGlow>>,
+    mut commands: Commands,
+) {
+    let board_changed = ev_card_played.iter().count()
+        + ev_card_redrawn.iter().count()
+        + ev_undo.iter().count()
+        + ev_restart.iter().count()
+        + ev_despawn_all.iter().count()
+        > 0;
+    if board_changed {
+        for entity in q.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
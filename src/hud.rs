@@ -0,0 +1,255 @@
+//! The text around the board while playing: the prompt bar with the controls of the device in
+//! use, the caption of the hovered card, the match timer and the move counter.
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+use crate::{
+    controls::{InputAction, InputMap},
+    core::action_caption,
+    layout::NonEssentialHud,
+    match_plugin::{Card, CardAction, MatchClock, MatchState, Used},
+    theme::{Theme, ThemedText},
+};
+
+const PROMPT_BAR_Y_POS: f32 = -615.;
+const CAPTION_BAR_Y_POS: f32 = -570.;
+const MATCH_TIMER_Y_POS: f32 = 440.;
+const MOVE_COUNTER_Y_POS: f32 = 405.;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum InputDevice {
+    Keyboard,
+    Gamepad,
+    Touch,
+}
+
+// The device the player touched last, used to pick which glyphs to show.
+struct LastInputDevice(InputDevice);
+
+#[derive(Component)]
+struct PromptBar;
+
+#[derive(Component)]
+struct CaptionBar;
+
+#[derive(Component)]
+struct MatchTimerText;
+
+#[derive(Component)]
+struct MoveCounterText;
+
+pub(crate) struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LastInputDevice(InputDevice::Keyboard))
+            .add_startup_system(setup_hud)
+            .add_system(track_input_device)
+            .add_system(update_prompt_bar)
+            .add_system(update_match_timer)
+            .add_system(update_move_counter.after("handle_input"))
+            .add_system_to_stage("view", update_caption_bar);
+    }
+}
+
+fn track_input_device(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    mut last_input_device: ResMut<LastInputDevice>,
+) {
+    let device = if keyboard_input.get_just_pressed().next().is_some() {
+        InputDevice::Keyboard
+    } else if gamepad_input.get_just_pressed().next().is_some() {
+        InputDevice::Gamepad
+    } else if touches.iter_just_pressed().next().is_some() {
+        InputDevice::Touch
+    } else {
+        return;
+    };
+
+    // Only write on actual change so the prompt bar is not rebuilt every frame.
+    if last_input_device.0 != device {
+        last_input_device.0 = device;
+    }
+}
+
+fn prompt_bar_text(device: InputDevice, input_map: &InputMap) -> String {
+    match device {
+        InputDevice::Keyboard => format!(
+            "{}/{} select · Shift+{}/{} move · {} play · {} undo · {} hint · {} restart",
+            input_map.keys_label(InputAction::PreviousCard),
+            input_map.keys_label(InputAction::NextCard),
+            input_map.keys_label(InputAction::PreviousCard),
+            input_map.keys_label(InputAction::NextCard),
+            input_map.keys_label(InputAction::PlayCard),
+            input_map.keys_label(InputAction::Undo),
+            input_map.keys_label(InputAction::Hint),
+            input_map.keys_label(InputAction::Restart),
+        ),
+        InputDevice::Gamepad => {
+            "D-pad select · A play · B undo · Y hint · Start restart".to_string()
+        }
+        InputDevice::Touch => "Tap a card to select · Tap again to play".to_string(),
+    }
+}
+
+pub(crate) fn format_match_time(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+// Not a system! A line of HUD text centred at `y`, in the theme's text colour.
+fn spawn_hud_text<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    font: &Handle<Font>,
+    theme: &Theme,
+    value: String,
+    font_size: f32,
+    y: f32,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut text = commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color: theme.text_color(),
+            },
+            TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        transform: Transform {
+            translation: Vec3::new(0., y, 50.),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    text.insert(ThemedText);
+    text
+}
+
+fn setup_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    last_input_device: Res<LastInputDevice>,
+    input_map: Res<InputMap>,
+    theme: Res<Theme>,
+) {
+    let font = asset_server.load("ReadexPro-Regular.ttf");
+    spawn_hud_text(
+        &mut commands,
+        &font,
+        &theme,
+        prompt_bar_text(last_input_device.0, &input_map),
+        22.,
+        PROMPT_BAR_Y_POS,
+    )
+    .insert(PromptBar)
+    .insert(NonEssentialHud);
+    spawn_hud_text(
+        &mut commands,
+        &font,
+        &theme,
+        String::new(),
+        28.,
+        CAPTION_BAR_Y_POS,
+    )
+    .insert(CaptionBar);
+    spawn_hud_text(
+        &mut commands,
+        &font,
+        &theme,
+        format_match_time(0.),
+        30.,
+        MATCH_TIMER_Y_POS,
+    )
+    .insert(MatchTimerText)
+    .insert(NonEssentialHud);
+    spawn_hud_text(
+        &mut commands,
+        &font,
+        &theme,
+        String::new(),
+        22.,
+        MOVE_COUNTER_Y_POS,
+    )
+    .insert(MoveCounterText)
+    .insert(NonEssentialHud);
+}
+
+fn update_prompt_bar(
+    last_input_device: Res<LastInputDevice>,
+    input_map: Res<InputMap>,
+    mut q: Query<&mut Text, With<PromptBar>>,
+) {
+    if !last_input_device.is_changed() && !input_map.is_changed() {
+        return;
+    }
+    for mut text in q.iter_mut() {
+        text.sections[0].value = prompt_bar_text(last_input_device.0, &input_map);
+    }
+}
+
+fn update_caption_bar(
+    match_state: Res<MatchState>,
+    q_cards: Query<(&CardAction, Option<&Used>), With<Card>>,
+    mut q: Query<&mut Text, With<CaptionBar>>,
+) {
+    if !match_state.is_changed() {
+        return;
+    }
+    let caption = match match_state.as_ref() {
+        MatchState::Playing(match_state) => {
+            match match_state.hovered_card.map(|c| q_cards.get(c)) {
+                Some(Ok((CardAction(action), used))) => match used {
+                    Some(_) => format!("{} (used)", action_caption(action)),
+                    None => action_caption(action),
+                },
+                _ => String::new(),
+            }
+        }
+        _ => String::new(),
+    };
+    for mut text in q.iter_mut() {
+        text.sections[0].value = caption.clone();
+    }
+}
+
+fn update_match_timer(clock: Res<MatchClock>, mut q: Query<&mut Text, With<MatchTimerText>>) {
+    if !clock.is_changed() {
+        return;
+    }
+    for mut text in q.iter_mut() {
+        let value = format_match_time(clock.elapsed);
+        // Only touch the text when the displayed second changes, to avoid relayouts every frame.
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+fn update_move_counter(
+    match_state: Res<MatchState>,
+    mut q: Query<&mut Text, With<MoveCounterText>>,
+) {
+    if !match_state.is_changed() {
+        return;
+    }
+    let value = match match_state.as_ref() {
+        MatchState::Playing(match_state) => {
+            format!(
+                "Moves {} · Par {} · Redraws {} · Seed {}",
+                match_state.moves, match_state.par, match_state.redraws_left, match_state.seed
+            )
+        }
+        _ => String::new(),
+    };
+    for mut text in q.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use jam_motion::spring::SpringDescription;
+
+use crate::{
+    animation::{
+        self, AnimationGroup, AnimationGroupDone, AnimationSpec, AnimationTarget, Animator, Motion,
+        Stagger, StaggerEasing,
+    },
+    assets::GameAssets,
+    controls::AnyPress,
+    core::{nature_count, TileNature, TileSide},
+    despawn_ui,
+    theme::Theme,
+    GameState,
+};
+
+const LOGO_TILE_SIZE: f32 = 110.;
+const LOGO_TILE_GAP: f32 = 120.;
+const LOGO_Y_POS: f32 = 60.;
+const LOGO_FLY_IN_Y_POS: f32 = -900.;
+const PROMPT_Y_POS: f32 = -120.;
+const PROMPT_FADE_IN: f32 = 0.6;
+// How long the logo stays up once it has landed, if the player doesn't skip it.
+const INTRO_HOLD: f32 = 2.;
+
+/// Sent once the assets are ready; the main menu opens when the intro is over.
+pub struct StartIntro;
+
+#[derive(Default)]
+pub struct Intro {
+    running: bool,
+    logo_group: Option<AnimationGroup>,
+    // When the logo finished landing.
+    landed_at: Option<f64>,
+}
+
+#[derive(Component)]
+pub struct IntroUi;
+
+#[derive(Component)]
+pub struct IntroPrompt;
+
+pub(crate) struct IntroPlugin;
+
+impl Plugin for IntroPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartIntro>()
+            .init_resource::<Intro>()
+            .add_system(start_intro.label("start_intro"))
+            .add_system(run_intro.after("start_intro"))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Intro).with_system(despawn_ui::<IntroUi>),
+            );
+    }
+}
+
+// The logo is a row of tiles, one per nature, alternating sides.
+pub(crate) fn start_intro(
+    mut ev_start_intro: EventReader<StartIntro>,
+    mut intro: ResMut<Intro>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_assets: Res<GameAssets>,
+    theme: Res<Theme>,
+    mut motion: Motion,
+) {
+    if ev_start_intro.iter().count() == 0 {
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 80.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: theme.background_color(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(IntroUi);
+
+    let row_width = LOGO_TILE_GAP * (nature_count() - 1) as f32;
+    let fly_in = (0..nature_count())
+        .map(|nature| {
+            let side = if nature % 2 == 0 {
+                TileSide::Left
+            } else {
+                TileSide::Right
+            };
+            let to = Vec3::new(
+                -row_width / 2. + LOGO_TILE_GAP * nature as f32,
+                LOGO_Y_POS,
+                81.,
+            );
+            let from = if motion.snaps() {
+                to
+            } else {
+                Vec3::new(to.x, LOGO_FLY_IN_Y_POS, to.z)
+            };
+            let id = commands
+                .spawn_bundle(game_assets.sheet(SpriteBundle {
+                    transform: Transform::from_translation(from),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(LOGO_TILE_SIZE, LOGO_TILE_SIZE)),
+                        ..Default::default()
+                    },
+                    texture: theme.tile_texture(side, TileNature(nature), &game_assets),
+                    ..Default::default()
+                }))
+                .insert(Animator::default())
+                .insert(IntroUi)
+                .id();
+            (
+                id,
+                AnimationSpec {
+                    target: AnimationTarget::Translation { from, to },
+                    spring: SpringDescription::with_duration_and_bounce(0.7, 0.25)
+                        .expect("a valid spring"),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "Press any key",
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 30.,
+                    color: theme.text_color() * [1., 1., 1., 0.],
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., PROMPT_Y_POS, 81.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(IntroUi)
+        .insert(IntroPrompt);
+
+    *intro = Intro {
+        running: true,
+        logo_group: None,
+        landed_at: None,
+    };
+    if motion.snaps() {
+        intro.landed_at = Some(0.);
+    } else {
+        let group = motion.groups.start();
+        intro.logo_group = Some(group);
+        animation::stagger(
+            &mut motion.ev_play_animation,
+            &fly_in,
+            Stagger {
+                total_delay: 0.5,
+                easing: StaggerEasing::EaseOut,
+            },
+            Some(group),
+        );
+    }
+}
+
+// Fades the prompt up once the logo has landed, and hands over to the main menu when the
+// player presses anything or the logo has been up long enough.
+pub(crate) fn run_intro(
+    time: Res<Time>,
+    press: AnyPress,
+    theme: Res<Theme>,
+    mut intro: ResMut<Intro>,
+    mut ev_group_done: EventReader<AnimationGroupDone>,
+    mut q_prompt: Query<&mut Text, With<IntroPrompt>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if !intro.running {
+        return;
+    }
+    let now = time.seconds_since_startup();
+    for AnimationGroupDone(group) in ev_group_done.iter() {
+        if intro.logo_group == Some(*group) {
+            intro.landed_at = Some(now);
+        }
+    }
+    // Reduced motion has nothing to wait for.
+    if intro.landed_at == Some(0.) {
+        intro.landed_at = Some(now);
+    }
+
+    let skipped = press.any_just_pressed();
+    let held = intro
+        .landed_at
+        .map_or(false, |landed_at| now - landed_at > INTRO_HOLD as f64);
+    if skipped || held {
+        *intro = Intro::default();
+        if let Err(err) = state.set(GameState::Menu) {
+            warn!("Could not open the main menu: {:?}", err);
+        }
+        return;
+    }
+
+    if let Some(landed_at) = intro.landed_at {
+        let alpha = ((now - landed_at) as f32 / PROMPT_FADE_IN).min(1.);
+        for mut text in q_prompt.iter_mut() {
+            text.sections[0].style.color = theme.text_color() * [1., 1., 1., alpha];
+        }
+    }
+}
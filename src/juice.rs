@@ -0,0 +1,632 @@
+use bevy::prelude::*;
+
+use crate::animation::{
+    self, AnimatedProperty, AnimationClock, AnimationGroup, AnimationGroupDone, AnimationGroups,
+    AnimationSpec, AnimationTarget, Animator, AnimatorDone, Motion, PlayAnimation, SimulatedField,
+    SimulationAnimator, SimulationChannel, Stagger, StaggerEasing, TimeScale,
+};
+use crate::{
+    controls::{InputAction, InputMap},
+    core::{action_side, Action, TileSide},
+    layout::Layout,
+    match_plugin::{
+        tiles_layout_poss, Card, CardPlayed, MatchState, MatchWon, Slot, Tile, TileMovement,
+        TileSprite, UpdateCardsStyle, UpdateTilesPosition, Used, VictoryScreen, CARDS_Y_POS,
+        TILE_POS_X_ABS, TILE_POS_Y_GAP,
+    },
+    toast::ShowToast,
+    MotionSettings,
+};
+use jam_motion::{
+    friction::{FrictionDescription, FrictionSimulation},
+    spring::{SpringDescription, SpringSimulation},
+};
+
+pub(crate) struct JuicePlugin;
+
+impl Plugin for JuicePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MotionSettings>()
+            .add_startup_system(spawn_camera)
+            .add_system(toggle_reduced_motion.after("leaderboard_input"))
+            .add_system(camera_punch_on_card_play)
+            .add_system(animate_camera_punch)
+            .add_system(camera_shake_on_card_play)
+            .add_system(animate_camera_shake)
+            .add_system(flash_column_on_card_play)
+            .add_system(despawn_faded_column_flashes)
+            .add_system(start_discard_toss.after("handle_input"))
+            .add_system(land_discard_toss.after("animate_simulations"))
+            .add_system(slow_motion_on_win)
+            .add_event::<WinningMoveSettled>()
+            .add_system(wait_for_winning_move.before("celebrate_victory"))
+            .add_system(celebrate_victory.label("celebrate_victory"))
+            .add_system_to_stage("view", idle_bob_tiles.after("animate"));
+    }
+}
+
+#[derive(Component)]
+pub struct MainCamera;
+
+fn spawn_camera(mut commands: Commands) {
+    commands
+        .spawn_bundle(OrthographicCameraBundle::new_2d())
+        .insert(MainCamera);
+}
+
+fn toggle_reduced_motion(
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut motion_settings: ResMut<MotionSettings>,
+    mut ev_toast: EventWriter<ShowToast>,
+) {
+    if input_map.just_pressed(InputAction::ToggleReducedMotion, &keyboard_input) {
+        motion_settings.reduced_motion = !motion_settings.reduced_motion;
+        ev_toast.send(ShowToast(
+            if motion_settings.reduced_motion {
+                "Reduced motion on"
+            } else {
+                "Reduced motion off"
+            }
+            .to_string(),
+        ));
+    }
+}
+
+// Both springs rest at the camera's neutral state and are kicked with an initial velocity, so
+// the camera moves towards the column and settles back without overshooting.
+#[derive(Component)]
+pub struct CameraPunch {
+    zoom: SpringSimulation,
+    pan: SpringSimulation,
+    started_at: f32,
+}
+
+const PUNCH_ZOOM_VELOCITY: f32 = -2.;
+const PUNCH_PAN_VELOCITY_FACTOR: f32 = 4.;
+
+fn punch_spring() -> SpringDescription {
+    SpringDescription::from_damping_ratio(1., 120., 1.4)
+}
+
+pub(crate) fn camera_punch_on_card_play(
+    mut ev_card_played: EventReader<CardPlayed>,
+    motion_settings: Res<MotionSettings>,
+    layout: Res<Layout>,
+    clock: Res<AnimationClock>,
+    q_camera: Query<Entity, With<MainCamera>>,
+    mut commands: Commands,
+) {
+    for CardPlayed { action, .. } in ev_card_played.iter() {
+        if motion_settings.reduced_motion {
+            continue;
+        }
+        // Actions across both columns punch straight in.
+        let column_x = action_side(action).map_or(0., |side| layout.side_x(side, TILE_POS_X_ABS));
+        for camera in q_camera.iter() {
+            commands.entity(camera).insert(CameraPunch {
+                zoom: SpringSimulation::new(punch_spring(), 1., 1., PUNCH_ZOOM_VELOCITY, 0.01),
+                pan: SpringSimulation::new(
+                    punch_spring(),
+                    0.,
+                    0.,
+                    column_x * PUNCH_PAN_VELOCITY_FACTOR,
+                    1.,
+                ),
+                started_at: clock.now(),
+            });
+        }
+    }
+}
+
+pub(crate) fn animate_camera_punch(
+    mut q_camera: Query<(
+        Entity,
+        &CameraPunch,
+        &mut Transform,
+        &mut OrthographicProjection,
+    )>,
+    layout: Res<Layout>,
+    clock: Res<AnimationClock>,
+    mut commands: Commands,
+) {
+    for (entity, punch, mut transform, mut projection) in q_camera.iter_mut() {
+        let t = clock.now() - punch.started_at;
+        let zoom = punch.zoom.x_or_end_x(t);
+        let pan = punch.pan.x_or_end_x(t);
+        projection.scale = zoom.x * layout.view_scale;
+        transform.translation.x = pan.x;
+        if zoom.is_done && pan.is_done {
+            commands.entity(entity).remove::<CameraPunch>();
+        }
+    }
+}
+
+// An underdamped spring resting at the camera's height, kicked down so the board rattles back and
+// forth a few times. Separate from the punch, which only moves the camera sideways.
+#[derive(Component)]
+pub struct CameraShake {
+    offset: SpringSimulation,
+    started_at: f32,
+}
+
+const SHAKE_VELOCITY: f32 = -300.;
+
+fn shake_spring() -> SpringDescription {
+    SpringDescription::from_damping_ratio(1., 900., 0.15)
+}
+
+// Actions that move more tiles shake harder.
+fn shake_strength(action: &Action) -> f32 {
+    match action {
+        Action::SwapTwoAdjacent { .. } | Action::SwapAcross { .. } | Action::SwapRows { .. } => 1.,
+        Action::SwapFirstAndLast { .. }
+        | Action::SwapTwoNatures { .. }
+        | Action::MoveTo { .. }
+        | Action::Wildcard => 1.5,
+        Action::Cycle { .. } | Action::Reverse { .. } => 2.,
+    }
+}
+
+pub(crate) fn camera_shake_on_card_play(
+    mut ev_card_played: EventReader<CardPlayed>,
+    motion_settings: Res<MotionSettings>,
+    clock: Res<AnimationClock>,
+    q_camera: Query<Entity, With<MainCamera>>,
+    mut commands: Commands,
+) {
+    for CardPlayed { action, .. } in ev_card_played.iter() {
+        if motion_settings.reduced_motion {
+            continue;
+        }
+        for camera in q_camera.iter() {
+            commands.entity(camera).insert(CameraShake {
+                offset: SpringSimulation::new(
+                    shake_spring(),
+                    0.,
+                    0.,
+                    SHAKE_VELOCITY * shake_strength(action),
+                    0.5,
+                ),
+                started_at: clock.now(),
+            });
+        }
+    }
+}
+
+pub(crate) fn animate_camera_shake(
+    mut q_camera: Query<(Entity, &CameraShake, &mut Transform)>,
+    clock: Res<AnimationClock>,
+    mut commands: Commands,
+) {
+    for (entity, shake, mut transform) in q_camera.iter_mut() {
+        let offset = shake.offset.x_or_end_x(clock.now() - shake.started_at);
+        transform.translation.y = offset.x;
+        if offset.is_done {
+            commands.entity(entity).remove::<CameraShake>();
+        }
+    }
+}
+
+/// Lights up a column the moment a card moves its tiles, and fades out.
+#[derive(Component)]
+pub struct ColumnFlash;
+
+const COLUMN_FLASH_WIDTH: f32 = 180.;
+const COLUMN_FLASH_COLOR: Color = Color::rgba(1., 0.95, 0.8, 0.35);
+
+fn column_flash_spring() -> SpringDescription {
+    SpringDescription::with_duration_and_bounce(0.4, 0.).expect("a valid spring")
+}
+
+// Actions across both columns flash both.
+pub(crate) fn flash_column_on_card_play(
+    mut ev_card_played: EventReader<CardPlayed>,
+    layout: Res<Layout>,
+    q_tiles: Query<&TileSide, With<Tile>>,
+    mut ev_play_animation: EventWriter<PlayAnimation>,
+    mut commands: Commands,
+) {
+    for CardPlayed { action, .. } in ev_card_played.iter() {
+        let sides = match action_side(action) {
+            Some(side) => vec![side],
+            None => vec![TileSide::Left, TileSide::Right],
+        };
+        for side in sides {
+            let count = q_tiles.iter().filter(|s| **s == side).count();
+            let (left, right) = tiles_layout_poss(TILE_POS_Y_GAP, count, &layout);
+            let col = match side {
+                TileSide::Left => left,
+                TileSide::Right => right,
+            };
+            let (first, last) = match (col.first(), col.last()) {
+                (Some(first), Some(last)) => (*first, *last),
+                _ => continue,
+            };
+            let height = last.y - first.y + COLUMN_FLASH_WIDTH * layout.board_scale(count);
+            let mut transparent = COLUMN_FLASH_COLOR;
+            transparent.set_a(0.);
+            let flash = commands
+                .spawn_bundle(SpriteBundle {
+                    transform: Transform::from_xyz(first.x, (first.y + last.y) / 2., 2.),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(
+                            COLUMN_FLASH_WIDTH * layout.board_scale(count),
+                            height,
+                        )),
+                        color: COLUMN_FLASH_COLOR,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(ColumnFlash)
+                .id();
+            ev_play_animation.send(PlayAnimation {
+                entity: flash,
+                spec: AnimationSpec {
+                    target: AnimationTarget::Tint {
+                        from: COLUMN_FLASH_COLOR,
+                        to: transparent,
+                    },
+                    spring: column_flash_spring(),
+                },
+                delay: 0.,
+                group: None,
+            });
+        }
+    }
+}
+
+pub(crate) fn despawn_faded_column_flashes(
+    mut ev_animator_done: EventReader<AnimatorDone>,
+    q_flashes: Query<(), With<ColumnFlash>>,
+    mut commands: Commands,
+) {
+    for AnimatorDone(entity) in ev_animator_done.iter() {
+        if q_flashes.get(*entity).is_ok() {
+            commands.entity(*entity).despawn_recursive();
+        }
+    }
+}
+
+const DISCARD_PILE_X_ABS: f32 = 620.;
+const DISCARD_PILE_Y_STEP: f32 = 6.;
+// Cards' illustrations sit 10 units above the card, 7 once scaled down, so each card of the
+// pile needs to be a bit more than that above the previous one.
+const DISCARD_PILE_Z_STEP: f32 = 8.;
+const DISCARD_FLING_DRAG: f32 = 0.005;
+
+/// Where the card played in position `order` rests on the discard pile, and its tilt.
+pub(crate) fn discard_pile_slot(order: usize, layout: &Layout) -> (Vec3, f32) {
+    let translation = Vec3::new(
+        layout.side_x(TileSide::Right, DISCARD_PILE_X_ABS),
+        CARDS_Y_POS + DISCARD_PILE_Y_STEP * order as f32,
+        DISCARD_PILE_Z_STEP * order as f32,
+    );
+    let tilt = ((order % 3) as f32 - 1.) * 0.06;
+    (translation, tilt)
+}
+
+/// A card that has been played and is on its way to the discard pile, flung by a
+/// `SimulationAnimator`.
+#[derive(Component)]
+pub struct DiscardToss {
+    target: Vec3,
+    target_tilt: f32,
+}
+
+/// A card resting on the discard pile.
+#[derive(Component)]
+pub struct Discarded;
+
+// Friction fling along one axis that comes to rest exactly on `end`.
+fn fling(start: f32, end: f32) -> FrictionSimulation {
+    FrictionSimulation::new(
+        FrictionDescription::from(DISCARD_FLING_DRAG),
+        start,
+        (start - end) * DISCARD_FLING_DRAG.ln(),
+        20.,
+    )
+}
+
+pub(crate) fn start_discard_toss(
+    mut ev_card_played: EventReader<CardPlayed>,
+    motion_settings: Res<MotionSettings>,
+    layout: Res<Layout>,
+    clock: Res<AnimationClock>,
+    q_transform: Query<&Transform>,
+    mut commands: Commands,
+    mut event_update_cards_style: EventWriter<UpdateCardsStyle>,
+) {
+    for CardPlayed { card, order, .. } in ev_card_played.iter() {
+        if motion_settings.reduced_motion || clock.is_instant() {
+            commands.entity(*card).insert(Discarded);
+            event_update_cards_style.send(UpdateCardsStyle);
+            continue;
+        }
+
+        if let Ok(transform) = q_transform.get(*card) {
+            let (target, target_tilt) = discard_pile_slot(*order, &layout);
+            let channels = vec![
+                SimulationChannel {
+                    field: SimulatedField::TranslationX,
+                    simulation: Box::new(fling(transform.translation.x, target.x)),
+                },
+                SimulationChannel {
+                    field: SimulatedField::TranslationY,
+                    simulation: Box::new(fling(transform.translation.y, target.y)),
+                },
+                SimulationChannel {
+                    field: SimulatedField::Rotation,
+                    simulation: Box::new(SpringSimulation::new(
+                        SpringDescription::from_damping_ratio(1., 60., 0.5),
+                        0.,
+                        target_tilt,
+                        12.,
+                        0.05,
+                    )),
+                },
+            ];
+            let mut translation = transform.translation;
+            translation.z = target.z;
+            commands
+                .entity(*card)
+                .insert(Transform {
+                    translation,
+                    ..*transform
+                })
+                .insert(SimulationAnimator::new(channels, clock.now()))
+                .insert(DiscardToss {
+                    target,
+                    target_tilt,
+                });
+        }
+    }
+}
+
+// The fling stops within a few units of the pile; once it has, the card is put right on it.
+pub fn land_discard_toss(
+    mut q: Query<(Entity, &DiscardToss, &mut Transform), Without<SimulationAnimator>>,
+    mut commands: Commands,
+) {
+    for (entity, toss, mut transform) in q.iter_mut() {
+        transform.translation = toss.target;
+        transform.rotation = Quat::from_rotation_z(toss.target_tilt);
+        commands
+            .entity(entity)
+            .remove::<DiscardToss>()
+            .insert(Discarded);
+    }
+}
+
+const IDLE_BOB_AMPLITUDE: f32 = 3.;
+const IDLE_BOB_PERIOD: f32 = 3.2;
+// A slight tilt back and forth, on a period of its own so it doesn't line up with the bobbing.
+const IDLE_WOBBLE_ANGLE: f32 = 0.025;
+const IDLE_WOBBLE_PERIOD: f32 = 4.3;
+// How long the bobbing waits after tiles are moved, and how long it takes to come back.
+const IDLE_BOB_PAUSE: f32 = 0.8;
+const IDLE_BOB_FADE_IN: f32 = 1.;
+
+/// When the bobbing of a tile's sprite last paused, the sprite's own transform holds the bob.
+#[derive(Component, Default)]
+pub struct IdleBob {
+    paused_at: f32,
+}
+
+// Bobs the `TileSprite` child of each tile, so the tile's own transform stays with the layout
+// and its animations. The bobbing pauses while the tile moves, and starts over from rest after.
+pub(crate) fn idle_bob_tiles(
+    mut ev_update_tiles_position: EventReader<UpdateTilesPosition>,
+    motion_settings: Res<MotionSettings>,
+    clock: Res<AnimationClock>,
+    q_tiles: Query<(&Animator, Option<&SimulationAnimator>), With<Tile>>,
+    mut q: Query<(Entity, &Parent, &mut IdleBob, &mut Transform), With<TileSprite>>,
+) {
+    let now = clock.now();
+    let tiles_moved = ev_update_tiles_position.iter().count() > 0;
+    for (entity, parent, mut bob, mut transform) in q.iter_mut() {
+        let (animator, simulation) = match q_tiles.get(parent.0) {
+            Ok(tile) => tile,
+            Err(_) => continue,
+        };
+        let (offset, tilt) = if tiles_moved
+            || animator.is_animating(AnimatedProperty::Translation)
+            || simulation.is_some()
+        {
+            bob.paused_at = now;
+            (0., 0.)
+        } else if motion_settings.reduced_motion {
+            (0., 0.)
+        } else {
+            let t = now - bob.paused_at - IDLE_BOB_PAUSE;
+            let fade = (t / IDLE_BOB_FADE_IN).clamp(0., 1.);
+            // Golden ratio spread, so neighbouring tiles never bob in sync.
+            let phase = entity.id() as f32 * 0.618;
+            let angle = (now / IDLE_BOB_PERIOD + phase) * std::f32::consts::TAU;
+            let wobble_angle = (now / IDLE_WOBBLE_PERIOD + phase) * std::f32::consts::TAU;
+            (
+                IDLE_BOB_AMPLITUDE * fade * fade * angle.sin(),
+                IDLE_WOBBLE_ANGLE * fade * fade * wobble_angle.sin(),
+            )
+        };
+        if transform.translation.y != offset {
+            transform.translation.y = offset;
+        }
+        let rotation = Quat::from_rotation_z(tilt);
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+    }
+}
+
+const CLEANUP_CARDS_Y_POS: f32 = -900.;
+
+fn cleanup_spring() -> SpringDescription {
+    SpringDescription::with_duration_and_bounce(0.6, -0.15).expect("a valid spring")
+}
+
+/// Sent once the winning move has brought all tiles to their slots.
+pub(crate) struct WinningMoveSettled;
+
+pub(crate) fn wait_for_winning_move(
+    q_victory: Query<(), Added<VictoryScreen>>,
+    tile_movement: Res<TileMovement>,
+    groups: Res<AnimationGroups>,
+    mut ev_group_done: EventReader<AnimationGroupDone>,
+    mut waiting_for_tiles: Local<Option<AnimationGroup>>,
+    mut ev_settled: EventWriter<WinningMoveSettled>,
+) {
+    let mut tiles_settled = false;
+    if !q_victory.is_empty() {
+        match tile_movement
+            .group
+            .filter(|group| groups.is_pending(*group))
+        {
+            Some(group) => *waiting_for_tiles = Some(group),
+            None => tiles_settled = true,
+        }
+    }
+    for AnimationGroupDone(group) in ev_group_done.iter() {
+        if *waiting_for_tiles == Some(*group) {
+            *waiting_for_tiles = None;
+            tiles_settled = true;
+        }
+    }
+    if tiles_settled {
+        ev_settled.send(WinningMoveSettled);
+    }
+}
+
+// Tiles pulse one after the other from top to bottom, and the cards left in hand are cleared
+// off the board while the victory text is up.
+pub(crate) fn celebrate_victory(
+    mut ev_settled: EventReader<WinningMoveSettled>,
+    mut motion: Motion,
+    match_state: Res<MatchState>,
+    layout: Res<Layout>,
+    q_tiles: Query<(Entity, &Slot, &TileSide), With<Tile>>,
+    q_card: Query<(&Transform, &TextureAtlasSprite, Option<&Used>), With<Card>>,
+) {
+    if ev_settled.iter().count() == 0 || motion.snaps() {
+        return;
+    }
+    let match_state = match match_state.as_ref() {
+        MatchState::Playing(match_state) => match_state,
+        _ => return,
+    };
+    // Top row first, left tile before right one.
+    let mut tiles = q_tiles.iter().collect::<Vec<_>>();
+    let board_scale = Vec3::splat(layout.board_scale(tiles.len() / 2));
+    tiles
+        .sort_by_key(|(_, Slot(slot), side)| (std::cmp::Reverse(*slot), **side == TileSide::Right));
+    let pulse = tiles
+        .iter()
+        .map(|(id, _, _)| {
+            (
+                *id,
+                AnimationSpec {
+                    target: AnimationTarget::Scale {
+                        from: board_scale * 1.2,
+                        to: board_scale,
+                    },
+                    spring: SpringDescription::with_duration_and_bounce(0.45, 0.6)
+                        .expect("a valid spring"),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    animation::stagger(
+        &mut motion.ev_play_animation,
+        &pulse,
+        Stagger {
+            total_delay: 0.4,
+            easing: StaggerEasing::Linear,
+        },
+        None,
+    );
+
+    // Cards slide down and fade out at the same time.
+    let hand = match_state
+        .hand_order
+        .iter()
+        .filter_map(|card| match q_card.get(*card) {
+            Ok((transform, sprite, None)) => Some((*card, (transform, sprite))),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let cleanup_stagger = Stagger {
+        total_delay: 0.3,
+        easing: StaggerEasing::EaseIn,
+    };
+    let slide_out = hand
+        .iter()
+        .map(|(id, (transform, _))| {
+            let from = transform.translation;
+            (
+                *id,
+                AnimationSpec {
+                    target: AnimationTarget::Translation {
+                        from,
+                        to: Vec3::new(from.x, CLEANUP_CARDS_Y_POS, from.z),
+                    },
+                    spring: cleanup_spring(),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    animation::stagger(
+        &mut motion.ev_play_animation,
+        &slide_out,
+        cleanup_stagger,
+        None,
+    );
+    let fade_out = hand
+        .iter()
+        .map(|(id, (_, sprite))| {
+            let mut transparent = sprite.color;
+            transparent.set_a(0.);
+            (
+                *id,
+                AnimationSpec {
+                    target: AnimationTarget::Tint {
+                        from: sprite.color,
+                        to: transparent,
+                    },
+                    spring: cleanup_spring(),
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    animation::stagger(
+        &mut motion.ev_play_animation,
+        &fade_out,
+        cleanup_stagger,
+        None,
+    );
+}
+
+const WIN_SLOW_MOTION_SPEED: f32 = 0.3;
+// In real seconds, the slow motion shouldn't drag on just because it slows time down.
+const WIN_SLOW_MOTION_DURATION: f64 = 0.8;
+
+// Slows the winning move down, then snaps back to normal speed.
+pub(crate) fn slow_motion_on_win(
+    mut ev_match_won: EventReader<MatchWon>,
+    motion_settings: Res<MotionSettings>,
+    time: Res<Time>,
+    mut time_scale: ResMut<TimeScale>,
+    mut slow_motion_until: Local<Option<f64>>,
+) {
+    let now = time.seconds_since_startup();
+    if ev_match_won.iter().count() > 0 && !motion_settings.reduced_motion {
+        time_scale.speed = WIN_SLOW_MOTION_SPEED;
+        *slow_motion_until = Some(now + WIN_SLOW_MOTION_DURATION);
+    }
+    if let Some(until) = *slow_motion_until {
+        if now >= until {
+            time_scale.speed = 1.;
+            *slow_motion_until = None;
+        }
+    }
+}
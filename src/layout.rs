@@ -0,0 +1,195 @@
+//! Where things are drawn: the camera zoom that fits the design area in the window, the
+//! mirrored layout and the HUD density.
+
+use bevy::{prelude::*, window::WindowResized};
+
+use crate::{
+    controls::{InputAction, InputMap},
+    core::TileSide,
+    juice,
+    match_plugin::{Card, MatchState, UpdateCardsStyle, UpdateTilesPosition, CARDS_GAP},
+    toast::ShowToast,
+};
+
+// Mirroring only changes where things are drawn: the left column stays the left column for
+// the game logic, it is just drawn on the right.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HudDensity {
+    Full,
+    // Only what is needed to play, with a slightly bigger board. Meant for clean captures.
+    Minimal,
+}
+
+pub(crate) struct Layout {
+    pub(crate) mirrored: bool,
+    pub(crate) hud: HudDensity,
+    // World units per window pixel, so that the design area fills the window.
+    pub(crate) view_scale: f32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            mirrored: false,
+            hud: HudDensity::Full,
+            view_scale: 1.,
+        }
+    }
+}
+
+// Everything is laid out to fit in this area around the centre, in world units. The camera
+// zooms to keep all of it in view, whatever the window's size and shape.
+const DESIGN_WIDTH: f32 = 1600.;
+const DESIGN_HEIGHT: f32 = 1300.;
+
+pub(crate) struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Layout>()
+            .add_system(toggle_layout_options.after("leaderboard_input"))
+            .add_system(fit_view_to_window.before("apply_layout"))
+            .add_system(apply_layout.label("apply_layout"))
+            .add_system(mirror_card_illustrations);
+    }
+}
+
+// Runs once the window exists, then again whenever it is resized.
+fn fit_view_to_window(
+    windows: Res<Windows>,
+    mut ev_resized: EventReader<WindowResized>,
+    mut fitted: Local<bool>,
+    mut layout: ResMut<Layout>,
+) {
+    let resized = ev_resized.iter().count() > 0;
+    if *fitted && !resized {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) if window.width() > 0. && window.height() > 0. => window,
+        // Minimized.
+        _ => return,
+    };
+    *fitted = true;
+    let view_scale = (DESIGN_WIDTH / window.width()).max(DESIGN_HEIGHT / window.height());
+    if layout.view_scale != view_scale {
+        layout.view_scale = view_scale;
+    }
+}
+
+// Boards with more rows than this are scaled down to the height this many take.
+pub(crate) const FITTING_BOARD_ROWS: usize = 4;
+
+// Not a system! How much a board `rows` tall is scaled down to fit, 1 if it fits as it is.
+pub(crate) fn rows_scale(rows: usize) -> f32 {
+    (FITTING_BOARD_ROWS as f32 / rows.max(1) as f32).min(1.)
+}
+
+impl Layout {
+    // The scale of a board `rows` tall.
+    pub(crate) fn board_scale(&self, rows: usize) -> f32 {
+        let hud_scale = match self.hud {
+            HudDensity::Full => 1.,
+            HudDensity::Minimal => 1.12,
+        };
+        hud_scale * rows_scale(rows)
+    }
+
+    pub(crate) fn side_x(&self, side: TileSide, x_abs: f32) -> f32 {
+        match (side, self.mirrored) {
+            (TileSide::Left, false) | (TileSide::Right, true) => -x_abs,
+            (TileSide::Left, true) | (TileSide::Right, false) => x_abs,
+        }
+    }
+
+    pub(crate) fn card_pos_x(&self, i: usize, count: usize) -> f32 {
+        let tot_card_len = CARDS_GAP * (count.saturating_sub(1) as f32);
+        let x = CARDS_GAP * (i as f32) - (tot_card_len / 2.);
+        if self.mirrored {
+            -x
+        } else {
+            x
+        }
+    }
+}
+
+// Whether the card's illustration children are currently drawn mirrored.
+#[derive(Component)]
+pub(crate) struct MirroredIllustration(pub(crate) bool);
+
+fn toggle_layout_options(
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut layout: ResMut<Layout>,
+    mut ev_toast: EventWriter<ShowToast>,
+) {
+    if input_map.just_pressed(InputAction::ToggleMirroredLayout, &keyboard_input) {
+        layout.mirrored = !layout.mirrored;
+        ev_toast.send(ShowToast(
+            if layout.mirrored {
+                "Mirrored layout on"
+            } else {
+                "Mirrored layout off"
+            }
+            .to_string(),
+        ));
+    }
+    if input_map.just_pressed(InputAction::ToggleMinimalHud, &keyboard_input) {
+        layout.hud = match layout.hud {
+            HudDensity::Full => HudDensity::Minimal,
+            HudDensity::Minimal => HudDensity::Full,
+        };
+    }
+}
+
+// HUD that is hidden in the minimal HUD density.
+#[derive(Component)]
+pub(crate) struct NonEssentialHud;
+
+fn apply_layout(
+    layout: Res<Layout>,
+    match_state: Res<MatchState>,
+    mut q_hud: Query<&mut Visibility, With<NonEssentialHud>>,
+    // A punch zooms relative to the view scale itself.
+    mut q_camera: Query<
+        &mut OrthographicProjection,
+        (With<juice::MainCamera>, Without<juice::CameraPunch>),
+    >,
+    mut update_tiles_position_event: EventWriter<UpdateTilesPosition>,
+    mut event_update_cards_style: EventWriter<UpdateCardsStyle>,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+
+    for mut visibility in q_hud.iter_mut() {
+        visibility.is_visible = layout.hud == HudDensity::Full;
+    }
+    for mut projection in q_camera.iter_mut() {
+        projection.scale = layout.view_scale;
+    }
+    if let MatchState::Playing(_) = match_state.as_ref() {
+        update_tiles_position_event.send(UpdateTilesPosition);
+        event_update_cards_style.send(UpdateCardsStyle);
+    }
+}
+
+// Card illustrations are laid out symmetrically around the card centre, so negating their x
+// is enough to mirror them.
+fn mirror_card_illustrations(
+    layout: Res<Layout>,
+    mut q_cards: Query<(&mut MirroredIllustration, &Children), With<Card>>,
+    mut q_transform: Query<&mut Transform, Without<Card>>,
+) {
+    for (mut mirrored, children) in q_cards.iter_mut() {
+        if mirrored.0 == layout.mirrored {
+            continue;
+        }
+        mirrored.0 = layout.mirrored;
+        for child in children.iter() {
+            if let Ok(mut transform) = q_transform.get_mut(*child) {
+                transform.translation.x = -transform.translation.x;
+            }
+        }
+    }
+}
@@ -0,0 +1,454 @@
+//! The best runs of each mode, saved to `leaderboard.ron`. A run that makes it onto its table
+//! asks for a name, typed in or picked on an on-screen keyboard, and the tables can be browsed
+//! from the main menu.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hud::format_match_time,
+    score::RunEnded,
+    storage::{self, Versioned},
+    GameMode,
+};
+
+const LEADERBOARD_PATH: &str = "leaderboard.ron";
+const TABLE_SIZE: usize = 10;
+const TABLES: [&str; 5] = ["Classic", "Timed", "Daily", "Seeded", "Deck"];
+const NAME_LENGTH: usize = 10;
+const KEYS_PER_ROW: usize = 10;
+// The keys that type a name, used up while it's typed so their shortcuts don't fire too.
+const TYPING_KEYS: [KeyCode; 46] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+];
+
+// The table a mode's runs go to. The tutorial and demos aren't scored, and co-op runs aren't one
+// player's.
+fn table_of(mode: GameMode) -> Option<usize> {
+    match mode {
+        GameMode::Classic => Some(0),
+        GameMode::Timed => Some(1),
+        GameMode::Daily(_) => Some(2),
+        GameMode::Seeded(_) => Some(3),
+        GameMode::Deck => Some(4),
+        GameMode::Coop | GameMode::Tutorial | GameMode::Demo => None,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    /// Seconds spent in matches over the run.
+    pub time: f32,
+}
+
+/// The top runs of each mode, best first. Ties go to the faster run.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Leaderboard {
+    pub version: u32,
+    pub tables: BTreeMap<String, Vec<HighScore>>,
+    /// The name entered last, offered again for the next high score.
+    pub last_name: String,
+}
+
+impl Default for Leaderboard {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            tables: BTreeMap::new(),
+            last_name: String::new(),
+        }
+    }
+}
+
+impl Versioned for Leaderboard {
+    const VERSION: u32 = 1;
+
+    fn version(&mut self) -> &mut u32 {
+        &mut self.version
+    }
+}
+
+impl Leaderboard {
+    /// Reads the leaderboard, starting an empty one when there is none. A file that can't be
+    /// parsed is kept aside as `leaderboard.ron.bak` rather than overwritten by the next score.
+    pub fn load() -> Self {
+        storage::load(LEADERBOARD_PATH)
+    }
+
+    // Not a system!
+    pub fn save(&self) {
+        storage::save(LEADERBOARD_PATH, self);
+    }
+
+    fn table(&self, table: usize) -> &[HighScore] {
+        self.tables
+            .get(TABLES[table])
+            .map_or(&[], |scores| scores.as_slice())
+    }
+
+    fn qualifies(&self, table: usize, score: u32) -> bool {
+        let scores = self.table(table);
+        score > 0 && (scores.len() < TABLE_SIZE || scores.iter().any(|s| s.score < score))
+    }
+
+    fn insert(&mut self, table: usize, high_score: HighScore) {
+        let scores = self.tables.entry(TABLES[table].to_string()).or_default();
+        scores.push(high_score);
+        scores.sort_by(|a, b| {
+            b.score.cmp(&a.score).then(
+                a.time
+                    .partial_cmp(&b.time)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+        });
+        scores.truncate(TABLE_SIZE);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Key {
+    Letter(char),
+    Delete,
+    Done,
+}
+
+fn keyboard_keys() -> Vec<Key> {
+    ('A'..='Z')
+        .map(Key::Letter)
+        .chain([Key::Delete, Key::Done])
+        .collect()
+}
+
+fn key_label(key: Key) -> String {
+    match key {
+        Key::Letter(letter) => letter.to_string(),
+        Key::Delete => "Del".to_string(),
+        Key::Done => "OK".to_string(),
+    }
+}
+
+struct NameEntry {
+    table: usize,
+    score: u32,
+    time: f32,
+    name: String,
+    // The key of the on-screen keyboard under the cursor.
+    key: usize,
+}
+
+enum LeaderboardView {
+    Table(usize),
+    NameEntry(NameEntry),
+}
+
+/// The leaderboard screen, when open: browsing the tables, or typing in the name of a new high
+/// score.
+#[derive(Default)]
+pub struct LeaderboardScreen(Option<LeaderboardView>);
+
+impl LeaderboardScreen {
+    pub fn is_open(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn show_tables(&mut self) {
+        self.0 = Some(LeaderboardView::Table(0));
+    }
+}
+
+#[derive(Component)]
+pub struct LeaderboardUi;
+
+#[derive(Component)]
+pub struct LeaderboardText;
+
+pub(crate) fn offer_name_entry(
+    mut ev_run_ended: EventReader<RunEnded>,
+    leaderboard: Res<Leaderboard>,
+    mut screen: ResMut<LeaderboardScreen>,
+) {
+    for RunEnded { mode, score, time } in ev_run_ended.iter() {
+        let table = match table_of(*mode) {
+            Some(table) if leaderboard.qualifies(table, *score) => table,
+            _ => continue,
+        };
+        screen.0 = Some(LeaderboardView::NameEntry(NameEntry {
+            table,
+            score: *score,
+            time: *time,
+            name: leaderboard.last_name.clone(),
+            key: 0,
+        }));
+    }
+}
+
+// Letters and digits type the name, the arrows and Space use the on-screen keyboard, Return saves
+// and Esc skips. Runs before the menus and the global shortcuts, so the keys it uses up aren't
+// seen by them.
+pub(crate) fn leaderboard_input(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut ev_char: EventReader<ReceivedCharacter>,
+    mut screen: ResMut<LeaderboardScreen>,
+    mut leaderboard: ResMut<Leaderboard>,
+) {
+    let typed = ev_char
+        .iter()
+        .filter(|ev| ev.char.is_ascii_alphanumeric())
+        .map(|ev| ev.char.to_ascii_uppercase())
+        .collect::<String>();
+    // Only borrowed mutably on a key press, so the screen isn't redrawn every frame.
+    let browsing = match screen.0.as_ref() {
+        Some(LeaderboardView::Table(table)) => Some(*table),
+        Some(LeaderboardView::NameEntry(_)) => None,
+        None => return,
+    };
+    if let Some(table) = browsing {
+        if keyboard_input.clear_just_pressed(KeyCode::Escape)
+            | keyboard_input.clear_just_pressed(KeyCode::Return)
+            | keyboard_input.clear_just_pressed(KeyCode::Space)
+        {
+            screen.0 = None;
+        } else if keyboard_input.just_pressed(KeyCode::Left) {
+            screen.0 = Some(LeaderboardView::Table(
+                (table + TABLES.len() - 1) % TABLES.len(),
+            ));
+        } else if keyboard_input.just_pressed(KeyCode::Right) {
+            screen.0 = Some(LeaderboardView::Table((table + 1) % TABLES.len()));
+        }
+        return;
+    }
+
+    if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        screen.0 = None;
+        return;
+    }
+    for key in TYPING_KEYS {
+        keyboard_input.clear_just_pressed(key);
+    }
+    let mut done = keyboard_input.clear_just_pressed(KeyCode::Return);
+    let press = keyboard_input.clear_just_pressed(KeyCode::Space);
+    let erase = keyboard_input.just_pressed(KeyCode::Back);
+    let (left, right, up, down) = (
+        keyboard_input.just_pressed(KeyCode::Left),
+        keyboard_input.just_pressed(KeyCode::Right),
+        keyboard_input.just_pressed(KeyCode::Up),
+        keyboard_input.just_pressed(KeyCode::Down),
+    );
+    if !(done || press || erase || left || right || up || down || !typed.is_empty()) {
+        return;
+    }
+    let entry = match screen.0.as_mut() {
+        Some(LeaderboardView::NameEntry(entry)) => entry,
+        _ => return,
+    };
+    let keys = keyboard_keys();
+    if press {
+        match keys[entry.key] {
+            Key::Letter(letter) => entry.name.push(letter),
+            Key::Delete => {
+                entry.name.pop();
+            }
+            Key::Done => done = true,
+        }
+    }
+    if erase {
+        entry.name.pop();
+    }
+    entry.name.push_str(&typed);
+    entry.name.truncate(NAME_LENGTH);
+    if left {
+        entry.key = (entry.key + keys.len() - 1) % keys.len();
+    } else if right {
+        entry.key = (entry.key + 1) % keys.len();
+    } else if up {
+        entry.key = entry.key.saturating_sub(KEYS_PER_ROW);
+    } else if down {
+        entry.key = (entry.key + KEYS_PER_ROW).min(keys.len() - 1);
+    }
+
+    let name = entry.name.trim().to_string();
+    if !done || name.is_empty() {
+        return;
+    }
+    let table = entry.table;
+    leaderboard.insert(
+        table,
+        HighScore {
+            name: name.clone(),
+            score: entry.score,
+            time: entry.time,
+        },
+    );
+    leaderboard.last_name = name;
+    leaderboard.save();
+    // Shows where the run landed.
+    screen.0 = Some(LeaderboardView::Table(table));
+}
+
+fn table_text(leaderboard: &Leaderboard, table: usize) -> String {
+    let scores = leaderboard.table(table);
+    let rows = if scores.is_empty() {
+        "No scores yet".to_string()
+    } else {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                format!(
+                    "{}. {} · {} · {}",
+                    i + 1,
+                    s.name,
+                    s.score,
+                    format_match_time(s.time)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    format!(
+        "High scores · {}\n\n{}\n\n← → mode · Esc close",
+        TABLES[table], rows
+    )
+}
+
+fn name_entry_text(entry: &NameEntry) -> String {
+    let keyboard = keyboard_keys()
+        .chunks(KEYS_PER_ROW)
+        .enumerate()
+        .map(|(row, keys)| {
+            keys.iter()
+                .enumerate()
+                .map(|(i, key)| {
+                    if row * KEYS_PER_ROW + i == entry.key {
+                        format!("[{}]", key_label(*key))
+                    } else {
+                        key_label(*key)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "New high score! {} · {}\n\nName: {}_\n\n{}\n\nArrows and Space type · Return save · Esc skip",
+        entry.score, TABLES[entry.table], entry.name, keyboard
+    )
+}
+
+pub(crate) fn update_leaderboard_screen(
+    screen: Res<LeaderboardScreen>,
+    leaderboard: Res<Leaderboard>,
+    q_ui: Query<Entity, With<LeaderboardUi>>,
+    mut q_text: Query<&mut Text, With<LeaderboardText>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if !screen.is_changed() {
+        return;
+    }
+
+    let value = match screen.0.as_ref() {
+        Some(LeaderboardView::Table(table)) => table_text(&leaderboard, *table),
+        Some(LeaderboardView::NameEntry(entry)) => name_entry_text(entry),
+        None => {
+            for e in q_ui.iter() {
+                commands.entity(e).despawn_recursive();
+            }
+            return;
+        }
+    };
+    if let Ok(mut text) = q_text.get_single_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 78.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: Color::rgba(0., 0., 0., 0.8),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(LeaderboardUi);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                value,
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 30.,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., 0., 79.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(LeaderboardUi)
+        .insert(LeaderboardText);
+}
@@ -0,0 +1,2166 @@
+//! The match itself: dealing the columns and the hand, playing cards, and everything drawn on
+//! the board. Menus, the HUD and the rest of the game are plugins of their own, added next to it
+//! in `main`.
+
+use crate::{
+    animation::{
+        self, AnimationGroup, AnimationSpec, AnimationTarget, Animator, Motion, PlayAnimation,
+        SimulatedField, SimulationAnimator, SimulationChannel, SpringPreset, SpringPresets,
+        Stagger, StaggerEasing,
+    },
+    assets::GameAssets,
+    controls::{ActionInput, InputAction},
+    core::{
+        action_description, affected_tiles, apply_action, apply_inverse_action, columns_match,
+        deal, deal_from_deck, nature_count, redraw_card, Action, CycleDirection, MatchConfig,
+        Puzzle, TileNature, TileSide,
+    },
+    deck::PlayerDeck,
+    flow::FocusPause,
+    juice::{self, DiscardToss, Discarded},
+    layout::{rows_scale, Layout},
+    pool::EntityPool,
+    redraw,
+    theme::{self, CardBorder, Theme},
+    toast::ShowToast,
+    tutorial, wildcard, DespawnAll, GameMode, GameState, MotionSettings, OpenMenus,
+};
+use bevy::{
+    ecs::{component::TableStorage, schedule::ShouldRun, system::SystemParam},
+    prelude::*,
+};
+use jam_motion::gravity::GravitySimulation;
+use rand::prelude::*;
+use std::marker::PhantomData;
+
+/// Deals matches and runs them: the match events and state, the input that plays cards, and
+/// the systems that keep tiles and cards in step with it. Needs the `"view"` stage to exist.
+#[derive(Default)]
+pub(crate) struct MatchPlugin {
+    pub(crate) config: MatchConfig,
+}
+
+impl Plugin for MatchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.config.clone())
+            .insert_resource(MatchState::Ready)
+            .init_resource::<GameRng>()
+            .init_resource::<MatchClock>()
+            .init_resource::<TileMovement>()
+            .add_event::<StartMatchEvent>()
+            .add_event::<UpdateTilesPosition>()
+            .add_event::<UpdateCardsStyle>()
+            .add_event::<MatchWon>()
+            .add_event::<CardPlayed>()
+            .add_event::<CardClicked>()
+            .add_event::<RestartRequest>()
+            .add_event::<UndoRequest>()
+            .add_event::<redraw::CardRedrawn>()
+            .add_startup_system(setup_cursor)
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(
+                        pick_card_with_mouse
+                            .label("pick_card_with_mouse")
+                            .before("handle_input"),
+                    )
+                    .with_system(handle_input.label("handle_input"))
+                    .with_system(
+                        wildcard::pick_tiles
+                            .label("pick_tiles")
+                            .before("handle_input"),
+                    )
+                    .with_system(inspect_card.label("inspect_card"))
+                    .with_system(
+                        redraw::discard_card
+                            .label("discard_card")
+                            .after("handle_input"),
+                    )
+                    .with_system(tick_match_clock.label("tick_match_clock"))
+                    .with_system(end_match.label("match_flow")),
+            )
+            .add_system(start_match.label("start_match").after("recycle"))
+            .add_system(restart.after("handle_input"))
+            .add_system(redraw::deal_redrawn_card.after("discard_card"))
+            .add_system(
+                crate::despawn_all::<InspectOverlay>
+                    .label("recycle")
+                    .after("match_flow"),
+            )
+            .add_system(
+                crate::despawn_all::<RowLink>
+                    .label("recycle")
+                    .after("match_flow"),
+            )
+            .add_system_to_stage(
+                "view",
+                update_cursor
+                    .label("update_cursor")
+                    .with_run_criteria(cursor_needs_update),
+            )
+            .add_system_to_stage(
+                "view",
+                update_tiles_position
+                    .label("update_tiles_position")
+                    .with_run_criteria(on_event::<UpdateTilesPosition>),
+            )
+            .add_system_to_stage(
+                "view",
+                update_cards_style
+                    .label("update_cards_style")
+                    .with_run_criteria(on_event::<UpdateCardsStyle>),
+            )
+            .add_system_to_stage("view", update_tiles_highlight)
+            .add_system_to_stage("view", update_row_links)
+            .add_system_to_stage("view", update_ghost_tiles)
+            .add_system_to_stage("view", wildcard::show_tile_pick);
+    }
+}
+
+pub(crate) const TILES_LEFT: [&str; nature_count()] = [
+    "tile_a_l.png",
+    "tile_b_l.png",
+    "tile_c_l.png",
+    "tile_d_l.png",
+    "tile_e_l.png",
+    "tile_f_l.png",
+    "tile_g_l.png",
+    "tile_h_l.png",
+];
+pub(crate) const TILES_RIGHT: [&str; nature_count()] = [
+    "tile_a_r.png",
+    "tile_b_r.png",
+    "tile_c_r.png",
+    "tile_d_r.png",
+    "tile_e_r.png",
+    "tile_f_r.png",
+    "tile_g_r.png",
+    "tile_h_r.png",
+];
+
+pub(crate) const CARDS_GAP: f32 = 180.;
+pub(crate) const CARDS_Y_POS: f32 = -370.;
+// The hand is dealt one card at a time from a deck below the bottom-left corner.
+pub(crate) const DEAL_IN_Y_POS: f32 = -900.;
+pub(crate) const DEAL_IN_DECK_X_ABS: f32 = 760.;
+pub(crate) const DEAL_IN_STEP: f32 = 0.12;
+// Tiles drop in from this far above their slot when a match starts, bottom row first.
+pub(crate) const TILE_DROP_HEIGHT: f32 = 1000.;
+pub(crate) const TILE_DROP_GRAVITY: f32 = -7000.;
+pub(crate) const TILE_DROP_STAGGER: f32 = 0.06;
+
+// Random seeds are kept to nine digits, short enough to share and type back in.
+pub(crate) const MAX_RANDOM_SEED: u64 = 1_000_000_000;
+
+/// Where the seeds of random matches come from. Everything else random in a match is drawn
+/// from its seed, so the seed is all it takes to deal it again.
+pub(crate) struct GameRng(pub(crate) StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+pub(crate) enum MatchState {
+    Ready,
+    Playing(MatchStatePlaying),
+    Finished(MatchResult),
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct MatchResult {
+    pub(crate) won: bool,
+    pub(crate) cards_used: usize,
+    pub(crate) moves: usize,
+    pub(crate) par: usize,
+}
+
+impl MatchResult {
+    // Three stars for a win at par or better, one less for each two moves over it.
+    pub(crate) fn stars(&self) -> usize {
+        if !self.won {
+            0
+        } else if self.moves <= self.par {
+            3
+        } else if self.moves <= self.par + 2 {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+// Tiles and cards hold their own data as components; this only keeps track of the hand and of
+// which cards the player is pointing at.
+pub(crate) struct MatchStatePlaying {
+    pub(crate) seed: u64,
+    // Cards played so far, counting the ones taken back by undoing or restarting.
+    pub(crate) moves: usize,
+    pub(crate) par: usize,
+    pub(crate) hovered_card: Option<Entity>,
+    // Cards in the order they are shown in the hand, left to right. The player can rearrange
+    // the hand, so this is not the order in which they were dealt.
+    pub(crate) hand_order: Vec<Entity>,
+    // Card whose affected tiles stay highlighted while hovering other cards.
+    pub(crate) pinned_card: Option<Entity>,
+    // Card currently enlarged for inspection. Gameplay input is ignored while set.
+    pub(crate) inspected_card: Option<Entity>,
+    pub(crate) hints_used: usize,
+    pub(crate) input_mode: InputMode,
+    // When set, the only card that can be played. The tutorial walks through its steps with it.
+    pub(crate) playable: Option<Entity>,
+    // Cards that can still be discarded for a new one this match.
+    pub(crate) redraws_left: usize,
+}
+
+// What the match input drives: the hand, or the tiles while a wildcard is being played.
+#[derive(Clone, Copy)]
+pub(crate) enum InputMode {
+    Hand,
+    PickTiles(TilePick),
+    // The tiles have been picked, and the hovered wildcard is played as this swap.
+    Picked(Action),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TilePick {
+    pub(crate) side: TileSide,
+    // Row under the tile cursor, counted from the bottom like the column indices.
+    pub(crate) row: usize,
+    // The second tile has to be in the same column as the first.
+    pub(crate) first: Option<usize>,
+}
+
+impl MatchStatePlaying {
+    // Gameplay input other than picking tiles is ignored while set.
+    pub(crate) fn picking_tiles(&self) -> bool {
+        matches!(self.input_mode, InputMode::PickTiles(_))
+    }
+
+    pub(crate) fn hand_slot(&self, card: Entity) -> usize {
+        self.hand_order.iter().position(|c| *c == card).unwrap()
+    }
+
+    // Moves the hover to the neighbouring slot, wrapping around. If `carry` is set the hovered
+    // card is moved along with the hover.
+    pub(crate) fn step_hover(&mut self, forward: bool, carry: bool) {
+        if let Some(hovered_card) = self.hovered_card {
+            let slot = self.hand_slot(hovered_card);
+            let next_slot = if forward {
+                (slot + 1) % self.hand_order.len()
+            } else if slot == 0 {
+                self.hand_order.len() - 1
+            } else {
+                slot - 1
+            };
+            if carry {
+                self.hand_order.swap(slot, next_slot);
+            } else {
+                self.hovered_card = Some(self.hand_order[next_slot]);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct TileData {
+    pub(crate) id: Entity,
+    pub(crate) nature: TileNature,
+}
+
+// The tile types are shared with the headless rules in `core`, which know nothing of Bevy.
+impl Component for TileNature {
+    type Storage = TableStorage;
+}
+
+impl Component for TileSide {
+    type Storage = TableStorage;
+}
+
+// Position of a tile in its column, from the bottom: slot 0 is drawn lowest.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Slot(pub(crate) usize);
+
+// Rebuilds both columns, bottom to top, from the tiles' components.
+pub(crate) fn tile_columns(
+    tiles: impl Iterator<Item = (Entity, TileSide, Slot, TileNature)>,
+) -> (Vec<TileData>, Vec<TileData>) {
+    let mut tiles = tiles.collect::<Vec<_>>();
+    tiles.sort_by_key(|(_, _, slot, _)| slot.0);
+    let col = |side| {
+        tiles
+            .iter()
+            .filter(|(_, s, _, _)| *s == side)
+            .map(|(id, _, _, nature)| TileData {
+                id: *id,
+                nature: *nature,
+            })
+            .collect()
+    };
+    (col(TileSide::Left), col(TileSide::Right))
+}
+
+#[derive(Component, Clone, Copy)]
+pub(crate) struct Tile;
+
+/// The sprite of a tile, drawn by a child of it so that the idle bob can move it without
+/// fighting the layout and the springs over the tile's own transform.
+#[derive(Component)]
+pub(crate) struct TileSprite;
+
+pub(crate) const TILE_SIZE: f32 = 150.;
+
+#[derive(Component)]
+pub(crate) struct Card;
+
+#[derive(Component)]
+pub(crate) struct CardAction(pub(crate) Action);
+
+// Added next to `Used` when a card was played as another action than its own, like a wildcard
+// played as the swap picked for it.
+#[derive(Component, Clone, Copy)]
+pub(crate) struct PlayedAs(pub(crate) Action);
+
+// Added to a card when it is played, with the order in which it was played.
+#[derive(Component, Clone, Copy)]
+pub(crate) struct Used(pub(crate) usize);
+
+pub(crate) struct StartMatchEvent;
+
+// Sent when the card just played makes the two columns match.
+pub(crate) struct MatchWon;
+
+// Sent after a card's action has been applied to the columns.
+pub(crate) struct CardPlayed {
+    pub(crate) card: Entity,
+    pub(crate) action: Action,
+    pub(crate) order: usize,
+}
+
+pub(crate) const TILE_POS_X_ABS: f32 = 200.;
+pub(crate) const TILE_POS_Y_GAP: f32 = 170.;
+
+pub(crate) fn tiles_layout_poss(gap: f32, count: usize, layout: &Layout) -> (Vec<Vec2>, Vec<Vec2>) {
+    let y_adjust = 150.;
+    let gap = gap * layout.board_scale(count);
+    let tot_col_height = gap * (count.saturating_sub(1) as f32);
+    let mut l = Vec::new();
+    let mut r = Vec::new();
+    for i in 0..count {
+        let pos_y = gap * (i as f32) - (tot_col_height / 2.) + y_adjust;
+        l.push(Vec2::new(
+            layout.side_x(TileSide::Left, TILE_POS_X_ABS * layout.board_scale(count)),
+            pos_y,
+        ));
+        r.push(Vec2::new(
+            layout.side_x(TileSide::Right, TILE_POS_X_ABS * layout.board_scale(count)),
+            pos_y,
+        ));
+    }
+    (l, r)
+}
+
+/// The commands, pool and resources the board's tiles and cards are spawned and drawn with.
+#[derive(SystemParam)]
+pub(crate) struct BoardView<'w, 's> {
+    pub(crate) commands: Commands<'w, 's>,
+    pub(crate) pool: ResMut<'w, EntityPool>,
+    pub(crate) game_assets: Res<'w, GameAssets>,
+    pub(crate) theme: Res<'w, Theme>,
+    pub(crate) layout: Res<'w, Layout>,
+}
+
+impl BoardView<'_, '_> {
+    pub(crate) fn spawn_tile(
+        &mut self,
+        side: TileSide,
+        nature: TileNature,
+        slot: Slot,
+        pos: Vec2,
+        rows: usize,
+    ) -> Entity {
+        let theme = &self.theme;
+        let id = self.pool.spawn_tile(
+            &mut self.commands,
+            Transform {
+                translation: Vec3::new(pos.x, pos.y, 0.),
+                scale: Vec3::splat(self.layout.board_scale(rows)),
+                ..Default::default()
+            },
+            self.game_assets.sheet(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                    ..Default::default()
+                },
+                texture: theme.tile_texture(side, nature, &self.game_assets),
+                ..Default::default()
+            }),
+            |parent| theme::spawn_tile_outline(parent, TILE_SIZE, theme),
+        );
+        self.commands
+            .entity(id)
+            .insert(side)
+            .insert(nature)
+            .insert(slot);
+        id
+    }
+}
+
+pub(crate) const ILLUSTRATION_PIP_SIZE: f32 = 10.;
+pub(crate) const CYCLE_PIP_GAP: f32 = 16.;
+pub(crate) const CYCLE_PIPS_Y_POS: f32 = -115.;
+pub(crate) const ILLUSTRATION_PIP_COLOR: Color = Color::rgb(0.35, 0.3, 0.25);
+pub(crate) const MOVE_TRAIL_GAP: f32 = 12.;
+
+// Not a system!
+pub(crate) fn card_illustration(
+    action: &Action,
+    tiles_count: usize,
+    card_size: f32,
+    game_assets: &GameAssets,
+    theme: &Theme,
+) -> Vec<SpriteSheetBundle> {
+    // Columns of a board too tall to fit are drawn smaller too, to stay inside the card.
+    let card_illustration_full_col_scale = rows_scale(tiles_count);
+    let card_illustration_full_col_gap = 45. * card_illustration_full_col_scale;
+    let card_illustration_tile_size = Vec2::splat(30. * card_illustration_full_col_scale);
+    let card_illustration_full_col_height =
+        card_illustration_full_col_gap * (tiles_count.saturating_sub(1) as f32);
+    let card_illustration_full_col_pos = (0..tiles_count)
+        .map(|i| {
+            card_illustration_full_col_gap * (i as f32) - (card_illustration_full_col_height / 2.)
+        })
+        .collect::<Vec<f32>>();
+
+    let mut illustration = Vec::new();
+    match action {
+        Action::SwapFirstAndLast { side } => {
+            let sprite = Sprite {
+                custom_size: Some(card_illustration_tile_size),
+                ..Default::default()
+            };
+            let pos_x = match side {
+                TileSide::Left => -15.,
+                TileSide::Right => 15.,
+            };
+
+            for (i, &pos_y) in card_illustration_full_col_pos.iter().enumerate() {
+                illustration.push(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(pos_x, pos_y, 10.),
+                        ..Default::default()
+                    },
+                    sprite: sprite.clone(),
+                    texture: game_assets.texture(match side {
+                        TileSide::Left => {
+                            if i == 0 || i == tiles_count - 1 {
+                                "tile_any_l.png"
+                            } else {
+                                "tile_empty_l.png"
+                            }
+                        }
+                        TileSide::Right => {
+                            if i == 0 || i == tiles_count - 1 {
+                                "tile_any_r.png"
+                            } else {
+                                "tile_empty_r.png"
+                            }
+                        }
+                    }),
+                    ..Default::default()
+                });
+            }
+
+            illustration.push(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(
+                        match side {
+                            TileSide::Left => -41.,
+                            TileSide::Right => 41.,
+                        },
+                        0.,
+                        10.,
+                    ),
+
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(card_size, card_size)),
+                    ..Default::default()
+                },
+                texture: game_assets.texture("swap_arrow.png"),
+                ..Default::default()
+            });
+        }
+        Action::SwapTwoAdjacent { top, side } => {
+            let sprite = Sprite {
+                custom_size: Some(card_illustration_tile_size),
+                ..Default::default()
+            };
+            let pos_x = match side {
+                TileSide::Left => -15.,
+                TileSide::Right => 15.,
+            };
+
+            for (i, &pos_y) in card_illustration_full_col_pos.iter().enumerate() {
+                illustration.push(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(pos_x, pos_y, 10.),
+                        ..Default::default()
+                    },
+                    sprite: sprite.clone(),
+                    texture: game_assets.texture(match side {
+                        TileSide::Left => {
+                            if i == *top || i == *top + 1 {
+                                "tile_any_l.png"
+                            } else {
+                                "tile_empty_l.png"
+                            }
+                        }
+                        TileSide::Right => {
+                            if i == *top || i == *top + 1 {
+                                "tile_any_r.png"
+                            } else {
+                                "tile_empty_r.png"
+                            }
+                        }
+                    }),
+                    ..Default::default()
+                });
+            }
+
+            illustration.push(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(
+                        match side {
+                            TileSide::Left => -41.,
+                            TileSide::Right => 41.,
+                        },
+                        0.,
+                        10.,
+                    ),
+
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(card_size, card_size)),
+                    ..Default::default()
+                },
+                texture: game_assets.texture("swap_arrow.png"),
+                ..Default::default()
+            });
+        }
+        Action::SwapTwoNatures {
+            nature_a,
+            nature_b,
+            side,
+        } => {
+            let tile_size = 38.;
+            let pos_y_abs = 30.;
+
+            illustration.push(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(
+                        match side {
+                            TileSide::Left => -tile_size / 2.,
+                            TileSide::Right => tile_size / 2.,
+                        },
+                        -pos_y_abs,
+                        10.,
+                    ),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(tile_size, tile_size)),
+                    ..Default::default()
+                },
+                texture: theme.tile_texture(*side, *nature_a, game_assets),
+                ..Default::default()
+            });
+
+            illustration.push(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(
+                        match side {
+                            TileSide::Left => -tile_size / 2.,
+                            TileSide::Right => tile_size / 2.,
+                        },
+                        pos_y_abs,
+                        10.,
+                    ),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(tile_size, tile_size)),
+                    ..Default::default()
+                },
+                texture: theme.tile_texture(*side, *nature_b, game_assets),
+                ..Default::default()
+            });
+
+            illustration.push(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(
+                        match side {
+                            TileSide::Left => -41.,
+                            TileSide::Right => 41.,
+                        },
+                        0.,
+                        10.,
+                    ),
+
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(card_size, card_size)),
+                    ..Default::default()
+                },
+                texture: game_assets.texture("swap_arrow.png"),
+                ..Default::default()
+            });
+        }
+        Action::Cycle {
+            times,
+            direction,
+            side,
+        } => {
+            let sprite = Sprite {
+                custom_size: Some(card_illustration_tile_size),
+                ..Default::default()
+            };
+            let texture = game_assets.texture(match side {
+                TileSide::Left => "tile_any_l.png",
+                TileSide::Right => "tile_any_r.png",
+            });
+            let pos_x = match side {
+                TileSide::Left => -15.,
+                TileSide::Right => 15.,
+            };
+
+            for &pos_y in card_illustration_full_col_pos.iter() {
+                illustration.push(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(pos_x, pos_y, 10.),
+                        ..Default::default()
+                    },
+                    sprite: sprite.clone(),
+                    texture: texture.clone(),
+                    ..Default::default()
+                });
+            }
+
+            illustration.push(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(
+                        match side {
+                            TileSide::Left => -40.,
+                            TileSide::Right => 40.,
+                        },
+                        0.,
+                        10.,
+                    ),
+
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(card_size, card_size)),
+                    flip_y: match direction {
+                        CycleDirection::Up => false,
+                        CycleDirection::Down => true,
+                    },
+                    ..Default::default()
+                },
+                texture: game_assets.texture("cycle_arrow.png"),
+                ..Default::default()
+            });
+
+            // One pip per row moved, in a line along the bottom of the card.
+            let pips_width = CYCLE_PIP_GAP * (times.saturating_sub(1) as f32);
+            for i in 0..*times {
+                illustration.push(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(
+                            pos_x + CYCLE_PIP_GAP * (i as f32) - pips_width / 2.,
+                            CYCLE_PIPS_Y_POS,
+                            10.,
+                        ),
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(ILLUSTRATION_PIP_SIZE, ILLUSTRATION_PIP_SIZE)),
+                        color: ILLUSTRATION_PIP_COLOR,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            }
+        }
+        Action::Reverse { side } => {
+            let sprite = Sprite {
+                custom_size: Some(card_illustration_tile_size),
+                ..Default::default()
+            };
+            let texture = game_assets.texture(match side {
+                TileSide::Left => "tile_any_l.png",
+                TileSide::Right => "tile_any_r.png",
+            });
+            let pos_x = match side {
+                TileSide::Left => -15.,
+                TileSide::Right => 15.,
+            };
+
+            for pos_y in card_illustration_full_col_pos.iter() {
+                illustration.push(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(pos_x, *pos_y, 10.),
+                        ..Default::default()
+                    },
+                    sprite: sprite.clone(),
+                    texture: texture.clone(),
+                    ..Default::default()
+                });
+            }
+
+            // Reversing swaps the outer pair, then the next pair in, and so on, so the arrows are
+            // nested.
+            for (scale, x_abs) in [(1., 41.), (0.6, 33.)] {
+                illustration.push(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(
+                            match side {
+                                TileSide::Left => -x_abs,
+                                TileSide::Right => x_abs,
+                            },
+                            0.,
+                            10.,
+                        ),
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(card_size, card_size) * scale),
+                        ..Default::default()
+                    },
+                    texture: game_assets.texture("swap_arrow.png"),
+                    ..Default::default()
+                });
+            }
+        }
+        Action::SwapAcross { row } => {
+            let sprite = Sprite {
+                custom_size: Some(card_illustration_tile_size),
+                ..Default::default()
+            };
+            for (pos_x, any, empty) in [
+                (-15., "tile_any_l.png", "tile_empty_l.png"),
+                (15., "tile_any_r.png", "tile_empty_r.png"),
+            ] {
+                for (i, pos_y) in card_illustration_full_col_pos.iter().enumerate() {
+                    illustration.push(SpriteBundle {
+                        transform: Transform {
+                            translation: Vec3::new(pos_x, *pos_y, 10.),
+                            ..Default::default()
+                        },
+                        sprite: sprite.clone(),
+                        texture: game_assets.texture(if i == *row { any } else { empty }),
+                        ..Default::default()
+                    });
+                }
+            }
+
+            // The swap arrow turned on its side, arching over the row.
+            illustration.push(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., card_illustration_full_col_pos[*row] + 30., 10.),
+                    rotation: Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(card_size, card_size) * 0.5),
+                    ..Default::default()
+                },
+                texture: game_assets.texture("swap_arrow.png"),
+                ..Default::default()
+            });
+        }
+        Action::MoveTo { from, to, side } => {
+            let (pos_x, any, empty) = match side {
+                TileSide::Left => (-15., "tile_any_l.png", "tile_empty_l.png"),
+                TileSide::Right => (15., "tile_any_r.png", "tile_empty_r.png"),
+            };
+            for (i, pos_y) in card_illustration_full_col_pos.iter().enumerate() {
+                illustration.push(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(pos_x, *pos_y, 10.),
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        custom_size: Some(card_illustration_tile_size),
+                        // Where the tile ends up is a faded copy of it.
+                        color: Color::rgba(1., 1., 1., if i == *to { 0.4 } else { 1. }),
+                        ..Default::default()
+                    },
+                    texture: game_assets.texture(if i == *from || i == *to { any } else { empty }),
+                    ..Default::default()
+                });
+            }
+
+            // A dotted trail beside the column from one slot to the other, ending in a bigger dot.
+            let from_y = card_illustration_full_col_pos[*from];
+            let to_y = card_illustration_full_col_pos[*to];
+            let dots = ((to_y - from_y).abs() / MOVE_TRAIL_GAP) as usize;
+            for i in 0..=dots {
+                let size = if i == dots {
+                    ILLUSTRATION_PIP_SIZE * 1.6
+                } else {
+                    ILLUSTRATION_PIP_SIZE * 0.6
+                };
+                illustration.push(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(
+                            pos_x * 2.6,
+                            from_y + (to_y - from_y) * (i as f32) / (dots as f32),
+                            10.,
+                        ),
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(size, size)),
+                        color: ILLUSTRATION_PIP_COLOR,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            }
+        }
+        Action::SwapRows { a, b, side } => {
+            let (pos_x, any, empty) = match side {
+                TileSide::Left => (-15., "tile_any_l.png", "tile_empty_l.png"),
+                TileSide::Right => (15., "tile_any_r.png", "tile_empty_r.png"),
+            };
+            for (i, pos_y) in card_illustration_full_col_pos.iter().enumerate() {
+                illustration.push(SpriteBundle {
+                    transform: Transform {
+                        translation: Vec3::new(pos_x, *pos_y, 10.),
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        custom_size: Some(card_illustration_tile_size),
+                        ..Default::default()
+                    },
+                    texture: game_assets.texture(if i == *a || i == *b { any } else { empty }),
+                    ..Default::default()
+                });
+            }
+
+            illustration.push(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(pos_x * 41. / 15., 0., 10.),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(card_size, card_size)),
+                    ..Default::default()
+                },
+                texture: game_assets.texture("swap_arrow.png"),
+                ..Default::default()
+            });
+        }
+        Action::Wildcard => {
+            let sprite = Sprite {
+                custom_size: Some(card_illustration_tile_size),
+                ..Default::default()
+            };
+            for (pos_x, any) in [(-15., "tile_any_l.png"), (15., "tile_any_r.png")] {
+                for pos_y in card_illustration_full_col_pos.iter() {
+                    illustration.push(SpriteBundle {
+                        transform: Transform {
+                            translation: Vec3::new(pos_x, *pos_y, 10.),
+                            ..Default::default()
+                        },
+                        sprite: sprite.clone(),
+                        texture: game_assets.texture(any),
+                        ..Default::default()
+                    });
+                }
+            }
+
+            // Any two tiles on either side, so the arrow sits between the columns.
+            illustration.push(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., 0., 10.),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(card_size, card_size) * 0.7),
+                    ..Default::default()
+                },
+                texture: game_assets.texture("swap_arrow.png"),
+                ..Default::default()
+            });
+        }
+    }
+    illustration
+        .into_iter()
+        .map(|sprite| game_assets.sheet(sprite))
+        .collect()
+}
+
+/// Deals the puzzles of the current mode: scripted, from a seed, or from the player's deck.
+#[derive(SystemParam)]
+pub(crate) struct Dealer<'w, 's> {
+    config: Res<'w, MatchConfig>,
+    mode: Res<'w, GameMode>,
+    rng: ResMut<'w, GameRng>,
+    deck: ResMut<'w, PlayerDeck>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl Dealer<'_, '_> {
+    /// The tutorial's hand is scripted, its cards can't be swapped for others.
+    pub(crate) fn is_scripted(&self) -> bool {
+        *self.mode == GameMode::Tutorial
+    }
+
+    /// A new card for the hand, in place of a discarded one.
+    pub(crate) fn redraw(&mut self, tiles: &[TileNature], hand: &[Action]) -> Action {
+        redraw_card(self.rng.0.gen_range(0, MAX_RANDOM_SEED), tiles, hand)
+    }
+
+    pub(crate) fn deal(&mut self) -> Puzzle {
+        let config = self.mode.match_config(&self.config);
+        match *self.mode {
+            GameMode::Tutorial => tutorial::tutorial_puzzle(),
+            GameMode::Deck => {
+                self.deck.deal_starter_if_empty(&mut self.rng, &config);
+                let seed = self.rng.0.gen_range(0, MAX_RANDOM_SEED);
+                deal_from_deck(seed, &config, &self.deck.cards)
+            }
+            mode => deal(
+                mode.seed()
+                    .unwrap_or_else(|| self.rng.0.gen_range(0, MAX_RANDOM_SEED)),
+                &config,
+            ),
+        }
+    }
+}
+
+pub(crate) fn start_match(
+    mut start_match_event: EventReader<StartMatchEvent>,
+    mut board: BoardView,
+    mut match_state: ResMut<MatchState>,
+    mut match_clock: ResMut<MatchClock>,
+    mut motion: Motion,
+    mut dealer: Dealer,
+    mut event_update_cards_style: EventWriter<UpdateCardsStyle>,
+) {
+    for _ in start_match_event.iter() {
+        if let Err(err) = dealer.config.validate() {
+            error!("Invalid match config: {}", err);
+            continue;
+        }
+
+        let puzzle = dealer.deal();
+        let tiles_count = puzzle.left_col.len();
+        let card_count = puzzle.cards.len();
+
+        let (tiles_pos_left, tiles_pos_right) =
+            tiles_layout_poss(TILE_POS_Y_GAP, tiles_count, &board.layout);
+        let drop_height = if motion.snaps() { 0. } else { TILE_DROP_HEIGHT };
+        for (i, (l, r)) in puzzle
+            .left_col
+            .iter()
+            .zip(puzzle.right_col.iter())
+            .enumerate()
+        {
+            spawn_row_link(
+                Slot(i),
+                l == r,
+                tiles_pos_left[i].y,
+                tiles_count,
+                &mut board.commands,
+                &board.layout,
+            );
+            for (side, nature, pos) in [
+                (TileSide::Left, *l, tiles_pos_left[i]),
+                (TileSide::Right, *r, tiles_pos_right[i]),
+            ] {
+                let tile = board.spawn_tile(
+                    side,
+                    nature,
+                    Slot(i),
+                    pos + Vec2::Y * drop_height,
+                    tiles_count,
+                );
+                if drop_height > 0. {
+                    let drop = GravitySimulation::new(TILE_DROP_GRAVITY, pos.y + drop_height, 0.)
+                        .with_floor(pos.y);
+                    board.commands.entity(tile).insert(SimulationAnimator::new(
+                        vec![SimulationChannel {
+                            field: SimulatedField::TranslationY,
+                            simulation: Box::new(drop),
+                        }],
+                        // Slot 0 is the bottom row, which lands first.
+                        motion.clock.now() + TILE_DROP_STAGGER * i as f32,
+                    ));
+                }
+            }
+        }
+
+        // Spawn cards.
+        let deck = Vec3::new(
+            board.layout.side_x(TileSide::Left, DEAL_IN_DECK_X_ABS),
+            DEAL_IN_Y_POS,
+            0.,
+        );
+        let mut cards = Vec::new();
+        let card_size = 270.;
+        for (i, card_action) in puzzle.cards.iter().enumerate() {
+            let id = board.pool.spawn_card(
+                &mut board.commands,
+                board.game_assets.sheet(SpriteBundle {
+                    transform: Transform {
+                        translation: if motion.snaps() {
+                            Vec3::new(board.layout.card_pos_x(i, card_count), CARDS_Y_POS, 0.)
+                        } else {
+                            deck
+                        },
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(card_size, card_size)),
+                        color: board.theme.card_back().color,
+                        ..Default::default()
+                    },
+                    texture: board.game_assets.texture("card_bg.png"),
+                    ..Default::default()
+                }),
+                card_illustration(
+                    card_action,
+                    tiles_count,
+                    card_size,
+                    &board.game_assets,
+                    &board.theme,
+                ),
+                |parent| theme::spawn_card_border(parent, card_size),
+            );
+            board.commands.entity(id).insert(CardAction(*card_action));
+            cards.push(id);
+        }
+
+        // Deal the hand from the deck, left to right.
+        if !motion.snaps() {
+            let deal_in = cards
+                .iter()
+                .enumerate()
+                .map(|(i, card)| {
+                    let to = Vec3::new(board.layout.card_pos_x(i, card_count), CARDS_Y_POS, 0.);
+                    (
+                        *card,
+                        AnimationSpec {
+                            target: AnimationTarget::Translation { from: deck, to },
+                            spring: motion.springs.get(SpringPreset::DealIn),
+                        },
+                    )
+                })
+                .collect::<Vec<_>>();
+            animation::stagger(
+                &mut motion.ev_play_animation,
+                &deal_in,
+                Stagger {
+                    total_delay: DEAL_IN_STEP * card_count.saturating_sub(1) as f32,
+                    easing: StaggerEasing::Linear,
+                },
+                None,
+            );
+        }
+
+        *match_clock = MatchClock::default();
+
+        *match_state = MatchState::Playing(MatchStatePlaying {
+            seed: puzzle.seed,
+            moves: 0,
+            par: puzzle.par,
+            hovered_card: cards.first().copied(),
+            hand_order: cards,
+            pinned_card: None,
+            inspected_card: None,
+            hints_used: 0,
+            input_mode: InputMode::Hand,
+            playable: None,
+            redraws_left: redraw::REDRAWS_PER_MATCH,
+        });
+
+        event_update_cards_style.send(UpdateCardsStyle);
+    }
+}
+
+/// Input to the match is locked while a menu is open, and once the last move has been made.
+#[derive(SystemParam)]
+pub(crate) struct InputLock<'w, 's> {
+    menus: OpenMenus<'w, 's>,
+    q_ending: Query<'w, 's, (), With<MatchEnding>>,
+}
+
+impl InputLock<'_, '_> {
+    pub(crate) fn is_locked(&self) -> bool {
+        self.menus.any_open() || !self.q_ending.is_empty()
+    }
+}
+
+/// The player's actions on the match, with the board's layout to tell which way they point.
+#[derive(SystemParam)]
+pub(crate) struct MatchInput<'w, 's> {
+    pub(crate) lock: InputLock<'w, 's>,
+    pub(crate) actions: ActionInput<'w, 's>,
+    pub(crate) layout: Res<'w, Layout>,
+}
+
+impl MatchInput<'_, '_> {
+    pub(crate) fn just_pressed(&self, action: InputAction) -> bool {
+        self.actions.just_pressed(action)
+    }
+
+    // Cards are laid out right to left when mirrored, so the keys are swapped to keep matching
+    // the on-screen direction.
+    pub(crate) fn step_pressed(&self) -> (bool, bool) {
+        let previous = self.just_pressed(InputAction::PreviousCard);
+        let next = self.just_pressed(InputAction::NextCard);
+        if self.layout.mirrored {
+            (next, previous)
+        } else {
+            (previous, next)
+        }
+    }
+}
+
+/// Asks the views to catch up with a change of the match state.
+#[derive(SystemParam)]
+pub(crate) struct ViewUpdates<'w, 's> {
+    pub(crate) tiles_position: EventWriter<'w, 's, UpdateTilesPosition>,
+    pub(crate) cards_style: EventWriter<'w, 's, UpdateCardsStyle>,
+}
+
+/// Everything the player's input can set off.
+#[derive(SystemParam)]
+pub(crate) struct PlayEvents<'w, 's> {
+    views: ViewUpdates<'w, 's>,
+    restart: EventWriter<'w, 's, RestartRequest>,
+    undo: EventWriter<'w, 's, UndoRequest>,
+    toast: EventWriter<'w, 's, ShowToast>,
+    card_played: EventWriter<'w, 's, CardPlayed>,
+    match_won: EventWriter<'w, 's, MatchWon>,
+}
+
+type MovableTile<'a> = (Entity, &'a mut TileSide, &'a mut Slot, &'a TileNature);
+
+pub(crate) fn handle_input(
+    input: MatchInput,
+    mut commands: Commands,
+    mut match_state: ResMut<MatchState>,
+    mut events: PlayEvents,
+    mut ev_card_clicked: EventReader<CardClicked>,
+    mut q_tiles: Query<MovableTile, With<Tile>>,
+    q_cards: Query<(&CardAction, Option<&Used>), With<Card>>,
+) {
+    let card_clicked = ev_card_clicked.iter().count() > 0;
+    if input.lock.is_locked() {
+        return;
+    }
+    if let MatchState::Playing(match_state) = match_state.as_ref() {
+        if match_state.inspected_card.is_some() || match_state.picking_tiles() {
+            return;
+        }
+    }
+
+    let playing = matches!(match_state.as_ref(), MatchState::Playing(_));
+    if playing && input.just_pressed(InputAction::Restart) {
+        events.restart.send(RestartRequest);
+    } else if playing && input.just_pressed(InputAction::Undo) {
+        events.undo.send(UndoRequest);
+    }
+
+    let (go_previous, go_next) = input.step_pressed();
+
+    // Holding shift grabs the hovered card and moves it around the hand.
+    let keyboard = &input.actions.keyboard;
+    let carry = keyboard.pressed(KeyCode::LShift) || keyboard.pressed(KeyCode::RShift);
+
+    if go_previous || go_next {
+        if let MatchState::Playing(match_state) = match_state.as_mut() {
+            // Played cards stay where they are.
+            let carry = carry
+                && match match_state.hovered_card {
+                    Some(card) => !matches!(q_cards.get(card), Ok((_, Some(_)))),
+                    None => false,
+                };
+            match_state.step_hover(go_next, carry);
+            events.views.cards_style.send(UpdateCardsStyle);
+        }
+    }
+
+    if input.just_pressed(InputAction::PinCard) {
+        if let MatchState::Playing(match_state) = match_state.as_mut() {
+            if let Some(hovered_card) = match_state.hovered_card {
+                match_state.pinned_card = if match_state.pinned_card == Some(hovered_card)
+                    || matches!(q_cards.get(hovered_card), Ok((_, Some(_))))
+                {
+                    None
+                } else {
+                    Some(hovered_card)
+                };
+                events.toast.send(ShowToast(
+                    match match_state.pinned_card {
+                        Some(_) => "Card pinned",
+                        None => "Card unpinned",
+                    }
+                    .to_string(),
+                ));
+            }
+            events.views.cards_style.send(UpdateCardsStyle);
+        }
+    }
+
+    if input.just_pressed(InputAction::PlayCard) || card_clicked {
+        if let MatchState::Playing(match_state) = match_state.as_mut() {
+            if let Some(hovered_card) = match_state.hovered_card {
+                if match_state
+                    .playable
+                    .map_or(false, |card| card != hovered_card)
+                {
+                    events
+                        .toast
+                        .send(ShowToast("Play the card the arrow points to".to_string()));
+                    return;
+                }
+                // If card not used.
+                if let Ok((CardAction(card_action), None)) = q_cards.get(hovered_card) {
+                    // A wildcard is played in two steps: the tiles are picked first.
+                    let action = match (card_action, match_state.input_mode) {
+                        (Action::Wildcard, InputMode::Picked(swap)) => swap,
+                        (Action::Wildcard, _) => {
+                            match_state.input_mode = InputMode::PickTiles(TilePick {
+                                side: TileSide::Left,
+                                row: 0,
+                                first: None,
+                            });
+                            events.toast.send(ShowToast(
+                                "Pick two tiles in one column to swap".to_string(),
+                            ));
+                            return;
+                        }
+                        (action, _) => *action,
+                    };
+                    match_state.input_mode = InputMode::Hand;
+                    let (mut left_col, mut right_col) = tile_columns(
+                        q_tiles
+                            .iter()
+                            .map(|(id, side, slot, nature)| (id, *side, *slot, *nature)),
+                    );
+                    apply_action(
+                        &action,
+                        &mut left_col,
+                        &mut right_col,
+                        Box::new(|x| x.nature),
+                    );
+                    // Tiles can change column, so their side is stored too.
+                    for (side, col) in [(TileSide::Left, &left_col), (TileSide::Right, &right_col)]
+                    {
+                        for (i, tile) in col.iter().enumerate() {
+                            if let Ok((_, mut tile_side, mut slot, _)) = q_tiles.get_mut(tile.id) {
+                                *tile_side = side;
+                                *slot = Slot(i);
+                            }
+                        }
+                    }
+
+                    // Store the order in which it was played.
+                    let order = q_cards.iter().filter(|(_, used)| used.is_some()).count();
+                    commands.entity(hovered_card).insert(Used(order));
+                    if let Action::Wildcard = card_action {
+                        commands.entity(hovered_card).insert(PlayedAs(action));
+                    }
+
+                    events.card_played.send(CardPlayed {
+                        card: hovered_card,
+                        action,
+                        order,
+                    });
+                    match_state.moves += 1;
+
+                    if match_state.pinned_card == Some(hovered_card) {
+                        match_state.pinned_card = None;
+                    }
+
+                    // Update cards position.
+                    events.views.tiles_position.send(UpdateTilesPosition);
+
+                    // Check for victory.
+                    let natures =
+                        |col: &[TileData]| col.iter().map(|tile| tile.nature).collect::<Vec<_>>();
+                    let result = MatchResult {
+                        won: columns_match(&natures(&left_col), &natures(&right_col)),
+                        cards_used: order + 1,
+                        moves: match_state.moves,
+                        par: match_state.par,
+                    };
+                    if result.won {
+                        info!("Victory");
+                        events.match_won.send(MatchWon);
+                        commands.spawn().insert(VictoryScreen).insert(MatchEnding {
+                            timer: Timer::from_seconds(2., false),
+                            result,
+                        });
+                    } else if result.cards_used == match_state.hand_order.len() {
+                        info!("Out of cards");
+                        commands.spawn().insert(MatchEnding {
+                            timer: Timer::from_seconds(1., false),
+                            result,
+                        });
+                    }
+                }
+
+                events.views.cards_style.send(UpdateCardsStyle);
+            }
+        }
+    }
+}
+
+// Sent when the player clicks a card, which is hovered by then. Playing it is left to
+// `handle_input`, like the keyboard.
+pub(crate) struct CardClicked;
+
+/// The mouse and the touches on the primary window, and where in the world they point.
+#[derive(SystemParam)]
+pub(crate) struct Pointer<'w, 's> {
+    windows: Res<'w, Windows>,
+    mouse_input: Res<'w, Input<MouseButton>>,
+    touches: Res<'w, Touches>,
+    ev_cursor_moved: EventReader<'w, 's, CursorMoved>,
+    q_camera: Query<
+        'w,
+        's,
+        (&'static GlobalTransform, &'static OrthographicProjection),
+        With<juice::MainCamera>,
+    >,
+}
+
+impl Pointer<'_, '_> {
+    /// Whether the mouse moved since the last call, to be made every frame.
+    pub(crate) fn moved(&mut self) -> bool {
+        self.ev_cursor_moved.iter().count() > 0
+    }
+
+    pub(crate) fn clicked(&self) -> bool {
+        self.mouse_input.just_pressed(MouseButton::Left)
+    }
+
+    /// Where a finger just touched the window, in the same coordinates as the cursor.
+    pub(crate) fn tapped(&self) -> Option<Vec2> {
+        self.windows
+            .get_primary()
+            .and_then(|window| tap_position(&self.touches, window))
+    }
+
+    /// The point of the world at `tapped`, or else under the mouse.
+    pub(crate) fn world_position(&self, tapped: Option<Vec2>) -> Option<Vec2> {
+        let window = self.windows.get_primary()?;
+        let (camera_transform, projection) = self.q_camera.iter().next()?;
+        tapped
+            .or_else(|| window.cursor_position())
+            .map(|cursor| cursor_to_world(cursor, window, camera_transform, projection))
+    }
+}
+
+// Not a system! The point of the world under `cursor`, a position in the window.
+fn cursor_to_world(
+    cursor: Vec2,
+    window: &Window,
+    camera_transform: &GlobalTransform,
+    projection: &OrthographicProjection,
+) -> Vec2 {
+    let window_size = Vec2::new(window.width(), window.height());
+    let from_centre = (cursor - window_size / 2.) * projection.scale;
+    camera_transform.mul_vec3(from_centre.extend(0.)).truncate()
+}
+
+// Not a system! Where a finger just touched the window, in the same coordinates as the cursor.
+// Touch positions grow downwards.
+fn tap_position(touches: &Touches, window: &Window) -> Option<Vec2> {
+    touches.iter_just_pressed().next().map(|touch| {
+        let position = touch.position();
+        Vec2::new(position.x, window.height() - position.y)
+    })
+}
+
+// Cards on the discard pile can't be picked.
+type HandCard = (With<Card>, Without<Discarded>);
+
+// Hovers the card under the mouse when it moves, and clicks the card under it. Cards in the hand
+// overlap, so the card whose centre is closest to the mouse wins. There is nothing to hover with
+// a finger, so tapping a card hovers it and tapping it again clicks it.
+pub(crate) fn pick_card_with_mouse(
+    menus: OpenMenus,
+    mut pointer: Pointer,
+    mut match_state: ResMut<MatchState>,
+    mut event_update_cards_style: EventWriter<UpdateCardsStyle>,
+    mut ev_card_clicked: EventWriter<CardClicked>,
+    q_cards: Query<(Entity, &GlobalTransform, &TextureAtlasSprite), HandCard>,
+) {
+    let cursor_moved = pointer.moved();
+    let clicked = pointer.clicked();
+    let tapped = pointer.tapped();
+    if menus.any_open() || !(cursor_moved || clicked || tapped.is_some()) {
+        return;
+    }
+    let match_state = match match_state.as_mut() {
+        MatchState::Playing(match_state)
+            if match_state.inspected_card.is_none() && !match_state.picking_tiles() =>
+        {
+            match_state
+        }
+        _ => return,
+    };
+    let point = match pointer.world_position(tapped) {
+        Some(point) => point,
+        None => return,
+    };
+
+    let picked = q_cards
+        .iter()
+        .filter(|(_, transform, sprite)| {
+            let half_size =
+                sprite.custom_size.unwrap_or(Vec2::ZERO) * transform.scale.truncate() / 2.;
+            let offset = point - transform.translation.truncate();
+            offset.x.abs() <= half_size.x && offset.y.abs() <= half_size.y
+        })
+        .min_by(|(_, a, _), (_, b, _)| {
+            let a = (point.x - a.translation.x).abs();
+            let b = (point.x - b.translation.x).abs();
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|(card, _, _)| card);
+
+    if let Some(card) = picked {
+        let was_hovered = match_state.hovered_card == Some(card);
+        if !was_hovered {
+            match_state.hovered_card = Some(card);
+            event_update_cards_style.send(UpdateCardsStyle);
+        }
+        if clicked || (tapped.is_some() && was_hovered) {
+            ev_card_clicked.send(CardClicked);
+        }
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct Cursor;
+
+pub(crate) const CURSOR_Y_POS: f32 = -530.;
+
+pub(crate) fn setup_cursor(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(30., 30.)),
+                ..Default::default()
+            },
+            texture: asset_server.load("cursor.png"),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(Cursor)
+        .insert(Animator::default());
+}
+
+// Run criterion for view systems that only have work to do when `T` was sent.
+pub(crate) fn on_event<T: Send + Sync + 'static>(mut ev: EventReader<T>) -> ShouldRun {
+    if ev.iter().next().is_some() {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+// The cursor only moves when the hovered card, the layout or the motion settings change.
+pub(crate) fn cursor_needs_update(
+    match_state: Res<MatchState>,
+    layout: Res<Layout>,
+    motion_settings: Res<MotionSettings>,
+) -> ShouldRun {
+    if match_state.is_changed() || layout.is_changed() || motion_settings.is_changed() {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+pub(crate) fn update_cursor(
+    mut q_cursor: Query<(Entity, &mut Transform, &mut Visibility), With<Cursor>>,
+    match_state: Res<MatchState>,
+    layout: Res<Layout>,
+    motion_settings: Res<MotionSettings>,
+    spring_presets: Res<SpringPresets>,
+    mut ev_play_animation: EventWriter<PlayAnimation>,
+    mut target: Local<Option<Vec3>>,
+) {
+    let (entity, mut transform, mut visibility) = q_cursor.single_mut();
+    match match_state.as_ref() {
+        MatchState::Ready | MatchState::Finished(_) => {
+            visibility.is_visible = false;
+        }
+        MatchState::Playing(match_state) => {
+            match match_state.hovered_card {
+                Some(i) => {
+                    let translation = Vec3::new(
+                        layout.card_pos_x(match_state.hand_slot(i), match_state.hand_order.len()),
+                        CURSOR_Y_POS,
+                        10.,
+                    );
+                    // Glide to the new card, but appear in place when a match starts.
+                    if motion_settings.reduced_motion || !visibility.is_visible {
+                        transform.translation = translation;
+                    } else if *target != Some(translation) {
+                        ev_play_animation.send(PlayAnimation {
+                            entity,
+                            spec: AnimationSpec {
+                                target: AnimationTarget::Translation {
+                                    from: transform.translation,
+                                    to: translation,
+                                },
+                                spring: spring_presets.get(SpringPreset::CursorMove),
+                            },
+                            delay: 0.,
+                            group: None,
+                        });
+                    }
+                    *target = Some(translation);
+                }
+                None => {
+                    visibility.is_visible = false;
+                }
+            }
+            visibility.is_visible = true;
+        }
+    }
+}
+
+// Time spent in the current match, not counting time spent in menus and dialogs or with the
+// window in the background.
+#[derive(Default)]
+pub(crate) struct MatchClock {
+    pub(crate) elapsed: f32,
+    // How much `elapsed` moved on this frame.
+    pub(crate) delta: f32,
+}
+
+pub(crate) fn tick_match_clock(
+    menus: OpenMenus,
+    time: Res<Time>,
+    match_state: Res<MatchState>,
+    focus_pause: Res<FocusPause>,
+    q_ending: Query<(), With<MatchEnding>>,
+    mut clock: ResMut<MatchClock>,
+) {
+    let playing = matches!(match_state.as_ref(), MatchState::Playing(_));
+    let paused = menus.any_open() || focus_pause.0;
+    let ending = !q_ending.is_empty();
+    clock.delta = if playing && !paused && !ending {
+        time.delta_seconds()
+    } else {
+        0.
+    };
+    clock.elapsed += clock.delta;
+}
+
+pub(crate) struct UpdateTilesPosition;
+
+// The tiles animating towards their slots after the latest `UpdateTilesPosition`, if any.
+#[derive(Default)]
+pub(crate) struct TileMovement {
+    pub(crate) group: Option<AnimationGroup>,
+}
+
+type PlacedTile<'a> = (
+    Entity,
+    &'a TileSide,
+    &'a Slot,
+    &'a TileNature,
+    &'a mut Transform,
+    &'a Children,
+);
+
+pub(crate) fn update_tiles_position(
+    mut update_tiles_position_event: EventReader<UpdateTilesPosition>,
+    match_state: Res<MatchState>,
+    mut board: BoardView,
+    mut motion: Motion,
+    mut q: Query<PlacedTile, With<Tile>>,
+    mut q_sprites: Query<&mut TextureAtlasSprite, With<TileSprite>>,
+    mut tile_movement: ResMut<TileMovement>,
+) {
+    for _ in update_tiles_position_event.iter() {
+        match match_state.as_ref() {
+            MatchState::Playing(_) => {
+                let tiles_count = q
+                    .iter()
+                    .filter(|(_, side, _, _, _, _)| **side == TileSide::Left)
+                    .count();
+                let (tiles_pos_left, tiles_pos_right) =
+                    tiles_layout_poss(TILE_POS_Y_GAP, tiles_count, &board.layout);
+
+                let mut tiles_group = None;
+                for (entity, side, slot, nature, mut transform, children) in q.iter_mut() {
+                    // A tile swapped across is drawn as the other side's half.
+                    let index = board.game_assets.atlas_index(&board.theme.tile_texture(
+                        *side,
+                        *nature,
+                        &board.game_assets,
+                    ));
+                    for child in children.iter() {
+                        if let Ok(mut sprite) = q_sprites.get_mut(*child) {
+                            if sprite.index != index {
+                                sprite.index = index;
+                            }
+                        }
+                    }
+                    let pos = match side {
+                        TileSide::Left => tiles_pos_left[slot.0],
+                        TileSide::Right => tiles_pos_right[slot.0],
+                    };
+                    let translation = Vec3::new(pos.x, pos.y, 0.);
+                    if !motion.snaps() && transform.translation != translation {
+                        motion.ev_play_animation.send(PlayAnimation {
+                            entity,
+                            spec: AnimationSpec {
+                                target: AnimationTarget::Translation {
+                                    from: transform.translation,
+                                    to: translation,
+                                },
+                                spring: motion.springs.get(SpringPreset::TileMove),
+                            },
+                            delay: 0.,
+                            group: Some(*tiles_group.get_or_insert_with(|| motion.groups.start())),
+                        });
+                    }
+                    transform.translation = translation;
+                    transform.scale = Vec3::splat(board.layout.board_scale(tiles_count));
+                    // Cuts a drop in short, the tile springs from wherever it got to.
+                    board.commands.entity(entity).remove::<SimulationAnimator>();
+                }
+                tile_movement.group = tiles_group;
+            }
+            // The match may have finished after this update was queued.
+            _ => return,
+        }
+    }
+}
+
+pub(crate) struct UpdateCardsStyle;
+
+type StyledCard<'a> = (
+    Entity,
+    &'a mut Transform,
+    &'a mut TextureAtlasSprite,
+    &'a Children,
+    Option<&'a Used>,
+    Option<&'a Discarded>,
+);
+
+pub(crate) fn update_cards_style(
+    mut update_cards_position_event: EventReader<UpdateCardsStyle>,
+    match_state: Res<MatchState>,
+    mut board: BoardView,
+    mut motion: Motion,
+    mut q: Query<StyledCard, With<Card>>,
+    mut q_border: Query<&mut Visibility, With<CardBorder>>,
+) {
+    for _ in update_cards_position_event.iter() {
+        match match_state.as_ref() {
+            MatchState::Playing(match_state) => {
+                for (entity, mut transform, mut sprite, children, used, discarded) in q.iter_mut() {
+                    let is_hovered = match_state.hovered_card == Some(entity);
+                    let is_used = used.is_some();
+                    let is_inspected = match_state.inspected_card == Some(entity);
+
+                    // High contrast replaces the subtle hover scale with a thick border.
+                    let scale = Vec3::splat(if is_inspected {
+                        INSPECT_SCALE
+                    } else if is_used {
+                        0.7
+                    } else if is_hovered && !board.theme.is_high_contrast() {
+                        1.1
+                    } else {
+                        1.
+                    });
+                    if motion.snaps() {
+                        transform.scale = scale;
+                    } else if transform.scale != scale {
+                        motion.ev_play_animation.send(PlayAnimation {
+                            entity,
+                            spec: AnimationSpec {
+                                target: AnimationTarget::Scale {
+                                    from: transform.scale,
+                                    to: scale,
+                                },
+                                spring: motion.springs.get(SpringPreset::CardHover),
+                            },
+                            delay: 0.,
+                            group: None,
+                        });
+                    }
+
+                    if is_inspected {
+                        transform.translation = Vec3::new(0., INSPECT_CARD_Y_POS, 70.);
+                        transform.rotation = Quat::IDENTITY;
+                        continue;
+                    }
+                    match (used, discarded) {
+                        (Some(Used(order)), Some(_)) => {
+                            let order = *order;
+                            let (translation, tilt) =
+                                juice::discard_pile_slot(order, &board.layout);
+                            transform.translation = translation;
+                            transform.rotation = Quat::from_rotation_z(tilt);
+                        }
+                        // Just played: the discard toss owns the transform until it lands.
+                        (Some(_), None) => (),
+                        (None, _) => {
+                            board
+                                .commands
+                                .entity(entity)
+                                .remove::<DiscardToss>()
+                                .remove::<Discarded>();
+                            transform.translation = Vec3::new(
+                                board.layout.card_pos_x(
+                                    match_state.hand_slot(entity),
+                                    match_state.hand_order.len(),
+                                ),
+                                CARDS_Y_POS,
+                                0.,
+                            );
+                            transform.rotation = Quat::IDENTITY;
+                        }
+                    }
+                    sprite.color = if match_state.pinned_card == Some(entity) {
+                        PINNED_HIGHLIGHT_COLOR
+                    } else {
+                        board.theme.card_back().color
+                    };
+                    for child in children.iter() {
+                        if let Ok(mut visibility) = q_border.get_mut(*child) {
+                            visibility.is_visible =
+                                is_hovered && !is_used && board.theme.is_high_contrast();
+                        }
+                    }
+                }
+            }
+            // The match may have finished after this update was queued.
+            _ => return,
+        }
+    }
+}
+
+pub(crate) const HOVERED_HIGHLIGHT_COLOR: Color = Color::rgb(1., 0.85, 0.5);
+pub(crate) const PINNED_HIGHLIGHT_COLOR: Color = Color::rgb(0.6, 0.8, 1.);
+pub(crate) const HOVERED_AND_PINNED_HIGHLIGHT_COLOR: Color = Color::rgb(0.7, 1., 0.6);
+
+// Tints the tiles moved by the hovered card and by the pinned one, so two-card combinations
+// can be compared.
+pub(crate) fn update_tiles_highlight(
+    match_state: Res<MatchState>,
+    q_cards: Query<(&CardAction, Option<&Used>), With<Card>>,
+    q: Query<(Entity, &TileSide, &Slot, &TileNature, &Children), With<Tile>>,
+    mut q_sprites: Query<&mut TextureAtlasSprite, With<TileSprite>>,
+) {
+    if !match_state.is_changed() {
+        return;
+    }
+
+    if let MatchState::Playing(match_state) = match_state.as_ref() {
+        let (left_col, right_col) = tile_columns(
+            q.iter()
+                .map(|(id, side, slot, nature, _)| (id, *side, *slot, *nature)),
+        );
+        let affected_by = |card: Option<Entity>| match card.map(|c| q_cards.get(c)) {
+            Some(Ok((CardAction(action), None))) => {
+                affected_tiles(action, &left_col, &right_col, Box::new(|x| x.nature))
+                    .iter()
+                    .map(|(side, i)| match side {
+                        TileSide::Left => left_col[*i].id,
+                        TileSide::Right => right_col[*i].id,
+                    })
+                    .collect::<Vec<Entity>>()
+            }
+            _ => Vec::new(),
+        };
+        let hovered = affected_by(match_state.hovered_card);
+        let pinned = affected_by(match_state.pinned_card);
+
+        for (entity, _, _, _, children) in q.iter() {
+            let color = match (hovered.contains(&entity), pinned.contains(&entity)) {
+                (true, true) => HOVERED_AND_PINNED_HIGHLIGHT_COLOR,
+                (true, false) => HOVERED_HIGHLIGHT_COLOR,
+                (false, true) => PINNED_HIGHLIGHT_COLOR,
+                (false, false) => Color::WHITE,
+            };
+            for child in children.iter() {
+                if let Ok(mut sprite) = q_sprites.get_mut(*child) {
+                    sprite.color = color;
+                }
+            }
+        }
+    }
+}
+
+// Sits between the columns in each row, and lights up while both tiles of the row are the same.
+// The match is won once they are all lit.
+#[derive(Component)]
+pub(crate) struct RowLink(Slot);
+
+pub(crate) const ROW_LINK_SIZE: f32 = 24.;
+pub(crate) const ROW_LINK_LIT_COLOR: Color = Color::rgb(1., 0.8, 0.2);
+pub(crate) const ROW_LINK_UNLIT_COLOR: Color = Color::rgba(1., 1., 1., 0.2);
+
+// Not a system!
+fn spawn_row_link(
+    slot: Slot,
+    lit: bool,
+    pos_y: f32,
+    rows: usize,
+    commands: &mut Commands,
+    layout: &Layout,
+) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., pos_y, 0.),
+                rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_4),
+                scale: Vec3::splat(layout.board_scale(rows)),
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(ROW_LINK_SIZE, ROW_LINK_SIZE)),
+                color: if lit {
+                    ROW_LINK_LIT_COLOR
+                } else {
+                    ROW_LINK_UNLIT_COLOR
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(RowLink(slot));
+}
+
+// Follows the tiles: relit whenever they move, and moved with the board when the layout changes.
+pub(crate) fn update_row_links(
+    mut update_tiles_position_event: EventReader<UpdateTilesPosition>,
+    layout: Res<Layout>,
+    q_tiles: Query<(&TileSide, &Slot, &TileNature), With<Tile>>,
+    mut q_links: Query<(&RowLink, &mut Transform, &mut Sprite)>,
+) {
+    if update_tiles_position_event.iter().count() == 0 {
+        return;
+    }
+    let nature_at = |side: TileSide, row: Slot| {
+        q_tiles
+            .iter()
+            .find(|(s, slot, _)| **s == side && **slot == row)
+            .map(|(_, _, nature)| *nature)
+    };
+    let rows = q_links.iter().count();
+    let (tiles_pos_left, _) = tiles_layout_poss(TILE_POS_Y_GAP, rows, &layout);
+    for (RowLink(slot), mut transform, mut sprite) in q_links.iter_mut() {
+        let lit = nature_at(TileSide::Left, *slot) == nature_at(TileSide::Right, *slot);
+        sprite.color = if lit {
+            ROW_LINK_LIT_COLOR
+        } else {
+            ROW_LINK_UNLIT_COLOR
+        };
+        transform.translation.y = tiles_pos_left[slot.0].y;
+        transform.scale = Vec3::splat(layout.board_scale(rows));
+    }
+}
+
+// A tile as it would end up if the hovered card were played.
+#[derive(Component)]
+pub(crate) struct GhostTile;
+
+pub(crate) const GHOST_TILE_ALPHA: f32 = 0.4;
+
+// Previews the hovered card by showing where the tiles it moves would go. The ghosts are rebuilt
+// whenever the hover changes or a card is played.
+pub(crate) fn update_ghost_tiles(
+    match_state: Res<MatchState>,
+    mut board: BoardView,
+    q_cards: Query<(&CardAction, Option<&Used>), With<Card>>,
+    q_tiles: Query<(Entity, &TileSide, &Slot, &TileNature), With<Tile>>,
+    q_ghosts: Query<Entity, With<GhostTile>>,
+) {
+    if !match_state.is_changed() {
+        return;
+    }
+    for e in q_ghosts.iter() {
+        board.commands.entity(e).despawn();
+    }
+
+    let action = match match_state.as_ref() {
+        MatchState::Playing(match_state) => {
+            match match_state.hovered_card.map(|card| q_cards.get(card)) {
+                Some(Ok((CardAction(action), None))) => *action,
+                _ => return,
+            }
+        }
+        _ => return,
+    };
+    let (left_col, right_col) = tile_columns(
+        q_tiles
+            .iter()
+            .map(|(id, side, slot, nature)| (id, *side, *slot, *nature)),
+    );
+    let (mut played_left_col, mut played_right_col) = (left_col.clone(), right_col.clone());
+    apply_action(
+        &action,
+        &mut played_left_col,
+        &mut played_right_col,
+        Box::new(|x| x.nature),
+    );
+
+    let (tiles_pos_left, tiles_pos_right) =
+        tiles_layout_poss(TILE_POS_Y_GAP, left_col.len(), &board.layout);
+    let moved = played_left_col
+        .iter()
+        .zip(left_col.iter())
+        .zip(tiles_pos_left.iter())
+        .map(|tiles| (TileSide::Left, tiles))
+        .chain(
+            played_right_col
+                .iter()
+                .zip(right_col.iter())
+                .zip(tiles_pos_right.iter())
+                .map(|tiles| (TileSide::Right, tiles)),
+        )
+        .filter(|(_, ((played, current), _))| played.id != current.id);
+    for (side, ((tile, _), pos)) in moved {
+        let texture = board
+            .theme
+            .tile_texture(side, tile.nature, &board.game_assets);
+        let ghost = board.game_assets.sheet(SpriteBundle {
+            transform: Transform {
+                translation: pos.extend(1.),
+                scale: Vec3::splat(board.layout.board_scale(left_col.len())),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                color: Color::rgba(1., 1., 1., GHOST_TILE_ALPHA),
+                custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                ..Default::default()
+            },
+            texture,
+            ..Default::default()
+        });
+        board.commands.spawn_bundle(ghost).insert(GhostTile);
+    }
+}
+
+pub(crate) const INSPECT_SCALE: f32 = 2.6;
+pub(crate) const INSPECT_CARD_Y_POS: f32 = 80.;
+
+#[derive(Component)]
+pub(crate) struct InspectOverlay;
+
+/// The dimmed backdrop and the description shown with the inspected card.
+#[derive(SystemParam)]
+pub(crate) struct InspectOverlays<'w, 's> {
+    commands: Commands<'w, 's>,
+    asset_server: Res<'w, AssetServer>,
+    q_overlay: Query<'w, 's, Entity, With<InspectOverlay>>,
+}
+
+impl InspectOverlays<'_, '_> {
+    fn show(&mut self, action: &Action) {
+        // Dim everything behind the enlarged card.
+        self.commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., 0., 60.),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(4000., 4000.)),
+                    color: Color::rgba(0., 0., 0., 0.7),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(InspectOverlay);
+        self.commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    action_description(action),
+                    TextStyle {
+                        font: self.asset_server.load("ReadexPro-Regular.ttf"),
+                        font_size: 26.,
+                        color: Color::WHITE,
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Center,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                ),
+                transform: Transform {
+                    translation: Vec3::new(0., INSPECT_CARD_Y_POS - 420., 75.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(InspectOverlay);
+    }
+
+    fn hide(&mut self) {
+        for e in self.q_overlay.iter() {
+            self.commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+pub(crate) fn inspect_card(
+    menus: OpenMenus,
+    input: ActionInput,
+    mouse_input: Res<Input<MouseButton>>,
+    mut match_state: ResMut<MatchState>,
+    q_cards: Query<&CardAction>,
+    mut overlays: InspectOverlays,
+    mut event_update_cards_style: EventWriter<UpdateCardsStyle>,
+) {
+    let toggle = input.just_pressed(InputAction::InspectCard)
+        || mouse_input.just_pressed(MouseButton::Right);
+    let close = input.keyboard.just_pressed(KeyCode::Escape);
+    if menus.any_open() || !(toggle || close) {
+        return;
+    }
+    let match_state = match match_state.as_mut() {
+        MatchState::Playing(match_state) => match_state,
+        _ => return,
+    };
+
+    if match_state.inspected_card.is_some() {
+        if toggle || close {
+            match_state.inspected_card = None;
+            overlays.hide();
+            event_update_cards_style.send(UpdateCardsStyle);
+        }
+    } else if toggle && !match_state.picking_tiles() {
+        if let Some((hovered_card, CardAction(action))) = match_state
+            .hovered_card
+            .and_then(|c| Some((c, q_cards.get(c).ok()?)))
+        {
+            match_state.inspected_card = Some(hovered_card);
+            overlays.show(action);
+            event_update_cards_style.send(UpdateCardsStyle);
+        }
+    }
+}
+
+pub(crate) struct RestartRequest;
+
+// Takes back the last card played.
+pub(crate) struct UndoRequest;
+
+pub(crate) fn restart(
+    mut ev: EventReader<RestartRequest>,
+    mut ev_undo: EventReader<UndoRequest>,
+    mut match_state: ResMut<MatchState>,
+    mut q_tiles: Query<MovableTile, With<Tile>>,
+    q_used: Query<(Entity, &CardAction, &Used, Option<&PlayedAs>), With<Card>>,
+    mut commands: Commands,
+    mut views: ViewUpdates,
+) {
+    let undo_counts = ev
+        .iter()
+        .map(|_| usize::MAX)
+        .chain(ev_undo.iter().map(|_| 1))
+        .collect::<Vec<_>>();
+    for undo_count in undo_counts {
+        match match_state.as_mut() {
+            // Picking tiles for a wildcard is called off.
+            MatchState::Playing(match_state) => match_state.input_mode = InputMode::Hand,
+            _ => unreachable!(),
+        }
+
+        // Undo the used cards in reverse order.
+        let (mut left_col, mut right_col) = tile_columns(
+            q_tiles
+                .iter()
+                .map(|(id, side, slot, nature)| (id, *side, *slot, *nature)),
+        );
+        let mut used = q_used.iter().collect::<Vec<_>>();
+        used.sort_by_key(|(_, _, Used(order), _)| std::cmp::Reverse(*order));
+        for (card, CardAction(action), _, played_as) in used.into_iter().take(undo_count) {
+            apply_inverse_action(
+                played_as.map_or(action, |PlayedAs(action)| action),
+                &mut left_col,
+                &mut right_col,
+                Box::new(|x| x.nature),
+            );
+            commands.entity(card).remove::<Used>().remove::<PlayedAs>();
+        }
+        for (side, col) in [(TileSide::Left, &left_col), (TileSide::Right, &right_col)] {
+            for (i, tile) in col.iter().enumerate() {
+                if let Ok((_, mut tile_side, mut slot, _)) = q_tiles.get_mut(tile.id) {
+                    *tile_side = side;
+                    *slot = Slot(i);
+                }
+            }
+        }
+        // Captions and highlights depend on which cards are used.
+        match_state.set_changed();
+
+        views.tiles_position.send(UpdateTilesPosition);
+        views.cards_style.send(UpdateCardsStyle);
+    }
+}
+
+// Added next to `MatchEnding` when the match was won.
+#[derive(Component)]
+pub(crate) struct VictoryScreen;
+
+// Spawned by the last move of a match, to leave the board up for a moment before the
+// end-of-match screen.
+#[derive(Component)]
+pub(crate) struct MatchEnding {
+    pub(crate) timer: Timer,
+    pub(crate) result: MatchResult,
+}
+
+// A demo goes back to the main menu instead of the end screen.
+pub(crate) fn end_match(
+    mut q: Query<(Entity, &mut MatchEnding)>,
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    mut commands: Commands,
+    mut ev_despawn_all: EventWriter<DespawnAll>,
+    mut match_state: ResMut<MatchState>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let (entity, mut ending) = match q.get_single_mut() {
+        Ok(ending) => ending,
+        Err(_) => return,
+    };
+    if !ending.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    commands.entity(entity).despawn();
+    ev_despawn_all.send(DespawnAll);
+    let next = if *mode == GameMode::Demo {
+        *match_state = MatchState::Ready;
+        GameState::Menu
+    } else {
+        *match_state = MatchState::Finished(ending.result);
+        GameState::GameOver
+    };
+    if let Err(err) = state.set(next) {
+        warn!("Could not end the match: {:?}", err);
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    animation::AnimationClock,
+    audio::{Sounds, Tone, Voice},
+    core::{TileNature, TileSide},
+    match_plugin::{MatchWon, Slot, Tile, UpdateTilesPosition},
+};
+
+const NOTE_VOLUME: f32 = 0.2;
+// How far to the side tiles of each column are heard, between 0 (centre) and 1.
+const SIDE_PAN: f32 = 0.6;
+// C major pentatonic from middle C, so any notes played together sound fine.
+const NATURE_NOTES: [f32; 8] = [
+    261.63, 293.66, 329.63, 392.00, 440.00, 523.25, 587.33, 659.25,
+];
+// On the animation clock, so the melody keeps pace with the animation speed and waits out a
+// pause with the rest of the celebration.
+const VICTORY_MELODY_DELAY: f32 = 0.4;
+const VICTORY_MELODY_STEP: f32 = 0.22;
+
+// Not a system! The note of a nature, panned to the column of `side`, or to the centre.
+fn note(nature: TileNature, side: Option<TileSide>) -> Tone {
+    let pan = match side {
+        Some(TileSide::Left) => -SIDE_PAN,
+        None => 0.,
+        Some(TileSide::Right) => SIDE_PAN,
+    };
+    Tone {
+        pan,
+        ..Tone::new(
+            Voice::Pluck,
+            NATURE_NOTES[nature.0 % NATURE_NOTES.len()],
+            NOTE_VOLUME,
+        )
+    }
+}
+
+/// Notes waiting to be played, with the `AnimationClock` time to play them at.
+#[derive(Default)]
+pub struct Melody {
+    queue: VecDeque<(f32, Tone)>,
+}
+
+fn play_tile_notes(
+    clock: Res<AnimationClock>,
+    mut sounds: Sounds,
+    mut melody: ResMut<Melody>,
+    mut ev_update_tiles_position: EventReader<UpdateTilesPosition>,
+    mut ev_match_won: EventReader<MatchWon>,
+    mut slots: Local<HashMap<Entity, Slot>>,
+    q_tiles: Query<(Entity, &Slot, &TileSide, &TileNature, ChangeTrackers<Tile>)>,
+) {
+    // Tiles dealt into a new match start from wherever they were dealt, silently.
+    for (e, slot, _, _, tracker) in q_tiles.iter() {
+        if tracker.is_added() {
+            slots.insert(e, *slot);
+        }
+    }
+
+    if ev_update_tiles_position.iter().count() > 0 {
+        for (e, slot, side, nature, _) in q_tiles.iter() {
+            if slots
+                .insert(e, *slot)
+                .map_or(false, |previous| previous != *slot)
+            {
+                sounds.play(note(*nature, Some(*side)));
+            }
+        }
+    }
+
+    // The solved column, top to bottom.
+    if ev_match_won.iter().count() > 0 {
+        let mut column = q_tiles
+            .iter()
+            .filter(|(_, _, side, _, _)| **side == TileSide::Left)
+            .map(|(_, slot, _, nature, _)| (slot.0, *nature))
+            .collect::<Vec<_>>();
+        column.sort_by_key(|(slot, _)| Reverse(*slot));
+        let start = clock.now() + VICTORY_MELODY_DELAY;
+        for (i, (_, nature)) in column.iter().enumerate() {
+            melody
+                .queue
+                .push_back((start + VICTORY_MELODY_STEP * i as f32, note(*nature, None)));
+        }
+    }
+
+    let now = clock.now();
+    while let Some((at, _)) = melody.queue.front() {
+        if *at > now {
+            break;
+        }
+        if let Some((_, tone)) = melody.queue.pop_front() {
+            sounds.play(tone);
+        }
+    }
+}
+
+/// Plays a note per tile as it moves, and the solved column as a melody on victory. Needs the
+/// `SoundPlugin` for the tones.
+pub struct MelodyPlugin;
+
+impl Plugin for MelodyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Melody>()
+            .add_system_to_stage("view", play_tile_notes);
+    }
+}
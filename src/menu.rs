@@ -0,0 +1,395 @@
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    controls::{self, ControlsMenu, ShortcutOverlay, UpdateControlsMenu},
+    cosmetics::{self, CustomizeScreen, UpdateCustomizeScreen},
+    credits::{self, Credits},
+    daily::{self, daily_seed, SeedEntry, UpdateSeedEntry},
+    despawn_ui,
+    leaderboard::{self, LeaderboardScreen},
+    match_plugin::MatchState,
+    modal::{Modal, ModalAction, ModalButton, OpenModal},
+    options::{self, OptionsMenu, UpdateOptionsMenu},
+    profile::PlayerProfile,
+    theme::Theme,
+    timed::Countdown,
+    DespawnAll, GameMode, GameState,
+};
+
+const MAIN_MENU_ENTRIES: [&str; 11] = [
+    "Play",
+    "Timed",
+    "Daily",
+    "Deck",
+    "Co-op",
+    "Tutorial",
+    "Seed",
+    "Scores",
+    "Customize",
+    "Options",
+    "Quit",
+];
+const PAUSE_MENU_ENTRIES: [&str; 4] = ["Resume", "Options", "Main menu", "Quit"];
+
+/// The main and pause menus, and the screens opened from them or over them: options, seed
+/// entry, scores, customization, controls and credits.
+pub(crate) struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UpdateControlsMenu>()
+            .add_event::<UpdateOptionsMenu>()
+            .add_event::<UpdateSeedEntry>()
+            .add_event::<UpdateCustomizeScreen>()
+            .init_resource::<MenuSelection>()
+            .init_resource::<ControlsMenu>()
+            .init_resource::<ShortcutOverlay>()
+            .init_resource::<Credits>()
+            .init_resource::<OptionsMenu>()
+            .init_resource::<SeedEntry>()
+            .init_resource::<LeaderboardScreen>()
+            .init_resource::<CustomizeScreen>()
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(spawn_main_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::Menu).with_system(
+                    main_menu_input
+                        .after("options_menu_input")
+                        .after("seed_entry_input")
+                        .after("leaderboard_input")
+                        .after("customize_screen_input")
+                        .before("controls_menu_input"),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Menu).with_system(despawn_ui::<MainMenuUi>),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(spawn_pause_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused).with_system(
+                    pause_menu_input
+                        .after("options_menu_input")
+                        .before("modal_input")
+                        .before("controls_menu_input"),
+                ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused).with_system(despawn_ui::<PauseMenuUi>),
+            )
+            .add_system(
+                options::options_menu_input
+                    .label("options_menu_input")
+                    .before("controls_menu_input"),
+            )
+            .add_system(options::update_options_menu)
+            .add_system(daily::seed_entry_input.label("seed_entry_input"))
+            .add_system(daily::update_seed_entry)
+            .add_system(leaderboard::offer_name_entry.before("leaderboard_input"))
+            .add_system(leaderboard::leaderboard_input.label("leaderboard_input"))
+            .add_system(leaderboard::update_leaderboard_screen)
+            .add_system(cosmetics::customize_screen_input.label("customize_screen_input"))
+            .add_system(cosmetics::update_customize_screen)
+            .add_system(controls::controls_menu_input.label("controls_menu_input"))
+            .add_system(controls::update_controls_menu)
+            .add_system(controls::shortcut_overlay)
+            .add_system(credits::toggle_credits.label("credits_input"))
+            .add_system(credits::scroll_credits.after("credits_input"));
+    }
+}
+
+/// The entry picked in whichever of the main and pause menus is open.
+#[derive(Default)]
+pub struct MenuSelection(usize);
+
+#[derive(Component, Clone, Copy)]
+pub struct MainMenuUi;
+
+#[derive(Component, Clone, Copy)]
+pub struct PauseMenuUi;
+
+#[derive(Component)]
+pub struct MenuEntriesText;
+
+fn entries_text(entries: &[&str], selected: usize) -> String {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            if i == selected {
+                format!("> {} <", entry)
+            } else {
+                entry.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The keys and the selection of whichever of the main and pause menus is open.
+#[derive(SystemParam)]
+pub(crate) struct MenuNavigation<'w, 's> {
+    keyboard_input: ResMut<'w, Input<KeyCode>>,
+    selection: ResMut<'w, MenuSelection>,
+    q_text: Query<'w, 's, &'static mut Text, With<MenuEntriesText>>,
+}
+
+impl MenuNavigation<'_, '_> {
+    // Up and Down move the selection, Return or Space picks it. The key picking an entry is used
+    // up, so whatever comes next doesn't also see it pressed this frame.
+    fn navigate(&mut self, entries: &[&str]) -> Option<usize> {
+        if self.keyboard_input.clear_just_pressed(KeyCode::Return)
+            | self.keyboard_input.clear_just_pressed(KeyCode::Space)
+        {
+            return Some(self.selection.0);
+        }
+        if self.keyboard_input.just_pressed(KeyCode::Up) {
+            self.selection.0 = (self.selection.0 + entries.len() - 1) % entries.len();
+        } else if self.keyboard_input.just_pressed(KeyCode::Down) {
+            self.selection.0 = (self.selection.0 + 1) % entries.len();
+        } else {
+            return None;
+        }
+        for mut text in self.q_text.iter_mut() {
+            text.sections[0].value = entries_text(entries, self.selection.0);
+        }
+        None
+    }
+}
+
+/// The screens opened from the main and pause menus, which take the input while open.
+#[derive(SystemParam)]
+pub(crate) struct MenuScreens<'w, 's> {
+    controls_menu: Res<'w, ControlsMenu>,
+    options_menu: ResMut<'w, OptionsMenu>,
+    seed_entry: ResMut<'w, SeedEntry>,
+    leaderboard_screen: ResMut<'w, LeaderboardScreen>,
+    customize_screen: ResMut<'w, CustomizeScreen>,
+    modal: Res<'w, Modal>,
+    ev_update_options_menu: EventWriter<'w, 's, UpdateOptionsMenu>,
+    ev_update_seed_entry: EventWriter<'w, 's, UpdateSeedEntry>,
+    ev_update_customize_screen: EventWriter<'w, 's, UpdateCustomizeScreen>,
+}
+
+impl MenuScreens<'_, '_> {
+    pub(crate) fn any_open(&self) -> bool {
+        self.controls_menu.open
+            || self.options_menu.open
+            || self.seed_entry.open
+            || self.leaderboard_screen.is_open()
+            || self.customize_screen.open
+            || self.modal.is_open()
+    }
+
+    fn show_options(&mut self) {
+        self.options_menu.show();
+        self.ev_update_options_menu.send(UpdateOptionsMenu);
+    }
+
+    fn show_seed_entry(&mut self) {
+        self.seed_entry.show();
+        self.ev_update_seed_entry.send(UpdateSeedEntry);
+    }
+
+    fn show_customize(&mut self) {
+        self.customize_screen.show();
+        self.ev_update_customize_screen.send(UpdateCustomizeScreen);
+    }
+}
+
+// Not a system!
+fn spawn_menu<T: Component + Copy>(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    marker: T,
+    title: &str,
+    entries: &[&str],
+    background: Color,
+    text_color: Color,
+) {
+    let font = asset_server.load("ReadexPro-Regular.ttf");
+    let alignment = TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Center,
+    };
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 76.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: background,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(marker);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                title,
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 60.,
+                    color: text_color,
+                },
+                alignment,
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., 160., 77.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(marker);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                entries_text(entries, 0),
+                TextStyle {
+                    font,
+                    font_size: 34.,
+                    color: text_color,
+                },
+                alignment,
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., -40., 77.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(marker)
+        .insert(MenuEntriesText);
+}
+
+pub(crate) fn spawn_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    mut selection: ResMut<MenuSelection>,
+) {
+    *selection = MenuSelection::default();
+    spawn_menu(
+        &mut commands,
+        &asset_server,
+        MainMenuUi,
+        "ggj-2022",
+        &MAIN_MENU_ENTRIES,
+        theme.background_color(),
+        theme.text_color(),
+    );
+}
+
+pub(crate) fn main_menu_input(
+    mut navigation: MenuNavigation,
+    mut screens: MenuScreens,
+    mut state: ResMut<State<GameState>>,
+    mut mode: ResMut<GameMode>,
+    mut countdown: ResMut<Countdown>,
+    profile: Res<PlayerProfile>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    if screens.any_open() {
+        return;
+    }
+    match navigation.navigate(&MAIN_MENU_ENTRIES) {
+        Some(entry @ 0..=5) => {
+            *mode = match entry {
+                // New players are walked through the tutorial first.
+                0 if !profile.tutorial_done => GameMode::Tutorial,
+                0 => GameMode::Classic,
+                1 => {
+                    // A timed run always starts with the full countdown.
+                    *countdown = Countdown::default();
+                    GameMode::Timed
+                }
+                2 => GameMode::Daily(daily_seed()),
+                3 => GameMode::Deck,
+                4 => GameMode::Coop,
+                _ => GameMode::Tutorial,
+            };
+            if let Err(err) = state.set(GameState::InGame) {
+                warn!("Could not start the game: {:?}", err);
+            }
+        }
+        Some(6) => screens.show_seed_entry(),
+        Some(7) => screens.leaderboard_screen.show_tables(),
+        Some(8) => screens.show_customize(),
+        Some(9) => screens.show_options(),
+        Some(_) => ev_exit.send(AppExit),
+        None => (),
+    }
+}
+
+pub(crate) fn spawn_pause_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut selection: ResMut<MenuSelection>,
+) {
+    *selection = MenuSelection::default();
+    spawn_menu(
+        &mut commands,
+        &asset_server,
+        PauseMenuUi,
+        "Paused",
+        &PAUSE_MENU_ENTRIES,
+        Color::rgba(0., 0., 0., 0.8),
+        Color::WHITE,
+    );
+}
+
+// Esc resumes too. Quitting from here asks first, since the match would be lost.
+pub(crate) fn pause_menu_input(
+    mut navigation: MenuNavigation,
+    mut screens: MenuScreens,
+    mut state: ResMut<State<GameState>>,
+    mut match_state: ResMut<MatchState>,
+    mut ev_despawn_all: EventWriter<DespawnAll>,
+    mut ev_open_modal: EventWriter<OpenModal>,
+) {
+    if screens.any_open() {
+        return;
+    }
+    let chosen = if navigation
+        .keyboard_input
+        .clear_just_pressed(KeyCode::Escape)
+    {
+        Some(0)
+    } else {
+        navigation.navigate(&PAUSE_MENU_ENTRIES)
+    };
+    let next = match chosen {
+        Some(0) => GameState::InGame,
+        Some(1) => {
+            screens.show_options();
+            return;
+        }
+        Some(2) => {
+            ev_despawn_all.send(DespawnAll);
+            *match_state = MatchState::Ready;
+            GameState::Menu
+        }
+        Some(_) => {
+            ev_open_modal.send(OpenModal {
+                title: "Quit".to_string(),
+                body: "Do you really want to quit?".to_string(),
+                buttons: vec![
+                    ModalButton {
+                        label: "Quit".to_string(),
+                        action: ModalAction::Quit,
+                    },
+                    ModalButton {
+                        label: "Cancel".to_string(),
+                        action: ModalAction::Dismiss,
+                    },
+                ],
+            });
+            return;
+        }
+        None => return,
+    };
+    if let Err(err) = state.set(next) {
+        warn!("Could not leave the pause menu: {:?}", err);
+    }
+}
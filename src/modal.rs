@@ -0,0 +1,224 @@
+use bevy::prelude::*;
+
+/// What picking a modal button does. Sent back as a `ModalResult` once the modal closes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ModalAction {
+    Quit,
+    AbandonMatch,
+    DeleteCrashLog,
+    KeepCrashLog,
+    Dismiss,
+}
+
+pub struct ModalButton {
+    pub label: String,
+    pub action: ModalAction,
+}
+
+/// Send this to open a modal. Ignored if one is already open.
+pub struct OpenModal {
+    pub title: String,
+    pub body: String,
+    pub buttons: Vec<ModalButton>,
+}
+
+pub struct ModalResult(pub ModalAction);
+
+/// The open modal, if any. While set, the modal owns keyboard input: other input systems should
+/// check `is_open` and bail out.
+#[derive(Default)]
+pub struct Modal {
+    buttons: Vec<ModalButton>,
+    selected: usize,
+}
+
+impl Modal {
+    pub fn is_open(&self) -> bool {
+        !self.buttons.is_empty()
+    }
+}
+
+#[derive(Component)]
+pub struct ModalUi;
+
+#[derive(Component)]
+pub struct ModalButtonsText;
+
+pub(crate) struct ModalPlugin;
+
+impl Plugin for ModalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenModal>()
+            .add_event::<ModalResult>()
+            .init_resource::<Modal>()
+            .add_system(open_modals.after("modal_input"))
+            .add_system(modal_input.label("modal_input"));
+    }
+}
+
+fn buttons_text(modal: &Modal) -> String {
+    modal
+        .buttons
+        .iter()
+        .enumerate()
+        .map(|(i, button)| {
+            if i == modal.selected {
+                format!("[ {} ]", button.label)
+            } else {
+                format!("  {}  ", button.label)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("    ")
+}
+
+pub fn open_modals(
+    mut ev: EventReader<OpenModal>,
+    mut modal: ResMut<Modal>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    for open in ev.iter() {
+        if modal.is_open() || open.buttons.is_empty() {
+            continue;
+        }
+
+        let font = asset_server.load("ReadexPro-Regular.ttf");
+        let alignment = TextAlignment {
+            vertical: VerticalAlign::Center,
+            horizontal: HorizontalAlign::Center,
+        };
+        commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., 0., 85.),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(4000., 4000.)),
+                    color: Color::rgba(0., 0., 0., 0.5),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(ModalUi);
+        commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., 0., 86.),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(700., 300.)),
+                    color: Color::rgb(0.15, 0.13, 0.12),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(ModalUi);
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text {
+                    sections: vec![
+                        TextSection {
+                            value: format!("{}\n\n", open.title),
+                            style: TextStyle {
+                                font: font.clone(),
+                                font_size: 36.,
+                                color: Color::WHITE,
+                            },
+                        },
+                        TextSection {
+                            value: open.body.clone(),
+                            style: TextStyle {
+                                font: font.clone(),
+                                font_size: 26.,
+                                color: Color::WHITE,
+                            },
+                        },
+                    ],
+                    alignment,
+                },
+                transform: Transform {
+                    translation: Vec3::new(0., 40., 87.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(ModalUi);
+
+        modal.buttons = open
+            .buttons
+            .iter()
+            .map(|b| ModalButton {
+                label: b.label.clone(),
+                action: b.action,
+            })
+            .collect();
+        modal.selected = 0;
+
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    buttons_text(&modal),
+                    TextStyle {
+                        font,
+                        font_size: 30.,
+                        color: Color::WHITE,
+                    },
+                    alignment,
+                ),
+                transform: Transform {
+                    translation: Vec3::new(0., -90., 87.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(ModalUi)
+            .insert(ModalButtonsText);
+    }
+}
+
+/// Input layer of the open modal: Left/Right pick a button, Space/Return confirm and Esc picks
+/// the last button, which by convention is the cancelling one.
+pub fn modal_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut modal: ResMut<Modal>,
+    q_ui: Query<Entity, With<ModalUi>>,
+    mut q_buttons: Query<&mut Text, With<ModalButtonsText>>,
+    mut commands: Commands,
+    mut ev_result: EventWriter<ModalResult>,
+) {
+    if !modal.is_open() {
+        return;
+    }
+
+    let buttons_count = modal.buttons.len();
+    let chosen = if keyboard_input.just_pressed(KeyCode::Escape) {
+        Some(buttons_count - 1)
+    } else if keyboard_input.just_pressed(KeyCode::Space)
+        || keyboard_input.just_pressed(KeyCode::Return)
+    {
+        Some(modal.selected)
+    } else {
+        if keyboard_input.just_pressed(KeyCode::Left) {
+            modal.selected = (modal.selected + buttons_count - 1) % buttons_count;
+        } else if keyboard_input.just_pressed(KeyCode::Right) {
+            modal.selected = (modal.selected + 1) % buttons_count;
+        } else {
+            return;
+        }
+        for mut text in q_buttons.iter_mut() {
+            text.sections[0].value = buttons_text(&modal);
+        }
+        None
+    };
+
+    if let Some(chosen) = chosen {
+        ev_result.send(ModalResult(modal.buttons[chosen].action));
+        *modal = Modal::default();
+        for e in q_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
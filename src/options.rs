@@ -0,0 +1,417 @@
+use std::{cmp::Ordering, marker::PhantomData};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    animation::{TimeScale, INSTANT_SPEED},
+    audio::SoundSettings,
+    controls::{ControlsMenu, InputMap, UpdateControlsMenu},
+    core::{nature_count, MatchConfig, MIN_BOARD_ROWS},
+    modal::Modal,
+    settings::{PendingSave, Settings},
+    telemetry::{self, Telemetry},
+    theme::{ContrastMode, Theme},
+    MotionSettings,
+};
+
+const ANIMATION_SPEEDS: [f32; 6] = [0.5, 0.75, 1., 1.5, 2., INSTANT_SPEED];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum OptionsRow {
+    Volume,
+    AnimationSpeed,
+    ReducedMotion,
+    HighContrast,
+    Palette,
+    BoardSize,
+    Telemetry,
+    Controls,
+}
+
+impl OptionsRow {
+    const ALL: [OptionsRow; 8] = [
+        OptionsRow::Volume,
+        OptionsRow::AnimationSpeed,
+        OptionsRow::ReducedMotion,
+        OptionsRow::HighContrast,
+        OptionsRow::Palette,
+        OptionsRow::BoardSize,
+        OptionsRow::Telemetry,
+        OptionsRow::Controls,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            OptionsRow::Volume => "Volume",
+            OptionsRow::AnimationSpeed => "Animation speed",
+            OptionsRow::ReducedMotion => "Reduced motion",
+            OptionsRow::HighContrast => "High contrast",
+            OptionsRow::Palette => "Colour blind palette",
+            OptionsRow::BoardSize => "Board size",
+            OptionsRow::Telemetry => "Send anonymous match stats",
+            OptionsRow::Controls => "Controls",
+        }
+    }
+}
+
+/// The options screen, opened from the main and pause menus. Every option applies as soon as
+/// it is changed and is saved with the settings.
+#[derive(Default)]
+pub struct OptionsMenu {
+    pub open: bool,
+    selected: usize,
+}
+
+impl OptionsMenu {
+    /// Send `UpdateOptionsMenu` to draw it.
+    pub fn show(&mut self) {
+        *self = OptionsMenu {
+            open: true,
+            ..Default::default()
+        };
+    }
+}
+
+pub struct UpdateOptionsMenu;
+
+#[derive(Component)]
+pub struct OptionsMenuUi;
+
+#[derive(Component)]
+pub struct OptionsMenuText;
+
+/// The resources the options change.
+#[derive(SystemParam)]
+pub(crate) struct Options<'w, 's> {
+    sound: ResMut<'w, SoundSettings>,
+    time_scale: ResMut<'w, TimeScale>,
+    motion_settings: ResMut<'w, MotionSettings>,
+    theme: ResMut<'w, Theme>,
+    config: ResMut<'w, MatchConfig>,
+    telemetry: ResMut<'w, Telemetry>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl Options<'_, '_> {
+    fn value(&self, row: OptionsRow) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+        match row {
+            OptionsRow::Volume => self.sound.label(),
+            OptionsRow::AnimationSpeed if self.time_scale.is_instant() => "Instant".to_string(),
+            OptionsRow::AnimationSpeed => format!("{}x", self.time_scale.preference),
+            OptionsRow::ReducedMotion => on_off(self.motion_settings.reduced_motion),
+            OptionsRow::HighContrast => on_off(self.theme.is_high_contrast()),
+            OptionsRow::Palette => self.theme.palette_name().to_string(),
+            OptionsRow::BoardSize => {
+                format!("{} rows, from the next match", self.config.tiles_count)
+            }
+            OptionsRow::Telemetry if !telemetry::SUPPORTED => "Not from the browser".to_string(),
+            OptionsRow::Telemetry => on_off(self.telemetry.opted_in),
+            OptionsRow::Controls => "Return to change".to_string(),
+        }
+    }
+
+    fn step(&mut self, row: OptionsRow, forward: bool) {
+        match row {
+            OptionsRow::Volume => self.sound.step_volume(if forward { 1. } else { -1. }),
+            OptionsRow::AnimationSpeed => {
+                let current = ANIMATION_SPEEDS
+                    .iter()
+                    .position(|speed| *speed >= self.time_scale.preference)
+                    .unwrap_or(ANIMATION_SPEEDS.len() - 1);
+                let next = if forward {
+                    (current + 1).min(ANIMATION_SPEEDS.len() - 1)
+                } else {
+                    current.saturating_sub(1)
+                };
+                self.time_scale.preference = ANIMATION_SPEEDS[next];
+            }
+            OptionsRow::ReducedMotion => {
+                self.motion_settings.reduced_motion = !self.motion_settings.reduced_motion;
+            }
+            OptionsRow::HighContrast => {
+                self.theme.contrast = if self.theme.is_high_contrast() {
+                    ContrastMode::Normal
+                } else {
+                    ContrastMode::High
+                };
+            }
+            OptionsRow::Palette => self.theme.step_palette(forward),
+            OptionsRow::BoardSize => {
+                let rows = if forward {
+                    self.config.tiles_count + 1
+                } else {
+                    self.config.tiles_count.saturating_sub(1)
+                };
+                self.config.tiles_count = rows.clamp(MIN_BOARD_ROWS, nature_count());
+            }
+            OptionsRow::Telemetry => {
+                self.telemetry.opted_in = telemetry::SUPPORTED && !self.telemetry.opted_in;
+            }
+            OptionsRow::Controls => (),
+        }
+    }
+}
+
+fn options_menu_text(options_menu: &OptionsMenu, options: &Options) -> String {
+    let mut lines = vec!["Options".to_string(), String::new()];
+    for (i, row) in OptionsRow::ALL.iter().enumerate() {
+        lines.push(format!(
+            "{} {}: {}",
+            if i == options_menu.selected { ">" } else { " " },
+            row.label(),
+            options.value(*row)
+        ));
+    }
+    lines.push(String::new());
+    lines.push("Up/Down select · Left/Right change · Esc close".to_string());
+    lines.join("\n")
+}
+
+// Runs before the menus and the controls menu, so the keys it uses up aren't seen by them.
+pub(crate) fn options_menu_input(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut options_menu: ResMut<OptionsMenu>,
+    mut options: Options,
+    mut controls_menu: ResMut<ControlsMenu>,
+    modal: Res<Modal>,
+    mut ev_update: EventWriter<UpdateOptionsMenu>,
+    mut ev_update_controls_menu: EventWriter<UpdateControlsMenu>,
+) {
+    if !options_menu.open || controls_menu.open || modal.is_open() {
+        return;
+    }
+
+    let row = OptionsRow::ALL[options_menu.selected];
+    if keyboard_input.clear_just_pressed(KeyCode::Escape) {
+        options_menu.open = false;
+    } else if keyboard_input.just_pressed(KeyCode::Up) {
+        options_menu.selected =
+            (options_menu.selected + OptionsRow::ALL.len() - 1) % OptionsRow::ALL.len();
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        options_menu.selected = (options_menu.selected + 1) % OptionsRow::ALL.len();
+    } else if keyboard_input.just_pressed(KeyCode::Left) {
+        options.step(row, false);
+    } else if keyboard_input.just_pressed(KeyCode::Right) {
+        options.step(row, true);
+    } else if keyboard_input.clear_just_pressed(KeyCode::Return) {
+        if row == OptionsRow::Controls {
+            controls_menu.show();
+            ev_update_controls_menu.send(UpdateControlsMenu);
+        } else {
+            options.step(row, true);
+        }
+    } else {
+        return;
+    }
+    ev_update.send(UpdateOptionsMenu);
+}
+
+pub(crate) fn update_options_menu(
+    mut ev_update: EventReader<UpdateOptionsMenu>,
+    options_menu: Res<OptionsMenu>,
+    options: Options,
+    q_ui: Query<Entity, With<OptionsMenuUi>>,
+    mut q_text: Query<&mut Text, With<OptionsMenuText>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if ev_update.iter().count() == 0 {
+        return;
+    }
+
+    if !options_menu.open {
+        for e in q_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+
+    let value = options_menu_text(&options_menu, &options);
+    if let Ok(mut text) = q_text.get_single_mut() {
+        text.sections[0].value = value;
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., 78.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(4000., 4000.)),
+                color: Color::rgba(0., 0., 0., 0.8),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OptionsMenuUi);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                value,
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 30.,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., 0., 79.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OptionsMenuUi)
+        .insert(OptionsMenuText);
+}
+
+// The animation speed in `ANIMATION_SPEEDS` closest to `speed`, so a hand-edited settings file
+// can't stop or reverse animations. Instant is saved as `inf`, and is the only way to get it.
+fn snap_animation_speed(speed: f32) -> f32 {
+    if speed == INSTANT_SPEED {
+        return INSTANT_SPEED;
+    } else if !speed.is_finite() {
+        return 1.;
+    }
+    // Instant is the last listed speed, so the fastest finite one comes before it.
+    let speed = speed.clamp(
+        ANIMATION_SPEEDS[0],
+        ANIMATION_SPEEDS[ANIMATION_SPEEDS.len() - 2],
+    );
+    ANIMATION_SPEEDS
+        .iter()
+        .copied()
+        .filter(|listed| listed.is_finite())
+        .min_by(|a, b| {
+            (a - speed)
+                .abs()
+                .partial_cmp(&(b - speed).abs())
+                .unwrap_or(Ordering::Equal)
+        })
+        .unwrap_or(1.)
+}
+
+// The tile pack and the card back are restored with the other cosmetics.
+pub(crate) fn apply_saved_options(
+    settings: Res<Settings>,
+    mut time_scale: ResMut<TimeScale>,
+    mut motion_settings: ResMut<MotionSettings>,
+    mut theme: ResMut<Theme>,
+    mut config: ResMut<MatchConfig>,
+    mut input_map: ResMut<InputMap>,
+    mut telemetry: ResMut<Telemetry>,
+) {
+    time_scale.preference = snap_animation_speed(settings.animation_speed);
+    motion_settings.reduced_motion = settings.reduced_motion;
+    if settings.high_contrast {
+        theme.contrast = ContrastMode::High;
+    }
+    theme.palette = settings.palette;
+    config.tiles_count = settings.board_rows.clamp(MIN_BOARD_ROWS, nature_count());
+    input_map.restore(&settings.bindings);
+    telemetry.opted_in = settings.telemetry;
+}
+
+/// The resources whose options are saved with the settings.
+#[derive(SystemParam)]
+pub(crate) struct SavedOptions<'w, 's> {
+    time_scale: Res<'w, TimeScale>,
+    motion_settings: Res<'w, MotionSettings>,
+    theme: Res<'w, Theme>,
+    config: Res<'w, MatchConfig>,
+    input_map: Res<'w, InputMap>,
+    telemetry: Res<'w, Telemetry>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl SavedOptions<'_, '_> {
+    fn is_changed(&self) -> bool {
+        self.time_scale.is_changed()
+            || self.motion_settings.is_changed()
+            || self.theme.is_changed()
+            || self.config.is_changed()
+            || self.input_map.is_changed()
+            || self.telemetry.is_changed()
+    }
+}
+
+// Options also change through their shortcuts, so whatever changed them is saved here.
+pub(crate) fn save_options(
+    options: SavedOptions,
+    time: Res<Time>,
+    mut settings: ResMut<Settings>,
+    mut pending_save: ResMut<PendingSave>,
+) {
+    if !options.is_changed() {
+        return;
+    }
+    let SavedOptions {
+        time_scale,
+        motion_settings,
+        theme,
+        config,
+        input_map,
+        telemetry,
+        ..
+    } = &options;
+    let tile_pack = theme.tile_pack.map(|_| theme.tile_pack_name().to_string());
+    let card_back = (theme.card_back != 0).then(|| theme.card_back().name.to_string());
+    let bindings = input_map.changed_bindings();
+    if settings.animation_speed != time_scale.preference
+        || settings.reduced_motion != motion_settings.reduced_motion
+        || settings.high_contrast != theme.is_high_contrast()
+        || settings.tile_pack != tile_pack
+        || settings.card_back != card_back
+        || settings.palette != theme.palette
+        || settings.board_rows != config.tiles_count
+        || settings.bindings != bindings
+        || settings.telemetry != telemetry.opted_in
+    {
+        settings.animation_speed = time_scale.preference;
+        settings.reduced_motion = motion_settings.reduced_motion;
+        settings.high_contrast = theme.is_high_contrast();
+        settings.tile_pack = tile_pack;
+        settings.card_back = card_back;
+        settings.palette = theme.palette;
+        settings.board_rows = config.tiles_count;
+        settings.bindings = bindings;
+        settings.telemetry = telemetry.opted_in;
+        pending_save.request(time.seconds_since_startup());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_animation_speed_survives_the_settings_file() {
+        for speed in ANIMATION_SPEEDS {
+            let settings = Settings {
+                animation_speed: speed,
+                ..Default::default()
+            };
+            let text = ron::ser::to_string(&settings).unwrap();
+            let saved = ron::from_str::<Settings>(&text).unwrap();
+            assert_eq!(snap_animation_speed(saved.animation_speed), speed);
+        }
+    }
+
+    #[test]
+    fn hand_edited_speeds_snap_to_the_listed_ones() {
+        assert_eq!(snap_animation_speed(0.), 0.5);
+        assert_eq!(snap_animation_speed(-3.), 0.5);
+        assert_eq!(snap_animation_speed(1.2), 1.);
+        assert_eq!(snap_animation_speed(1e30), 2.);
+        assert_eq!(snap_animation_speed(f32::NAN), 1.);
+        assert_eq!(snap_animation_speed(f32::NEG_INFINITY), 1.);
+    }
+}
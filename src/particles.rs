@@ -0,0 +1,225 @@
+//! Sprite particles: an emitter spawns a burst of small squares where it is and goes away. Each
+//! particle is launched with friction slowing it down, falls with gravity, and fades out over
+//! its lifetime. Used for dust when tiles land and confetti on a win.
+
+use std::ops::Range;
+
+use bevy::prelude::*;
+use jam_motion::{
+    friction::{FrictionDescription, FrictionSimulation2D},
+    Simulation2D,
+};
+use rand::prelude::*;
+
+use crate::{
+    animation::{AnimatedProperty, AnimationClock, AnimationFinished, SimulationDone},
+    match_plugin::{Tile, VictoryScreen},
+    MotionSettings,
+};
+
+/// A burst of particles, spawned the frame the emitter is added.
+#[derive(Component, Clone)]
+pub struct ParticleEmitter {
+    pub count: usize,
+    /// Each particle picks one.
+    pub colors: &'static [Color],
+    pub size: Range<f32>,
+    /// Launch direction in radians, counter-clockwise from the right, and how far either side of
+    /// it a particle may go.
+    pub angle: f32,
+    pub spread: f32,
+    pub speed: Range<f32>,
+    /// The fraction of its speed a particle keeps after a second.
+    pub drag: f32,
+    pub gravity: f32,
+    pub lifetime: Range<f32>,
+    /// Top turning speed, in radians per second either way.
+    pub spin: f32,
+}
+
+#[derive(Component)]
+pub struct Particle {
+    motion: FrictionSimulation2D,
+    gravity: f32,
+    spin: f32,
+    age: f32,
+    lifetime: f32,
+    alpha: f32,
+}
+
+const DUST_COLORS: &[Color] = &[
+    Color::rgba(0.9, 0.85, 0.75, 0.7),
+    Color::rgba(0.75, 0.7, 0.6, 0.7),
+];
+// Tiles are 150 units tall before the board is scaled, dust rises from their bottom edge.
+const DUST_Y_OFFSET: f32 = -70.;
+
+const CONFETTI_COLORS: &[Color] = &[
+    Color::rgb(1., 0.8, 0.2),
+    Color::rgb(0.95, 0.4, 0.35),
+    Color::rgb(0.45, 0.75, 1.),
+    Color::rgb(0.5, 0.85, 0.5),
+    Color::rgb(0.85, 0.55, 0.95),
+];
+const CONFETTI_Y_POS: f32 = -600.;
+const CONFETTI_Z: f32 = 92.;
+
+fn dust() -> ParticleEmitter {
+    ParticleEmitter {
+        count: 6,
+        colors: DUST_COLORS,
+        size: 6.0..12.,
+        angle: std::f32::consts::FRAC_PI_2,
+        spread: 1.3,
+        speed: 80.0..200.,
+        drag: 0.02,
+        gravity: -150.,
+        lifetime: 0.35..0.6,
+        spin: 0.,
+    }
+}
+
+fn confetti() -> ParticleEmitter {
+    ParticleEmitter {
+        count: 120,
+        colors: CONFETTI_COLORS,
+        size: 10.0..18.,
+        angle: std::f32::consts::FRAC_PI_2,
+        spread: 0.6,
+        speed: 1200.0..2200.,
+        drag: 0.3,
+        gravity: -900.,
+        lifetime: 1.8..2.8,
+        spin: 8.,
+    }
+}
+
+pub(crate) struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(dust_on_tile_landing.after("animate_simulations"))
+            .add_system(confetti_on_victory)
+            .add_system(emit_particles)
+            .add_system(crate::despawn_all::<Particle>)
+            .add_system_to_stage("view", move_particles.label("move_particles"))
+            .add_system_to_stage("view", fade_particles.after("move_particles"))
+            .add_system_to_stage("view", expire_particles.after("move_particles"));
+    }
+}
+
+// Not a system!
+fn spawn_emitter(commands: &mut Commands, emitter: ParticleEmitter, translation: Vec3) {
+    commands
+        .spawn()
+        .insert(emitter)
+        .insert(Transform::from_translation(translation))
+        .insert(GlobalTransform::default());
+}
+
+// Tiles land at the end of their drop when the match starts, and at the end of each move.
+fn dust_on_tile_landing(
+    motion_settings: Res<MotionSettings>,
+    mut ev_simulation_done: EventReader<SimulationDone>,
+    mut ev_animation_finished: EventReader<AnimationFinished>,
+    q_tiles: Query<&Transform, With<Tile>>,
+    mut commands: Commands,
+) {
+    let dropped = ev_simulation_done.iter().map(|SimulationDone(tile)| *tile);
+    let moved = ev_animation_finished
+        .iter()
+        .filter(|finished| finished.property == AnimatedProperty::Translation)
+        .map(|finished| finished.entity);
+    let landed = dropped.chain(moved).collect::<Vec<_>>();
+    if motion_settings.reduced_motion {
+        return;
+    }
+    for tile in landed {
+        if let Ok(transform) = q_tiles.get(tile) {
+            let bottom =
+                transform.translation + Vec3::new(0., DUST_Y_OFFSET * transform.scale.y, 1.);
+            spawn_emitter(&mut commands, dust(), bottom);
+        }
+    }
+}
+
+fn confetti_on_victory(
+    motion_settings: Res<MotionSettings>,
+    q_victory: Query<(), Added<VictoryScreen>>,
+    mut commands: Commands,
+) {
+    if q_victory.is_empty() || motion_settings.reduced_motion {
+        return;
+    }
+    spawn_emitter(
+        &mut commands,
+        confetti(),
+        Vec3::new(0., CONFETTI_Y_POS, CONFETTI_Z),
+    );
+}
+
+fn emit_particles(
+    q_emitters: Query<(Entity, &ParticleEmitter, &Transform), Added<ParticleEmitter>>,
+    mut commands: Commands,
+) {
+    let mut rng = thread_rng();
+    for (entity, emitter, transform) in q_emitters.iter() {
+        let origin = transform.translation;
+        for _ in 0..emitter.count {
+            let angle = emitter.angle + rng.gen_range(-emitter.spread, emitter.spread);
+            let speed = rng.gen_range(emitter.speed.start, emitter.speed.end);
+            let size = rng.gen_range(emitter.size.start, emitter.size.end);
+            let color = emitter.colors[rng.gen_range(0, emitter.colors.len())];
+            commands
+                .spawn_bundle(SpriteBundle {
+                    transform: Transform::from_translation(origin),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(size, size)),
+                        color,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(Particle {
+                    motion: FrictionSimulation2D::new(
+                        FrictionDescription::from(emitter.drag),
+                        origin.truncate(),
+                        Vec2::new(angle.cos(), angle.sin()) * speed,
+                        1.,
+                    ),
+                    gravity: emitter.gravity,
+                    spin: emitter.spin * rng.gen_range(-1., 1.),
+                    age: 0.,
+                    lifetime: rng.gen_range(emitter.lifetime.start, emitter.lifetime.end),
+                    alpha: color.a(),
+                });
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+// Particles follow the animation clock, so they stop with the game.
+fn move_particles(clock: Res<AnimationClock>, mut q: Query<(&mut Particle, &mut Transform)>) {
+    for (mut particle, mut transform) in q.iter_mut() {
+        particle.age += clock.delta();
+        let t = particle.age;
+        let position = particle.motion.x(t) + Vec2::Y * particle.gravity * t * t / 2.;
+        transform.translation = position.extend(transform.translation.z);
+        transform.rotation = Quat::from_rotation_z(particle.spin * t);
+    }
+}
+
+fn fade_particles(mut q: Query<(&Particle, &mut Sprite)>) {
+    for (particle, mut sprite) in q.iter_mut() {
+        let left = (1. - particle.age / particle.lifetime).clamp(0., 1.);
+        sprite.color.set_a(particle.alpha * left);
+    }
+}
+
+fn expire_particles(q: Query<(Entity, &Particle)>, mut commands: Commands) {
+    for (entity, particle) in q.iter() {
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+use crate::{
+    animation::{Animator, SimulationAnimator},
+    core::{TileNature, TileSide},
+    juice::{DiscardToss, Discarded, IdleBob},
+    layout::MirroredIllustration,
+    match_plugin::{Card, CardAction, PlayedAs, Slot, Tile, TileSprite, Used},
+    DespawnAll,
+};
+
+// Where pooled entities wait for the next match, well outside of the camera.
+const POOL_PARKING_Y_POS: f32 = -5000.;
+
+/// One of the sprites drawn on a card to illustrate its action.
+#[derive(Component)]
+pub struct CardIllustration;
+
+struct PooledTile {
+    id: Entity,
+    sprite: Entity,
+}
+
+struct PooledCard {
+    id: Entity,
+    illustrations: Vec<Entity>,
+}
+
+/// Tiles and cards left over from previous matches. Instead of despawning them at the end of a
+/// match they are hidden here, and the next match reconfigures them in place.
+#[derive(Default)]
+pub struct EntityPool {
+    tiles: Vec<PooledTile>,
+    cards: Vec<PooledCard>,
+}
+
+impl EntityPool {
+    /// Reuses a pooled tile if there is one. The tile is placed by `transform` and drawn by a
+    /// `TileSprite` child made of `sprite`. `spawn_children` adds to that child, and only runs
+    /// for brand new tiles.
+    pub fn spawn_tile(
+        &mut self,
+        commands: &mut Commands,
+        transform: Transform,
+        sprite: SpriteSheetBundle,
+        spawn_children: impl FnOnce(&mut ChildBuilder),
+    ) -> Entity {
+        let id = match self.tiles.pop() {
+            Some(tile) => {
+                commands.entity(tile.sprite).insert_bundle(sprite);
+                commands.entity(tile.id).insert(transform);
+                tile.id
+            }
+            None => commands
+                .spawn()
+                .insert(transform)
+                .insert(GlobalTransform::default())
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(sprite)
+                        .insert(TileSprite)
+                        .insert(IdleBob::default())
+                        .with_children(spawn_children);
+                })
+                .id(),
+        };
+        commands.entity(id).insert(Tile).insert(Animator::default());
+        id
+    }
+
+    /// Reuses a pooled card if there is one, reconfiguring its illustration sprites to match
+    /// `illustrations`. `spawn_children` only runs for brand new cards.
+    pub fn spawn_card(
+        &mut self,
+        commands: &mut Commands,
+        bundle: SpriteSheetBundle,
+        illustrations: Vec<SpriteSheetBundle>,
+        spawn_children: impl FnOnce(&mut ChildBuilder),
+    ) -> Entity {
+        let (id, mut reused) = match self.cards.pop() {
+            Some(card) => {
+                commands.entity(card.id).insert_bundle(bundle);
+                (card.id, card.illustrations)
+            }
+            None => (
+                commands
+                    .spawn_bundle(bundle)
+                    .with_children(spawn_children)
+                    .id(),
+                Vec::new(),
+            ),
+        };
+
+        // Surplus sprites from a busier illustration are the only thing that gets despawned.
+        if reused.len() > illustrations.len() {
+            for e in reused.drain(illustrations.len()..) {
+                commands.entity(e).despawn_recursive();
+            }
+        }
+        let mut reused = reused.into_iter();
+        for illustration in illustrations {
+            match reused.next() {
+                Some(e) => {
+                    commands.entity(e).insert_bundle(illustration);
+                }
+                None => {
+                    commands.entity(id).with_children(|parent| {
+                        parent.spawn_bundle(illustration).insert(CardIllustration);
+                    });
+                }
+            }
+        }
+
+        commands
+            .entity(id)
+            .insert(Card)
+            .insert(MirroredIllustration(false));
+        id
+    }
+}
+
+pub(crate) struct PoolPlugin;
+
+impl Plugin for PoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EntityPool>()
+            .add_system(recycle_tiles.label("recycle").after("match_flow"))
+            .add_system(recycle_cards.label("recycle").after("match_flow"));
+    }
+}
+
+fn park(entity: &mut EntityCommands) {
+    entity
+        .insert(Transform::from_xyz(0., POOL_PARKING_Y_POS, 0.))
+        .insert(Visibility { is_visible: false })
+        .remove::<Animator>()
+        .remove::<SimulationAnimator>();
+}
+
+pub(crate) fn recycle_tiles(
+    mut ev: EventReader<DespawnAll>,
+    q: Query<(Entity, &Children), With<Tile>>,
+    q_sprites: Query<(), With<TileSprite>>,
+    mut pool: ResMut<EntityPool>,
+    mut commands: Commands,
+) {
+    for _ in ev.iter() {
+        for (e, children) in q.iter() {
+            park(
+                commands
+                    .entity(e)
+                    .remove::<Tile>()
+                    .remove::<TileSide>()
+                    .remove::<TileNature>()
+                    .remove::<Slot>(),
+            );
+            let sprite = match children.iter().find(|child| q_sprites.get(**child).is_ok()) {
+                Some(sprite) => *sprite,
+                None => continue,
+            };
+            // The bob starts over from rest on the next match.
+            commands
+                .entity(sprite)
+                .insert(Transform::default())
+                .insert(IdleBob::default());
+            pool.tiles.push(PooledTile { id: e, sprite });
+        }
+    }
+}
+
+pub(crate) fn recycle_cards(
+    mut ev: EventReader<DespawnAll>,
+    q: Query<(Entity, &Children), With<Card>>,
+    q_illustrations: Query<(), With<CardIllustration>>,
+    mut pool: ResMut<EntityPool>,
+    mut commands: Commands,
+) {
+    for _ in ev.iter() {
+        for (e, children) in q.iter() {
+            park(
+                commands
+                    .entity(e)
+                    .remove::<Card>()
+                    .remove::<CardAction>()
+                    .remove::<Used>()
+                    .remove::<PlayedAs>()
+                    .remove::<DiscardToss>()
+                    .remove::<Discarded>(),
+            );
+            pool.cards.push(PooledCard {
+                id: e,
+                illustrations: children
+                    .iter()
+                    .filter(|child| q_illustrations.get(**child).is_ok())
+                    .copied()
+                    .collect(),
+            });
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::MatchConfig,
+    cosmetics,
+    hint::HintUsed,
+    match_plugin::MatchEnding,
+    storage::{self, Versioned},
+    GameMode,
+};
+
+const PROFILE_PATH: &str = "profile.ron";
+
+/// The player's stats over every match played, saved to `profile.ron` next to the game at the
+/// end of each match.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct PlayerProfile {
+    pub version: u32,
+    /// Matches played to the end, won or not. Abandoned ones don't count.
+    pub matches_played: u32,
+    pub wins: u32,
+    /// Matches won since the last one lost.
+    pub win_streak: u32,
+    pub best_win_streak: u32,
+    pub cards_used: u32,
+    pub hints_used: u32,
+    /// Fewest moves a match was won in, by level. See `level_key`.
+    pub best_moves: BTreeMap<String, usize>,
+    /// Until the tutorial is won, Play starts it instead of a regular match.
+    pub tutorial_done: bool,
+}
+
+impl Default for PlayerProfile {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            matches_played: 0,
+            wins: 0,
+            win_streak: 0,
+            best_win_streak: 0,
+            cards_used: 0,
+            hints_used: 0,
+            best_moves: BTreeMap::new(),
+            tutorial_done: false,
+        }
+    }
+}
+
+impl Versioned for PlayerProfile {
+    const VERSION: u32 = 1;
+
+    fn version(&mut self) -> &mut u32 {
+        &mut self.version
+    }
+}
+
+impl PlayerProfile {
+    /// Reads the profile, starting a new one when there is none. A file that can't be parsed is
+    /// kept aside as `profile.ron.bak` rather than overwritten at the end of the next match.
+    pub fn load() -> Self {
+        storage::load(PROFILE_PATH)
+    }
+
+    // Not a system!
+    pub fn save(&self) {
+        storage::save(PROFILE_PATH, self);
+    }
+
+    pub(crate) fn best_moves(&self, config: &MatchConfig) -> Option<usize> {
+        self.best_moves.get(&level_key(config)).copied()
+    }
+}
+
+pub(crate) struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PlayerProfile::load())
+            .add_system(record_match_result.label("record_match_result"))
+            .add_system(cosmetics::announce_unlocks.after("record_match_result"));
+    }
+}
+
+// Matches are dealt at random, so a level is the shape of the deal: tiles per column, cards in
+// hand and cards applied to scramble, like "4x5-3".
+fn level_key(config: &MatchConfig) -> String {
+    format!(
+        "{}x{}-{}",
+        config.tiles_count, config.card_count, config.applied_card_count
+    )
+}
+
+// The tutorial isn't a match of any level, so only finishing it is recorded. Demos aren't the
+// player's matches at all.
+pub(crate) fn record_match_result(
+    config: Res<MatchConfig>,
+    mode: Res<GameMode>,
+    mut profile: ResMut<PlayerProfile>,
+    mut ev_hint_used: EventReader<HintUsed>,
+    q_ending: Query<&MatchEnding, Added<MatchEnding>>,
+) {
+    // Saved with the rest at the end of the match.
+    profile.hints_used += ev_hint_used.iter().count() as u32;
+    if *mode == GameMode::Demo {
+        return;
+    }
+    for MatchEnding { result, .. } in q_ending.iter() {
+        if *mode == GameMode::Tutorial {
+            profile.tutorial_done |= result.won;
+            profile.save();
+            continue;
+        }
+        profile.matches_played += 1;
+        profile.cards_used += result.cards_used as u32;
+        if result.won {
+            profile.wins += 1;
+            profile.win_streak += 1;
+            profile.best_win_streak = profile.best_win_streak.max(profile.win_streak);
+            let best = profile
+                .best_moves
+                .entry(level_key(&mode.match_config(&config)))
+                .or_insert(usize::MAX);
+            *best = (*best).min(result.moves);
+        } else {
+            profile.win_streak = 0;
+        }
+        profile.save();
+    }
+}
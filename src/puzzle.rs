@@ -0,0 +1,121 @@
+use std::fs;
+
+use rand::prelude::*;
+use serde::Serialize;
+
+use crate::core::{deal, solve, MatchConfig, Puzzle};
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    // Rated by how many cards the shortest solution needs.
+    fn rate(solution_length: usize) -> Self {
+        match solution_length {
+            0 | 1 => Difficulty::Easy,
+            2 => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RatedPuzzle {
+    difficulty: Difficulty,
+    solution_length: usize,
+    puzzle: Puzzle,
+}
+
+// How many deals to try per requested puzzle before giving up on finding enough.
+const ATTEMPTS_PER_PUZZLE: usize = 1000;
+
+/// `--generate N [--difficulty easy|medium|hard] [--out puzzles.ron]`: deals puzzles with the
+/// default match config, keeps the solvable ones of the requested difficulty and writes them
+/// out without opening a window. Returns `None` when the arguments don't ask for it.
+pub fn run_generate_command(args: &[String]) -> Option<Result<(), String>> {
+    let position = args.iter().position(|arg| arg == "--generate")?;
+    Some(generate(&args[position..]))
+}
+
+fn generate(args: &[String]) -> Result<(), String> {
+    let mut count = None;
+    let mut difficulty = None;
+    let mut out = "puzzles.ron".to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{} needs a value", arg))
+                .map(|value| value.as_str())
+        };
+        match arg.as_str() {
+            "--generate" => {
+                let value = value()?;
+                count = Some(
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| format!("not a number of puzzles: {}", value))?,
+                );
+            }
+            "--difficulty" => {
+                let value = value()?;
+                difficulty = Some(
+                    Difficulty::parse(value)
+                        .ok_or_else(|| format!("unknown difficulty: {}", value))?,
+                );
+            }
+            "--out" => out = value()?.to_string(),
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+    let count = count.ok_or_else(|| "--generate needs a number of puzzles".to_string())?;
+
+    let config = MatchConfig::default();
+    config.validate().map_err(|err| err.to_string())?;
+    let mut puzzles = Vec::new();
+    let mut attempts = 0;
+    while puzzles.len() < count && attempts < count * ATTEMPTS_PER_PUZZLE {
+        attempts += 1;
+        let puzzle = deal(random::<u64>(), &config);
+        // Puzzles that are solved before a card is played aren't worth keeping.
+        let solution_length = match solve(&puzzle) {
+            Some(0) | None => continue,
+            Some(solution_length) => solution_length,
+        };
+        let rating = Difficulty::rate(solution_length);
+        if difficulty.map_or(true, |difficulty| difficulty == rating) {
+            puzzles.push(RatedPuzzle {
+                difficulty: rating,
+                solution_length,
+                puzzle,
+            });
+        }
+    }
+    if puzzles.len() < count {
+        eprintln!(
+            "Only found {} of {} puzzles after {} attempts",
+            puzzles.len(),
+            count,
+            attempts
+        );
+    }
+
+    let text = ron::ser::to_string_pretty(&puzzles, ron::ser::PrettyConfig::default())
+        .map_err(|err| format!("could not serialize puzzles: {}", err))?;
+    fs::write(&out, text).map_err(|err| format!("could not write {}: {}", out, err))?;
+    println!("Wrote {} puzzles to {}", puzzles.len(), out);
+    Ok(())
+}
@@ -0,0 +1,162 @@
+//! Discarding a card for a new one: the hovered card, if it hasn't been played, is thrown away
+//! and a newly made card is dealt into its slot. It costs a move, and only a few redraws are
+//! allowed per match.
+
+use bevy::prelude::*;
+
+use crate::{
+    animation::{AnimationSpec, AnimationTarget, Motion, PlayAnimation, SpringPreset},
+    controls::InputAction,
+    core::{Action, TileNature, TileSide},
+    match_plugin::{
+        card_illustration, BoardView, Card, CardAction, Dealer, MatchInput, MatchState, Tile,
+        UpdateCardsStyle, Used, CARDS_Y_POS, DEAL_IN_DECK_X_ABS, DEAL_IN_Y_POS,
+    },
+    theme,
+    toast::ShowToast,
+};
+
+pub(crate) const REDRAWS_PER_MATCH: usize = 2;
+
+/// Sent when a card has been discarded, for the new one to be dealt into its hand slot.
+pub(crate) struct CardRedrawn {
+    discarded: Entity,
+    slot: usize,
+    action: Action,
+    tiles_count: usize,
+}
+
+pub(crate) fn discard_card(
+    input: MatchInput,
+    mut dealer: Dealer,
+    mut match_state: ResMut<MatchState>,
+    q_tiles: Query<(&TileSide, &TileNature), With<Tile>>,
+    q_cards: Query<(&CardAction, Option<&Used>), With<Card>>,
+    mut ev_toast: EventWriter<ShowToast>,
+    mut ev_card_redrawn: EventWriter<CardRedrawn>,
+) {
+    if !input.just_pressed(InputAction::DiscardCard)
+        || input.lock.is_locked()
+        || dealer.is_scripted()
+    {
+        return;
+    }
+    let playing = match match_state.as_mut() {
+        MatchState::Playing(playing) => playing,
+        _ => return,
+    };
+    if playing.inspected_card.is_some() || playing.picking_tiles() || playing.playable.is_some() {
+        return;
+    }
+    let card = match playing.hovered_card {
+        Some(card) if matches!(q_cards.get(card), Ok((_, None))) => card,
+        _ => return,
+    };
+    if playing.redraws_left == 0 {
+        ev_toast.send(ShowToast("No redraws left".to_string()));
+        return;
+    }
+
+    let tiles = q_tiles
+        .iter()
+        .filter(|(side, _)| **side == TileSide::Left)
+        .map(|(_, nature)| *nature)
+        .collect::<Vec<_>>();
+    let kept = playing
+        .hand_order
+        .iter()
+        .filter(|c| **c != card)
+        .filter_map(|c| q_cards.get(*c).ok())
+        .map(|(CardAction(action), _)| *action)
+        .collect::<Vec<_>>();
+    let action = dealer.redraw(&tiles, &kept);
+
+    playing.redraws_left -= 1;
+    playing.moves += 1;
+    ev_card_redrawn.send(CardRedrawn {
+        discarded: card,
+        slot: playing.hand_slot(card),
+        action,
+        tiles_count: tiles.len(),
+    });
+}
+
+// Deals the new card from the deck, like the hand at the start of the match.
+pub(crate) fn deal_redrawn_card(
+    mut ev_card_redrawn: EventReader<CardRedrawn>,
+    mut match_state: ResMut<MatchState>,
+    mut board: BoardView,
+    mut motion: Motion,
+    mut event_update_cards_style: EventWriter<UpdateCardsStyle>,
+) {
+    for CardRedrawn {
+        discarded,
+        slot,
+        action,
+        tiles_count,
+    } in ev_card_redrawn.iter()
+    {
+        board.commands.entity(*discarded).despawn_recursive();
+        let playing = match match_state.as_mut() {
+            MatchState::Playing(playing) => playing,
+            _ => continue,
+        };
+        let to = Vec3::new(
+            board.layout.card_pos_x(*slot, playing.hand_order.len()),
+            CARDS_Y_POS,
+            0.,
+        );
+        let deck = Vec3::new(
+            board.layout.side_x(TileSide::Left, DEAL_IN_DECK_X_ABS),
+            DEAL_IN_Y_POS,
+            0.,
+        );
+        let card_size = 270.;
+        let id = board.pool.spawn_card(
+            &mut board.commands,
+            board.game_assets.sheet(SpriteBundle {
+                transform: Transform {
+                    translation: if motion.snaps() { to } else { deck },
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(card_size, card_size)),
+                    color: board.theme.card_back().color,
+                    ..Default::default()
+                },
+                texture: board.game_assets.texture("card_bg.png"),
+                ..Default::default()
+            }),
+            card_illustration(
+                action,
+                *tiles_count,
+                card_size,
+                &board.game_assets,
+                &board.theme,
+            ),
+            |parent| theme::spawn_card_border(parent, card_size),
+        );
+        board.commands.entity(id).insert(CardAction(*action));
+
+        let old = std::mem::replace(&mut playing.hand_order[*slot], id);
+        if playing.hovered_card == Some(old) {
+            playing.hovered_card = Some(id);
+        }
+        if playing.pinned_card == Some(old) {
+            playing.pinned_card = None;
+        }
+
+        if !motion.snaps() {
+            motion.ev_play_animation.send(PlayAnimation {
+                entity: id,
+                spec: AnimationSpec {
+                    target: AnimationTarget::Translation { from: deck, to },
+                    spring: motion.springs.get(SpringPreset::DealIn),
+                },
+                delay: 0.,
+                group: None,
+            });
+        }
+        event_update_cards_style.send(UpdateCardsStyle);
+    }
+}
@@ -0,0 +1,230 @@
+//! The score of a run of matches. A win is worth more for each card still in hand, and wins in a
+//! row without restarting multiply it. A run lasts until the player goes back to the main menu,
+//! until the countdown of a timed run runs out, or until a Deck run loses a match.
+
+use bevy::prelude::*;
+use jam_motion::spring::{SpringDescription, SpringSimulation};
+
+use crate::{
+    animation::AnimationClock,
+    match_plugin::{MatchClock, MatchEnding, MatchState, RestartRequest, VictoryScreen},
+    theme::ThemedText,
+    timed::Countdown,
+    GameMode, GameState,
+};
+
+const BASE_POINTS: u32 = 100;
+const MAX_MULTIPLIER: u32 = 5;
+const SCORE_X_POS: f32 = 760.;
+const SCORE_Y_POS: f32 = 600.;
+const COMBO_Y_POS: f32 = 565.;
+const COMBO_COLOR: Color = Color::rgb(1., 0.8, 0.2);
+
+#[derive(Default)]
+pub(crate) struct Score {
+    total: u32,
+    wins_in_a_row: u32,
+    // Time spent in matches over the run.
+    time: f32,
+}
+
+impl Score {
+    /// What the next win is multiplied by.
+    fn multiplier(&self) -> u32 {
+        (self.wins_in_a_row + 1).min(MAX_MULTIPLIER)
+    }
+}
+
+// Not a system! The points of a win before the multiplier: the base points, once more for each
+// card left unplayed.
+fn win_points(cards: usize, cards_used: usize) -> u32 {
+    BASE_POINTS * (1 + cards.saturating_sub(cards_used)) as u32
+}
+
+/// Sent when a run that scored any points is over.
+pub(crate) struct RunEnded {
+    pub(crate) mode: GameMode,
+    pub(crate) score: u32,
+    pub(crate) time: f32,
+}
+
+// Not a system!
+fn end_run(mode: GameMode, score: &mut Score, ev_run_ended: &mut EventWriter<RunEnded>) {
+    if score.total > 0 {
+        ev_run_ended.send(RunEnded {
+            mode,
+            score: score.total,
+            time: score.time,
+        });
+    }
+    *score = Score::default();
+}
+
+// The number shown springs towards the score instead of jumping to it.
+#[derive(Component)]
+struct ScoreCounter {
+    target: u32,
+    shown: SpringSimulation,
+    started_at: f32,
+}
+
+fn tick_spring() -> SpringDescription {
+    SpringDescription::with_duration_and_bounce(0.8, 0.).expect("a valid spring")
+}
+
+#[derive(Component)]
+struct ComboText;
+
+pub(crate) struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .add_event::<RunEnded>()
+            .add_startup_system(setup_score_text)
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(end_run_in_menu))
+            .add_system(tick_run_time.after("tick_match_clock"))
+            .add_system(score_match.after("handle_input"))
+            .add_system(break_combo_on_restart.after("handle_input"))
+            .add_system_to_stage("view", update_score_text);
+    }
+}
+
+fn end_run_in_menu(
+    mode: Res<GameMode>,
+    mut score: ResMut<Score>,
+    mut ev_run_ended: EventWriter<RunEnded>,
+) {
+    end_run(*mode, &mut score, &mut ev_run_ended);
+}
+
+fn tick_run_time(clock: Res<MatchClock>, mut score: ResMut<Score>) {
+    if clock.delta > 0. {
+        score.time += clock.delta;
+    }
+}
+
+// The tutorial and demos don't count. A loss ends the combo, and running out of time or losing a Deck
+// match the whole run.
+fn score_match(
+    mode: Res<GameMode>,
+    match_state: Res<MatchState>,
+    countdown: Res<Countdown>,
+    q_ending: Query<(&MatchEnding, Option<&VictoryScreen>), Added<MatchEnding>>,
+    mut score: ResMut<Score>,
+    mut ev_run_ended: EventWriter<RunEnded>,
+) {
+    if *mode == GameMode::Tutorial || *mode == GameMode::Demo {
+        return;
+    }
+    let cards = match match_state.as_ref() {
+        MatchState::Playing(match_state) => match_state.hand_order.len(),
+        _ => return,
+    };
+    for (MatchEnding { result, .. }, victory) in q_ending.iter() {
+        if victory.is_some() {
+            score.total += win_points(cards, result.cards_used) * score.multiplier();
+            score.wins_in_a_row += 1;
+        } else if *mode == GameMode::Deck || (*mode == GameMode::Timed && countdown.expired()) {
+            end_run(*mode, &mut score, &mut ev_run_ended);
+        } else {
+            score.wins_in_a_row = 0;
+        }
+    }
+}
+
+fn break_combo_on_restart(mut ev_restart: EventReader<RestartRequest>, mut score: ResMut<Score>) {
+    if ev_restart.iter().count() > 0 && score.wins_in_a_row > 0 {
+        score.wins_in_a_row = 0;
+    }
+}
+
+fn setup_score_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("ReadexPro-Regular.ttf");
+    let alignment = TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Right,
+    };
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 36.,
+                    color: Color::WHITE,
+                },
+                alignment,
+            ),
+            transform: Transform::from_xyz(SCORE_X_POS, SCORE_Y_POS, 50.),
+            ..Default::default()
+        })
+        .insert(ScoreCounter {
+            target: 0,
+            shown: SpringSimulation::new(tick_spring(), 0., 0., 0., 0.5),
+            started_at: 0.,
+        })
+        .insert(ThemedText);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font,
+                    font_size: 24.,
+                    color: COMBO_COLOR,
+                },
+                alignment,
+            ),
+            transform: Transform::from_xyz(SCORE_X_POS, COMBO_Y_POS, 50.),
+            ..Default::default()
+        })
+        .insert(ComboText);
+}
+
+// Shown through a match and on its end screen, outside of the tutorial and demos.
+fn update_score_text(
+    mode: Res<GameMode>,
+    state: Res<State<GameState>>,
+    score: Res<Score>,
+    clock: Res<AnimationClock>,
+    mut q_score: Query<(&mut ScoreCounter, &mut Text, &mut Visibility), Without<ComboText>>,
+    mut q_combo: Query<(&mut Text, &mut Visibility), With<ComboText>>,
+) {
+    let visible = *mode != GameMode::Tutorial
+        && *mode != GameMode::Demo
+        && matches!(
+            state.current(),
+            GameState::InGame | GameState::Paused | GameState::GameOver
+        );
+    for (mut counter, mut text, mut visibility) in q_score.iter_mut() {
+        if visibility.is_visible != visible {
+            visibility.is_visible = visible;
+        }
+        if counter.target != score.total {
+            let t = clock.now() - counter.started_at;
+            counter.shown = counter.shown.retarget(t, tick_spring(), score.total as f32);
+            counter.target = score.total;
+            counter.started_at = clock.now();
+        }
+        let shown = counter.shown.x_or_end_x(clock.now() - counter.started_at);
+        let value = format!("{}", shown.x.round().max(0.) as u32);
+        // Only touch the text when the number shown changes, to avoid relayouts every frame.
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+
+    let value = match score.multiplier() {
+        1 => String::new(),
+        multiplier => format!("Combo ×{}", multiplier),
+    };
+    for (mut text, mut visibility) in q_combo.iter_mut() {
+        if visibility.is_visible != visible {
+            visibility.is_visible = visible;
+        }
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use bevy::{
+    prelude::*,
+    window::{WindowMoved, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::SoundSettings,
+    colorblind::ColorblindFilter,
+    controls::{self, InputAction},
+    core::MatchConfig,
+    cosmetics, options,
+    storage::{self, Versioned},
+};
+
+const SETTINGS_PATH: &str = "settings.ron";
+// Dragging or resizing the window fires an event per frame; only save once it settles.
+const SAVE_DELAY_SECS: f64 = 0.5;
+
+/// Where the window was last left. Bevy 0.6 can't pick a monitor, so the monitor is implied by
+/// the position, which is in logical pixels across the whole desktop.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct WindowPlacement {
+    pub width: f32,
+    pub height: f32,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Everything persisted between launches, saved to `settings.ron` next to the game.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub window: Option<WindowPlacement>,
+    /// Pause the match while the game window is in the background.
+    pub pause_on_focus_loss: bool,
+    /// Send anonymous match reports to `telemetry_endpoint`. Off unless the player opts in.
+    pub telemetry: bool,
+    /// Where the reports are posted. Only plain `http://` URLs are supported.
+    pub telemetry_endpoint: String,
+    pub sound: SoundSettings,
+    /// Multiplies the speed of gameplay animations, `inf` skips them.
+    pub animation_speed: f32,
+    pub reduced_motion: bool,
+    pub high_contrast: bool,
+    /// Name of the tile pack in use, `None` for the built-in tiles.
+    pub tile_pack: Option<String>,
+    /// Name of the card back in use, `None` for the classic one.
+    pub card_back: Option<String>,
+    /// How many rows of tiles matches are dealt.
+    pub board_rows: usize,
+    /// The kind of colour blindness tiles and cards are recoloured for, `None` for none.
+    pub palette: Option<ColorblindFilter>,
+    /// Key bindings changed in the controls menu; actions not listed keep their default keys.
+    pub bindings: Vec<(InputAction, Vec<KeyCode>)>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            window: None,
+            pause_on_focus_loss: true,
+            telemetry: false,
+            telemetry_endpoint: String::new(),
+            sound: SoundSettings::default(),
+            animation_speed: 1.,
+            reduced_motion: false,
+            high_contrast: false,
+            tile_pack: None,
+            card_back: None,
+            board_rows: MatchConfig::default().tiles_count,
+            palette: None,
+            bindings: Vec::new(),
+        }
+    }
+}
+
+impl Versioned for Settings {
+    // 2: the keys menus navigate with, and Shift, became reserved.
+    const VERSION: u32 = 2;
+
+    fn version(&mut self) -> &mut u32 {
+        &mut self.version
+    }
+
+    fn migrate(&mut self, from: u32) {
+        if from < 2 {
+            // Drop the reserved keys from the saved bindings. An action left without any goes
+            // back to its default keys.
+            for (action, keys) in self.bindings.iter_mut() {
+                keys.retain(|key| !controls::is_reserved(*action, *key));
+            }
+            self.bindings.retain(|(_, keys)| !keys.is_empty());
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings file, falling back to the defaults when there is none. A file that
+    /// can't be parsed is kept aside as `settings.ron.bak` rather than silently overwritten.
+    pub fn load() -> Self {
+        storage::load(SETTINGS_PATH)
+    }
+
+    // Not a system!
+    pub fn save(&self) {
+        storage::save(SETTINGS_PATH, self);
+    }
+
+    /// The window to open at startup, where it was last left if known.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn window_descriptor(&self) -> WindowDescriptor {
+        let mut descriptor = WindowDescriptor::default();
+        if let Some(placement) = self.window {
+            descriptor.width = placement.width;
+            descriptor.height = placement.height;
+            descriptor.position = Some(Vec2::new(placement.x, placement.y));
+        }
+        descriptor
+    }
+
+    /// In the browser the window is the page's canvas, which the page sizes.
+    #[cfg(target_arch = "wasm32")]
+    pub fn window_descriptor(&self) -> WindowDescriptor {
+        WindowDescriptor {
+            canvas: Some(crate::web::CANVAS_SELECTOR.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// When the settings changed and still need saving.
+#[derive(Default)]
+pub struct PendingSave(Option<f64>);
+
+impl PendingSave {
+    /// Saves the settings once they have stopped changing for a moment.
+    pub fn request(&mut self, now: f64) {
+        self.0 = Some(now);
+    }
+}
+
+/// Keeps the settings file in step with the options, the window and the key bindings. The
+/// settings themselves are loaded before the app is built, to open the window where it was.
+pub(crate) struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingSave>()
+            .add_startup_system(options::apply_saved_options)
+            .add_startup_system(cosmetics::apply_saved_cosmetics.after("load_tile_packs"))
+            .add_system(options::save_options.before("save_settings"))
+            .add_system(track_window_placement.before("save_settings"))
+            .add_system(save_settings.label("save_settings"));
+    }
+}
+
+pub fn track_window_placement(
+    windows: Res<Windows>,
+    time: Res<Time>,
+    mut ev_resized: EventReader<WindowResized>,
+    mut ev_moved: EventReader<WindowMoved>,
+    mut settings: ResMut<Settings>,
+    mut pending_save: ResMut<PendingSave>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let resized = ev_resized.iter().any(|ev| ev.id == window.id());
+    let moved = ev_moved.iter().any(|ev| ev.id == window.id());
+    if !resized && !moved {
+        return;
+    }
+
+    // Positions come in physical pixels, while the window descriptor takes logical ones.
+    let position = window
+        .position()
+        .map(|position| position.as_vec2() / window.scale_factor() as f32);
+    let previous = settings.window;
+    let placement = WindowPlacement {
+        width: window.width(),
+        height: window.height(),
+        x: position.map_or(previous.map_or(0., |p| p.x), |position| position.x),
+        y: position.map_or(previous.map_or(0., |p| p.y), |position| position.y),
+    };
+    if previous != Some(placement) {
+        settings.window = Some(placement);
+        pending_save.request(time.seconds_since_startup());
+    }
+}
+
+pub fn save_settings(
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut pending_save: ResMut<PendingSave>,
+) {
+    if let Some(changed_at) = pending_save.0 {
+        if time.seconds_since_startup() - changed_at >= SAVE_DELAY_SECS {
+            settings.save();
+            pending_save.0 = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_1_bindings_lose_the_keys_reserved_since() {
+        let text = "(
+            version: 1,
+            bindings: [
+                (Hint, [Up]),
+                (Undo, [Back, B]),
+                (PlayCard, [Return]),
+            ],
+        )";
+        let mut settings = ron::from_str::<Settings>(text).unwrap();
+        settings.migrate(settings.version);
+        assert_eq!(
+            settings.bindings,
+            vec![
+                (InputAction::Undo, vec![KeyCode::B]),
+                (InputAction::PlayCard, vec![KeyCode::Return]),
+            ]
+        );
+    }
+}
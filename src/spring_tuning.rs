@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+
+use crate::animation::{SpringPreset, SpringPresets};
+use jam_motion::spring::SpringDescription;
+
+// Debug panel to tune the spring presets live: F9 toggles it, PageUp/PageDown pick a value,
+// -/= turn it down/up and F10 dumps all presets to the log, ready to be pasted back as the
+// presets' defaults. Changes apply to the next animation started with the preset.
+pub struct SpringTuningPlugin;
+
+impl Plugin for SpringTuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpringTuning>()
+            .add_system(spring_tuning_input.label("spring_tuning_input"))
+            .add_system(update_spring_tuning_panel.after("spring_tuning_input"));
+    }
+}
+
+const PARAMETERS: [&str; 3] = ["mass", "stiffness", "damping"];
+const STEP_FACTOR: f32 = 1.1;
+
+#[derive(Default)]
+struct SpringTuning {
+    open: bool,
+    // Row in the panel: one per parameter of each preset.
+    selected: usize,
+}
+
+impl SpringTuning {
+    fn rows() -> usize {
+        SpringPreset::ALL.len() * PARAMETERS.len()
+    }
+
+    fn selected(&self) -> (SpringPreset, usize) {
+        (
+            SpringPreset::ALL[self.selected / PARAMETERS.len()],
+            self.selected % PARAMETERS.len(),
+        )
+    }
+}
+
+#[derive(Component)]
+struct SpringTuningPanel;
+
+fn parameters(spring: &SpringDescription) -> [f32; 3] {
+    [spring.mass(), spring.stiffness(), spring.damping()]
+}
+
+fn dump(presets: &SpringPresets) -> String {
+    SpringPreset::ALL
+        .iter()
+        .map(|preset| {
+            let [mass, stiffness, damping] = parameters(&presets.get(*preset));
+            format!(
+                "{}: (mass: {:.3}, stiffness: {:.3}, damping: {:.3}),",
+                preset.name(),
+                mass,
+                stiffness,
+                damping
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn spring_tuning_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut tuning: ResMut<SpringTuning>,
+    mut presets: ResMut<SpringPresets>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F9) {
+        tuning.open = !tuning.open;
+    }
+    if !tuning.open {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::F10) {
+        info!("Spring presets:\n{}", dump(&presets));
+    }
+
+    if keyboard_input.just_pressed(KeyCode::PageUp) {
+        tuning.selected = (tuning.selected + SpringTuning::rows() - 1) % SpringTuning::rows();
+    } else if keyboard_input.just_pressed(KeyCode::PageDown) {
+        tuning.selected = (tuning.selected + 1) % SpringTuning::rows();
+    }
+
+    let factor = if keyboard_input.just_pressed(KeyCode::Equals) {
+        STEP_FACTOR
+    } else if keyboard_input.just_pressed(KeyCode::Minus) {
+        1. / STEP_FACTOR
+    } else {
+        return;
+    };
+    let (preset, parameter) = tuning.selected();
+    let mut values = parameters(&presets.get(preset));
+    values[parameter] *= factor;
+    presets.set(
+        preset,
+        SpringDescription::new(values[0], values[1], values[2]),
+    );
+}
+
+fn update_spring_tuning_panel(
+    tuning: Res<SpringTuning>,
+    presets: Res<SpringPresets>,
+    q_panel: Query<Entity, With<SpringTuningPanel>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if !tuning.is_changed() && !presets.is_changed() {
+        return;
+    }
+    for e in q_panel.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    if !tuning.open {
+        return;
+    }
+
+    let (selected_preset, selected_parameter) = tuning.selected();
+    let mut lines = vec!["Springs (PgUp/PgDn, -/=, F10 dump)".to_string()];
+    for preset in SpringPreset::ALL {
+        lines.push(preset.name().to_string());
+        for (i, value) in parameters(&presets.get(preset)).iter().enumerate() {
+            let marker = if preset == selected_preset && i == selected_parameter {
+                ">"
+            } else {
+                " "
+            };
+            lines.push(format!("{} {}: {:.2}", marker, PARAMETERS[i], value));
+        }
+    }
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                lines.join("\n"),
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Top,
+                    horizontal: HorizontalAlign::Left,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(-900., 500., 92.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(SpringTuningPanel);
+}
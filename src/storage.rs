@@ -0,0 +1,129 @@
+//! Where the game keeps its files. Native builds read and write them next to the game, the
+//! browser build keeps them in the page's LocalStorage under the same names.
+
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::{fs, io};
+
+    pub fn read(path: &str) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    pub fn write(path: &str, text: &str) -> io::Result<()> {
+        fs::write(path, text)
+    }
+
+    pub fn rename(from: &str, to: &str) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    pub fn remove(path: &str) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use std::io;
+
+    use web_sys::Storage;
+
+    fn js_error(err: impl std::fmt::Debug) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, format!("{:?}", err))
+    }
+
+    // Missing when the page runs without storage, e.g. in some private windows.
+    fn local_storage() -> io::Result<Storage> {
+        web_sys::window()
+            .ok_or_else(|| js_error("no window"))?
+            .local_storage()
+            .map_err(js_error)?
+            .ok_or_else(|| js_error("LocalStorage is unavailable"))
+    }
+
+    pub fn read(path: &str) -> io::Result<String> {
+        local_storage()?
+            .get_item(path)
+            .map_err(js_error)?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    pub fn write(path: &str, text: &str) -> io::Result<()> {
+        local_storage()?.set_item(path, text).map_err(js_error)
+    }
+
+    // LocalStorage has no rename, so it's a copy.
+    pub fn rename(from: &str, to: &str) -> io::Result<()> {
+        let text = read(from)?;
+        write(to, &text)?;
+        remove(from)
+    }
+
+    pub fn remove(path: &str) -> io::Result<()> {
+        local_storage()?.remove_item(path).map_err(js_error)
+    }
+}
+
+pub use backend::{read, remove, rename, write};
+
+/// A file saved as RON that records the version of its format, so files written by older
+/// versions of the game can be brought up to date.
+pub trait Versioned: Serialize + DeserializeOwned + Default {
+    /// Bumped whenever the format changes in a way older files need migrating from.
+    const VERSION: u32;
+
+    fn version(&mut self) -> &mut u32;
+
+    /// Brings a file written with format `from` up to date. Fields added since are filled in by
+    /// `#[serde(default)]`, so only changes in meaning need a step here.
+    fn migrate(&mut self, _from: u32) {}
+}
+
+/// Reads and migrates the file at `path`, falling back to the default when there is none. A file
+/// that can't be parsed is kept aside as `<path>.bak` rather than overwritten on the next save.
+pub fn load<T: Versioned>(path: &str) -> T {
+    let text = match read(path) {
+        Ok(text) => text,
+        Err(_) => return T::default(),
+    };
+    let mut value = match ron::from_str::<T>(&text) {
+        Ok(value) => value,
+        Err(err) => {
+            warn!("Could not read {}: {}", path, err);
+            if let Err(err) = rename(path, &format!("{}.bak", path)) {
+                warn!("Could not back up {}: {}", path, err);
+            }
+            return T::default();
+        }
+    };
+    let version = *value.version();
+    match version.cmp(&T::VERSION) {
+        Ordering::Less => value.migrate(version),
+        Ordering::Equal => (),
+        Ordering::Greater => warn!(
+            "{} was written by a newer version of the game, some of it may be ignored",
+            path
+        ),
+    }
+    *value.version() = T::VERSION;
+    value
+}
+
+// Not a system!
+pub fn save<T: Versioned>(path: &str, value: &T) {
+    let text = match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(text) => text,
+        Err(err) => {
+            error!("Could not serialize {}: {}", path, err);
+            return;
+        }
+    };
+    if let Err(err) = write(path, &text) {
+        error!("Could not write {}: {}", path, err);
+    }
+}
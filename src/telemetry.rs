@@ -0,0 +1,255 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use bevy::{app::AppExit, ecs::schedule::ShouldRun, prelude::*, tasks::IoTaskPool};
+use serde::Serialize;
+
+use crate::{
+    hint::HintUsed,
+    match_plugin::{CardPlayed, MatchEnding, MatchState, RestartRequest},
+    modal::{ModalAction, ModalResult},
+    settings::Settings,
+    GameMode,
+};
+
+// Matches are sent in batches of this many.
+const BATCH_SIZE: usize = 5;
+const TIMEOUT: Duration = Duration::from_secs(5);
+// Reports are posted over a plain socket, which the browser doesn't give, so the web build
+// collects nothing even when opted in.
+pub(crate) const SUPPORTED: bool = !cfg!(target_arch = "wasm32");
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+enum Outcome {
+    Won,
+    // Out of cards, or out of time in the timed mode.
+    Lost,
+    Abandoned,
+}
+
+/// What is sent about a match. There is nothing about the player or the machine in it.
+#[derive(Serialize, Clone, Debug)]
+struct MatchReport {
+    seed: u64,
+    cards_played: usize,
+    restarts: usize,
+    hints_used: usize,
+    seconds: f32,
+    outcome: Option<Outcome>,
+}
+
+/// Anonymous match reports, only collected and sent when the player opted in from the options
+/// screen.
+#[derive(Default)]
+pub struct Telemetry {
+    pub opted_in: bool,
+    current: Option<MatchReport>,
+    started_at: f64,
+    batch: Vec<MatchReport>,
+}
+
+impl Telemetry {
+    // Closes the current match report, if any, and queues it for sending.
+    fn finish(&mut self, outcome: Outcome, now: f64) {
+        if let Some(mut report) = self.current.take() {
+            report.outcome = Some(outcome);
+            report.seconds = (now - self.started_at) as f32;
+            self.batch.push(report);
+        }
+    }
+}
+
+pub(crate) struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Telemetry>()
+            .add_system(
+                count_match_events
+                    .before("record_telemetry")
+                    .with_run_criteria(played_by_player),
+            )
+            .add_system(
+                record_telemetry
+                    .label("record_telemetry")
+                    .after("match_flow")
+                    .with_run_criteria(played_by_player),
+            )
+            .add_system_to_stage("view", flush_telemetry);
+    }
+}
+
+// Demo matches play themselves, so there is nothing to learn from them.
+fn played_by_player(mode: Res<GameMode>) -> ShouldRun {
+    if *mode == GameMode::Demo {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+// Counts into the report of the match being played, if there is one.
+pub(crate) fn count_match_events(
+    mut telemetry: ResMut<Telemetry>,
+    mut ev_card_played: EventReader<CardPlayed>,
+    mut ev_restart: EventReader<RestartRequest>,
+    mut ev_hint_used: EventReader<HintUsed>,
+) {
+    let (cards_played, restarts, hints_used) = (
+        ev_card_played.iter().count(),
+        ev_restart.iter().count(),
+        ev_hint_used.iter().count(),
+    );
+    if cards_played + restarts + hints_used == 0 {
+        return;
+    }
+    if let Some(report) = telemetry.current.as_mut() {
+        report.cards_played += cards_played;
+        report.restarts += restarts;
+        report.hints_used += hints_used;
+    }
+}
+
+pub(crate) fn record_telemetry(
+    settings: Res<Settings>,
+    time: Res<Time>,
+    match_state: Res<MatchState>,
+    mut telemetry: ResMut<Telemetry>,
+    q_ending: Query<&MatchEnding, Added<MatchEnding>>,
+    mut ev_modal_result: EventReader<ModalResult>,
+    io_pool: Res<IoTaskPool>,
+) {
+    if !SUPPORTED || !telemetry.opted_in {
+        // Nothing collected before opting out is kept.
+        if telemetry.current.is_some() || !telemetry.batch.is_empty() {
+            telemetry.current = None;
+            telemetry.batch.clear();
+        }
+        return;
+    }
+    let now = time.seconds_since_startup();
+
+    for MatchEnding { result, .. } in q_ending.iter() {
+        let outcome = if result.won {
+            Outcome::Won
+        } else {
+            Outcome::Lost
+        };
+        telemetry.finish(outcome, now);
+    }
+    for ModalResult(action) in ev_modal_result.iter() {
+        if *action == ModalAction::AbandonMatch {
+            telemetry.finish(Outcome::Abandoned, now);
+        }
+    }
+
+    if match_state.is_changed() {
+        if let MatchState::Playing(playing) = match_state.as_ref() {
+            let is_new = telemetry
+                .current
+                .as_ref()
+                .map_or(true, |report| report.seed != playing.seed);
+            if is_new {
+                telemetry.finish(Outcome::Abandoned, now);
+                telemetry.current = Some(MatchReport {
+                    seed: playing.seed,
+                    cards_played: 0,
+                    restarts: 0,
+                    hints_used: 0,
+                    seconds: 0.,
+                    outcome: None,
+                });
+                telemetry.started_at = now;
+            }
+        }
+    }
+
+    if telemetry.batch.len() >= BATCH_SIZE {
+        send_in_background(&io_pool, &settings, &mut telemetry);
+    }
+}
+
+// Whatever didn't make a full batch is sent on the way out. Quitting doesn't wait for it, so
+// the batch is lost if the game closes before the collector answers.
+pub(crate) fn flush_telemetry(
+    settings: Res<Settings>,
+    mut telemetry: ResMut<Telemetry>,
+    mut ev_exit: EventReader<AppExit>,
+    io_pool: Res<IoTaskPool>,
+) {
+    if ev_exit.iter().count() == 0
+        || !SUPPORTED
+        || !telemetry.opted_in
+        || telemetry.batch.is_empty()
+    {
+        return;
+    }
+    send_in_background(&io_pool, &settings, &mut telemetry);
+}
+
+// Not a system!
+fn send_in_background(io_pool: &IoTaskPool, settings: &Settings, telemetry: &mut Telemetry) {
+    let batch = std::mem::take(&mut telemetry.batch);
+    let endpoint = settings.telemetry_endpoint.clone();
+    io_pool
+        .spawn(async move {
+            if let Err(err) = send(&endpoint, &batch) {
+                warn!("Could not send telemetry: {}", err);
+            }
+        })
+        .detach();
+}
+
+// Not a system! Posts the batch as JSON. Only plain `http://host[:port]/path` endpoints are
+// supported, which is enough for a collector behind a local proxy.
+fn send(endpoint: &str, batch: &[MatchReport]) -> Result<(), String> {
+    let rest = endpoint
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("unsupported endpoint {:?}, expected http://", endpoint))?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+    let address = address
+        .to_socket_addrs()
+        .map_err(|err| format!("could not resolve {}: {}", host, err))?
+        .next()
+        .ok_or_else(|| format!("could not resolve {}", host))?;
+
+    let body = serde_json::to_string(batch).map_err(|err| err.to_string())?;
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    );
+    let mut stream =
+        TcpStream::connect_timeout(&address, TIMEOUT).map_err(|err| err.to_string())?;
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(|err| err.to_string())?;
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|err| err.to_string())?;
+
+    // Only the status line matters.
+    let mut response = [0; 64];
+    let read = stream.read(&mut response).map_err(|err| err.to_string())?;
+    let status_line = String::from_utf8_lossy(&response[..read]);
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(format!(
+            "unexpected response: {}",
+            status_line.lines().next().unwrap_or("")
+        )),
+    }
+}
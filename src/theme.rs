@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+
+use crate::assets::GameAssets;
+use crate::colorblind::{colorblind_preview, ColorblindFilter, ColorblindPreview};
+use crate::controls::{InputAction, InputMap};
+use crate::core::{TileNature, TileSide};
+use crate::cosmetics::{step_unlocked, CardBack, CARD_BACKS};
+use crate::match_plugin::{MatchState, UpdateCardsStyle, TILES_LEFT, TILES_RIGHT};
+use crate::profile::PlayerProfile;
+use crate::tile_packs::{load_tile_packs, TilePack};
+use crate::toast::ShowToast;
+
+const BACKGROUND_COLOR: Color = Color::rgb(0.866_666_7, 0.8, 0.686_274_5);
+const HIGH_CONTRAST_BACKGROUND_COLOR: Color = Color::rgb(0.12, 0.1, 0.08);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ContrastMode {
+    Normal,
+    High,
+}
+
+pub struct Theme {
+    pub contrast: ContrastMode,
+    /// Tile packs found in the mods folder, in addition to the built-in tiles.
+    pub tile_packs: Vec<TilePack>,
+    /// Index in `tile_packs` of the pack in use, `None` for the built-in tiles.
+    pub tile_pack: Option<usize>,
+    /// The kind of colour blindness the tiles and cards are recoloured for, if any.
+    pub palette: Option<ColorblindFilter>,
+    /// Index in `CARD_BACKS` of the card back in use.
+    pub card_back: usize,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            contrast: ContrastMode::Normal,
+            tile_packs: Vec::new(),
+            tile_pack: None,
+            palette: None,
+            card_back: 0,
+        }
+    }
+}
+
+impl Theme {
+    pub fn background_color(&self) -> Color {
+        match self.contrast {
+            ContrastMode::Normal => BACKGROUND_COLOR,
+            ContrastMode::High => HIGH_CONTRAST_BACKGROUND_COLOR,
+        }
+    }
+
+    pub fn text_color(&self) -> Color {
+        match self.contrast {
+            ContrastMode::Normal => Color::BLACK,
+            ContrastMode::High => Color::WHITE,
+        }
+    }
+
+    pub fn is_high_contrast(&self) -> bool {
+        self.contrast == ContrastMode::High
+    }
+
+    pub fn tile_pack_name(&self) -> &str {
+        match self.tile_pack {
+            Some(i) => &self.tile_packs[i].name,
+            None => "Classic",
+        }
+    }
+
+    /// Picks the next unlocked tile pack, or the previous one, going round through the built-in
+    /// tiles.
+    pub fn step_tile_pack(&mut self, forward: bool, profile: &PlayerProfile) {
+        let current = self.tile_pack.map_or(0, |i| i + 1);
+        let next = step_unlocked(current, self.tile_packs.len() + 1, forward, |i| {
+            i == 0 || self.tile_packs[i - 1].is_unlocked(profile)
+        });
+        self.tile_pack = next.checked_sub(1);
+    }
+
+    pub fn card_back(&self) -> &'static CardBack {
+        &CARD_BACKS[self.card_back]
+    }
+
+    /// Picks the next unlocked card back, or the previous one.
+    pub fn step_card_back(&mut self, forward: bool, profile: &PlayerProfile) {
+        self.card_back = step_unlocked(self.card_back, CARD_BACKS.len(), forward, |i| {
+            CARD_BACKS[i].is_unlocked(profile)
+        });
+    }
+
+    pub fn palette_name(&self) -> &'static str {
+        self.palette.map_or("Standard", |filter| filter.label())
+    }
+
+    /// Picks the next palette, or the previous one, going round through the standard colours.
+    pub fn step_palette(&mut self, forward: bool) {
+        let count = ColorblindFilter::ALL.len() + 1;
+        let current = self
+            .palette
+            .and_then(|palette| ColorblindFilter::ALL.iter().position(|f| *f == palette))
+            .map_or(0, |i| i + 1);
+        let next = if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        };
+        self.palette = next.checked_sub(1).map(|i| ColorblindFilter::ALL[i]);
+    }
+
+    /// The texture of a tile in the tile pack in use.
+    pub(crate) fn tile_texture(
+        &self,
+        side: TileSide,
+        nature: TileNature,
+        game_assets: &GameAssets,
+    ) -> Handle<Image> {
+        match (self.tile_pack, side) {
+            (Some(i), TileSide::Left) => self.tile_packs[i].left[nature.0].clone(),
+            (Some(i), TileSide::Right) => self.tile_packs[i].right[nature.0].clone(),
+            (None, TileSide::Left) => game_assets.texture(TILES_LEFT[nature.0]),
+            (None, TileSide::Right) => game_assets.texture(TILES_RIGHT[nature.0]),
+        }
+    }
+}
+
+/// HUD text whose colour follows the theme.
+#[derive(Component)]
+pub struct ThemedText;
+
+/// Outline drawn behind a tile, only visible in high contrast.
+#[derive(Component)]
+pub struct TileOutline;
+
+/// Border drawn behind a card, only visible on the hovered card in high contrast.
+#[derive(Component)]
+pub struct CardBorder;
+
+// Not a system!
+pub fn spawn_tile_outline(parent: &mut ChildBuilder, tile_size: f32, theme: &Theme) {
+    parent
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., -1.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(tile_size + 16., tile_size + 16.)),
+                color: Color::WHITE,
+                ..Default::default()
+            },
+            visibility: Visibility {
+                is_visible: theme.is_high_contrast(),
+            },
+            ..Default::default()
+        })
+        .insert(TileOutline);
+}
+
+// Not a system! The card art only covers the middle ~59% of the card texture width.
+pub fn spawn_card_border(parent: &mut ChildBuilder, card_size: f32) {
+    parent
+        .spawn_bundle(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(0., 0., -1.),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(card_size * 0.59 + 20., card_size + 20.)),
+                color: Color::YELLOW,
+                ..Default::default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(CardBorder);
+}
+
+/// The look of the board: contrast, tile packs, card backs and colour blind palettes.
+pub(crate) struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClearColor(Theme::default().background_color()))
+            .init_resource::<Theme>()
+            .init_resource::<ColorblindPreview>()
+            .add_startup_system(load_tile_packs.label("load_tile_packs"))
+            .add_system(toggle_high_contrast.after("leaderboard_input"))
+            .add_system(cycle_tile_pack.after("leaderboard_input"))
+            .add_system(colorblind_preview.after("leaderboard_input"))
+            .add_system(apply_theme);
+    }
+}
+
+pub fn toggle_high_contrast(
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    mut theme: ResMut<Theme>,
+    mut ev_toast: EventWriter<ShowToast>,
+) {
+    if input_map.just_pressed(InputAction::ToggleHighContrast, &keyboard_input) {
+        theme.contrast = match theme.contrast {
+            ContrastMode::Normal => ContrastMode::High,
+            ContrastMode::High => ContrastMode::Normal,
+        };
+        ev_toast.send(ShowToast(
+            if theme.is_high_contrast() {
+                "High contrast on"
+            } else {
+                "High contrast off"
+            }
+            .to_string(),
+        ));
+    }
+}
+
+// Tiles already dealt keep their art; the new pack is used from the next match.
+pub fn cycle_tile_pack(
+    keyboard_input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
+    profile: Res<PlayerProfile>,
+    mut theme: ResMut<Theme>,
+    mut ev_toast: EventWriter<ShowToast>,
+) {
+    if input_map.just_pressed(InputAction::CycleTilePack, &keyboard_input) {
+        theme.step_tile_pack(true, &profile);
+        ev_toast.send(ShowToast(format!(
+            "Tile pack: {} ({}/{}), from the next match",
+            theme.tile_pack_name(),
+            theme.tile_pack.map_or(1, |i| i + 2),
+            theme.tile_packs.len() + 1
+        )));
+    }
+}
+
+pub(crate) fn apply_theme(
+    theme: Res<Theme>,
+    match_state: Res<MatchState>,
+    mut clear_color: ResMut<ClearColor>,
+    mut q_outlines: Query<&mut Visibility, With<TileOutline>>,
+    mut q_text: Query<&mut Text, With<ThemedText>>,
+    mut event_update_cards_style: EventWriter<UpdateCardsStyle>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+
+    clear_color.0 = theme.background_color();
+    for mut visibility in q_outlines.iter_mut() {
+        visibility.is_visible = theme.is_high_contrast();
+    }
+    for mut text in q_text.iter_mut() {
+        text.sections[0].style.color = theme.text_color();
+    }
+    if let MatchState::Playing(_) = match_state.as_ref() {
+        event_update_cards_style.send(UpdateCardsStyle);
+    }
+}
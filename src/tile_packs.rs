@@ -0,0 +1,119 @@
+use std::{fs, path::Path};
+
+use bevy::{
+    prelude::*,
+    render::texture::{Image, ImageType},
+};
+use serde::Deserialize;
+
+use crate::{core::nature_count, cosmetics::Unlock, profile::PlayerProfile, theme::Theme};
+
+const TILE_PACKS_DIR: &str = "mods/tilesets";
+const MANIFEST_FILE: &str = "tileset.ron";
+
+// `mods/tilesets/<pack>/tileset.ron`, with the PNGs next to it:
+//
+// (
+//     name: "Pastel",
+//     natures: [
+//         (left: "a_l.png", right: "a_r.png"),
+//         ...
+//     ],
+//     // Optional, the pack can only be picked once the player has won 10 matches.
+//     unlock: Some(Wins(10)),
+// )
+#[derive(Deserialize)]
+struct TilePackManifest {
+    name: String,
+    natures: Vec<NatureArt>,
+    #[serde(default)]
+    unlock: Option<Unlock>,
+}
+
+#[derive(Deserialize)]
+struct NatureArt {
+    left: String,
+    right: String,
+}
+
+/// User-provided tile art, one left and one right texture per nature.
+pub struct TilePack {
+    pub name: String,
+    pub left: Vec<Handle<Image>>,
+    pub right: Vec<Handle<Image>>,
+    /// What the player needs to pick the pack, `None` when anyone can.
+    pub unlock: Option<Unlock>,
+}
+
+impl TilePack {
+    pub fn is_unlocked(&self, profile: &PlayerProfile) -> bool {
+        self.unlock.iter().all(|unlock| unlock.is_met(profile))
+    }
+
+    pub fn textures(&self) -> impl Iterator<Item = &Handle<Image>> {
+        self.left.iter().chain(self.right.iter())
+    }
+}
+
+// Packs are read up front, so a broken one is skipped with a warning instead of showing up
+// half-drawn in a match.
+pub fn load_tile_packs(mut theme: ResMut<Theme>, mut images: ResMut<Assets<Image>>) {
+    let entries = match fs::read_dir(TILE_PACKS_DIR) {
+        Ok(entries) => entries,
+        // No mods installed.
+        Err(_) => return,
+    };
+    let mut dirs = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort();
+
+    for dir in dirs {
+        match load_tile_pack(&dir, &mut images) {
+            Ok(pack) => {
+                info!("Loaded tile pack \"{}\" from {}", pack.name, dir.display());
+                theme.tile_packs.push(pack);
+            }
+            Err(err) => warn!("Skipping tile pack {}: {}", dir.display(), err),
+        }
+    }
+}
+
+// Not a system!
+fn load_tile_pack(dir: &Path, images: &mut Assets<Image>) -> Result<TilePack, String> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let manifest = fs::read_to_string(&manifest_path)
+        .map_err(|err| format!("could not read {}: {}", manifest_path.display(), err))?;
+    let manifest = ron::from_str::<TilePackManifest>(&manifest)
+        .map_err(|err| format!("invalid {}: {}", MANIFEST_FILE, err))?;
+    if manifest.natures.len() != nature_count() {
+        return Err(format!(
+            "expected art for {} natures, found {}",
+            nature_count(),
+            manifest.natures.len()
+        ));
+    }
+
+    let mut load = |file: &str| -> Result<Handle<Image>, String> {
+        let path = dir.join(file);
+        let bytes =
+            fs::read(&path).map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+        let image = Image::from_buffer(&bytes, ImageType::Extension("png"))
+            .map_err(|err| format!("could not decode {}: {}", path.display(), err))?;
+        Ok(images.add(image))
+    };
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    for art in manifest.natures.iter() {
+        left.push(load(&art.left)?);
+        right.push(load(&art.right)?);
+    }
+    Ok(TilePack {
+        name: manifest.name,
+        left,
+        right,
+        unlock: manifest.unlock,
+    })
+}
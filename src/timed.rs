@@ -0,0 +1,207 @@
+//! Timed mode: matches are played against a countdown that carries over from one match to the
+//! next. Winning buys time, taking cards back costs some, and the run is over when it runs out.
+//! It runs on top of the regular match systems, which don't know about it.
+
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+
+use crate::{
+    match_plugin::{
+        Card, MatchClock, MatchEnding, MatchResult, MatchState, RestartRequest, StartMatchEvent,
+        UndoRequest, Used, VictoryScreen,
+    },
+    theme::Theme,
+    GameMode, GameState,
+};
+
+const START_TIME: f32 = 120.;
+// Taken for each card undone, or taken back by a restart: those were wasted.
+const WASTED_CARD_PENALTY: f32 = 5.;
+const WIN_TIME_BONUS: f32 = 15.;
+const STAR_TIME_BONUS: f32 = 5.;
+// Below this the countdown turns red.
+const LOW_TIME: f32 = 10.;
+const LOW_TIME_COLOR: Color = Color::rgb(0.85, 0.2, 0.15);
+const COUNTDOWN_Y_POS: f32 = 500.;
+
+/// Time left in the current timed run.
+pub(crate) struct Countdown {
+    pub(crate) remaining: f32,
+}
+
+impl Default for Countdown {
+    fn default() -> Self {
+        Self {
+            remaining: START_TIME,
+        }
+    }
+}
+
+impl Countdown {
+    pub(crate) fn expired(&self) -> bool {
+        self.remaining <= 0.
+    }
+}
+
+// Not a system! The time a win adds to the countdown.
+pub(crate) fn win_bonus(result: &MatchResult) -> f32 {
+    WIN_TIME_BONUS + STAR_TIME_BONUS * result.stars() as f32
+}
+
+#[derive(Component)]
+struct CountdownText;
+
+pub(crate) struct TimedPlugin;
+
+impl Plugin for TimedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Countdown>()
+            .add_startup_system(setup_countdown_text)
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(timed_match)
+                    .with_system(refill_expired_countdown.before("start_match"))
+                    .with_system(
+                        tick_countdown
+                            .after("tick_match_clock")
+                            .after("handle_input"),
+                    )
+                    .with_system(penalize_wasted_cards.after("handle_input"))
+                    .with_system(add_win_bonus.after("handle_input")),
+            )
+            .add_system(update_countdown_text);
+    }
+}
+
+// The timed systems only run in game, and only in timed mode.
+fn timed_match(mode: Res<GameMode>, state: Res<State<GameState>>) -> ShouldRun {
+    if *mode == GameMode::Timed && *state.current() == GameState::InGame {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+// A run that ran out of time starts over with the next match.
+fn refill_expired_countdown(
+    mut ev_start_match: EventReader<StartMatchEvent>,
+    mut countdown: ResMut<Countdown>,
+) {
+    if ev_start_match.iter().count() > 0 && countdown.expired() {
+        *countdown = Countdown::default();
+    }
+}
+
+// Counts down with the match clock, so the countdown stops in menus too. Running out of time
+// ends the match as lost.
+fn tick_countdown(
+    clock: Res<MatchClock>,
+    match_state: Res<MatchState>,
+    q_ending: Query<(), With<MatchEnding>>,
+    q_used: Query<(), (With<Card>, With<Used>)>,
+    mut countdown: ResMut<Countdown>,
+    mut commands: Commands,
+) {
+    if clock.delta == 0. || countdown.expired() {
+        return;
+    }
+    countdown.remaining = (countdown.remaining - clock.delta).max(0.);
+    if !countdown.expired() || !q_ending.is_empty() {
+        return;
+    }
+    if let MatchState::Playing(match_state) = match_state.as_ref() {
+        info!("Out of time");
+        commands.spawn().insert(MatchEnding {
+            timer: Timer::from_seconds(1., false),
+            result: MatchResult {
+                won: false,
+                cards_used: q_used.iter().count(),
+                moves: match_state.moves,
+                par: match_state.par,
+            },
+        });
+    }
+}
+
+fn penalize_wasted_cards(
+    mut ev_restart: EventReader<RestartRequest>,
+    mut ev_undo: EventReader<UndoRequest>,
+    q_used: Query<(), (With<Card>, With<Used>)>,
+    mut countdown: ResMut<Countdown>,
+) {
+    let used = q_used.iter().count();
+    let restarted = ev_restart.iter().count() > 0;
+    let wasted = if restarted {
+        used
+    } else {
+        ev_undo.iter().count().min(used)
+    };
+    if wasted > 0 {
+        countdown.remaining = (countdown.remaining - WASTED_CARD_PENALTY * wasted as f32).max(0.);
+    }
+}
+
+fn add_win_bonus(
+    q_won: Query<&MatchEnding, (Added<MatchEnding>, With<VictoryScreen>)>,
+    mut countdown: ResMut<Countdown>,
+) {
+    for MatchEnding { result, .. } in q_won.iter() {
+        countdown.remaining += win_bonus(result);
+    }
+}
+
+fn format_countdown(seconds: f32) -> String {
+    let seconds = seconds.ceil() as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn setup_countdown_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 44.,
+                    color: Color::WHITE,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(0., COUNTDOWN_Y_POS, 50.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(CountdownText);
+}
+
+// Shown in game in timed mode. Not a themed text, since it turns red when time is short.
+fn update_countdown_text(
+    mode: Res<GameMode>,
+    countdown: Res<Countdown>,
+    theme: Res<Theme>,
+    state: Res<State<GameState>>,
+    mut q: Query<(&mut Text, &mut Visibility), With<CountdownText>>,
+) {
+    if !(mode.is_changed() || countdown.is_changed() || theme.is_changed() || state.is_changed()) {
+        return;
+    }
+    let visible = *mode == GameMode::Timed
+        && matches!(state.current(), GameState::InGame | GameState::Paused);
+    let value = format_countdown(countdown.remaining);
+    let color = if countdown.remaining < LOW_TIME {
+        LOW_TIME_COLOR
+    } else {
+        theme.text_color()
+    };
+    for (mut text, mut visibility) in q.iter_mut() {
+        visibility.is_visible = visible;
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+        text.sections[0].style.color = color;
+    }
+}
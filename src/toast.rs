@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+
+use jam_motion::{
+    friction::{FrictionDescription, FrictionSimulation},
+    spring::{SpringDescription, SpringSimulation},
+    Simulation,
+};
+
+const TOAST_Y_HIDDEN: f32 = 720.;
+const TOAST_Y_SHOWN: f32 = 580.;
+const TOAST_WIDTH: f32 = 640.;
+const TOAST_HEIGHT: f32 = 60.;
+const TOAST_LIFETIME: f32 = 2.;
+const TOAST_FADE_DRAG: f32 = 0.02;
+
+/// Send this to pop a short message at the top of the screen.
+pub struct ShowToast(pub String);
+
+#[derive(Component)]
+pub struct Toast {
+    slide: SpringSimulation,
+    shown_at: f32,
+    fade: Option<(FrictionSimulation, f32)>,
+}
+
+impl Toast {
+    fn start_fade(&mut self, now: f32) {
+        if self.fade.is_none() {
+            // Velocity chosen so that the opacity comes to rest exactly at 0.
+            let fade = FrictionSimulation::new(
+                FrictionDescription::from(TOAST_FADE_DRAG),
+                1.,
+                TOAST_FADE_DRAG.ln(),
+                0.01,
+            );
+            self.fade = Some((fade, now));
+        }
+    }
+}
+
+pub(crate) struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShowToast>()
+            .add_system(show_toasts)
+            .add_system(animate_toasts);
+    }
+}
+
+pub fn show_toasts(
+    mut ev: EventReader<ShowToast>,
+    mut q: Query<&mut Toast>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    let now = time.seconds_since_startup() as f32;
+    for ShowToast(message) in ev.iter() {
+        // Only one toast on screen at a time: the old ones make room by fading out.
+        for mut toast in q.iter_mut() {
+            toast.start_fade(now);
+        }
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., TOAST_Y_HIDDEN, 95.),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(TOAST_WIDTH, TOAST_HEIGHT)),
+                    color: Color::rgba(0.1, 0.1, 0.1, 0.9),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(Toast {
+                slide: SpringSimulation::new(
+                    SpringDescription::from_damping_ratio(1., 180., 0.7),
+                    TOAST_Y_HIDDEN,
+                    TOAST_Y_SHOWN,
+                    0.,
+                    1.,
+                ),
+                shown_at: now,
+                fade: None,
+            })
+            .with_children(|parent| {
+                parent.spawn_bundle(Text2dBundle {
+                    text: Text::with_section(
+                        message.clone(),
+                        TextStyle {
+                            font: asset_server.load("ReadexPro-Regular.ttf"),
+                            font_size: 26.,
+                            color: Color::WHITE,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    transform: Transform {
+                        translation: Vec3::new(0., 0., 1.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            });
+    }
+}
+
+pub fn animate_toasts(
+    mut q: Query<(Entity, &mut Toast, &mut Transform, &mut Sprite, &Children)>,
+    mut q_text: Query<&mut Text>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    let now = time.seconds_since_startup() as f32;
+    for (entity, mut toast, mut transform, mut sprite, children) in q.iter_mut() {
+        let t = now - toast.shown_at;
+        transform.translation.y = toast.slide.x_or_end_x(t).x;
+
+        if t > TOAST_LIFETIME {
+            toast.start_fade(now);
+        }
+
+        if let Some((fade, fade_started_at)) = &toast.fade {
+            let fade_t = now - fade_started_at;
+            if fade.is_done(fade_t) {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+            let alpha = fade.x(fade_t).clamp(0., 1.);
+            sprite.color.set_a(0.9 * alpha);
+            for child in children.iter() {
+                if let Ok(mut text) = q_text.get_mut(*child) {
+                    text.sections[0].style.color.set_a(alpha);
+                }
+            }
+        }
+    }
+}
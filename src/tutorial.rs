@@ -0,0 +1,340 @@
+//! The tutorial: a fixed first match, played one card at a time. A prompt explains each step,
+//! and arrows point at the card to play next and at the tiles it moves.
+
+use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+
+use crate::{
+    animation::AnimationClock,
+    core::{
+        affected_tiles, apply_inverse_action, Action, CycleDirection, Puzzle, TileNature, TileSide,
+    },
+    layout::Layout,
+    match_plugin::{
+        tile_columns, CardPlayed, MatchState, RestartRequest, Slot, StartMatchEvent, Tile,
+        UndoRequest,
+    },
+    theme::{Theme, ThemedText},
+    GameMode, MotionSettings,
+};
+
+const TUTORIAL_TILES: usize = 4;
+const TUTORIAL_HAND: [Action; 4] = [
+    Action::Cycle {
+        times: 1,
+        direction: CycleDirection::Up,
+        side: TileSide::Left,
+    },
+    Action::SwapTwoAdjacent {
+        top: 1,
+        side: TileSide::Left,
+    },
+    Action::Reverse {
+        side: TileSide::Right,
+    },
+    Action::SwapFirstAndLast {
+        side: TileSide::Right,
+    },
+];
+
+// A card of `TUTORIAL_HAND` to play, and what to say about it.
+struct ScriptStep {
+    card: usize,
+    prompt: &'static str,
+}
+
+// Playing these in order solves the tutorial board. The card left over is never played.
+const SCRIPT: [ScriptStep; 3] = [
+    ScriptStep {
+        card: 1,
+        prompt:
+            "Make both columns the same to win.\nThis card swaps the two middle tiles on the left.",
+    },
+    ScriptStep {
+        card: 3,
+        prompt:
+            "Each card can be played once.\nThis one swaps the top and bottom tiles on the right.",
+    },
+    ScriptStep {
+        card: 0,
+        prompt: "Last one: move every tile on the left up by one.",
+    },
+];
+const DONE_PROMPT: &str = "That's all there is to it!";
+
+const PROMPT_Y_POS: f32 = 330.;
+const ARROW_COLOR: Color = Color::rgb(1., 0.6, 0.2);
+const ARROW_HEAD_SIZE: f32 = 30.;
+const ARROW_SHAFT_WIDTH: f32 = 10.;
+const ARROW_SHAFT_LENGTH: f32 = 50.;
+// Where the arrows' tips are: above the card's centre, and beside the tile's.
+const CARD_ARROW_Y: f32 = 175.;
+const TILE_ARROW_X_ABS: f32 = 95.;
+const ARROW_BOB_DISTANCE: f32 = 10.;
+const ARROW_BOB_SPEED: f32 = 5.;
+
+// Not a system! The tutorial board: solved columns, scrambled by playing the script backwards.
+pub(crate) fn tutorial_puzzle() -> Puzzle {
+    let mut left_col = (0..TUTORIAL_TILES).map(TileNature).collect::<Vec<_>>();
+    let mut right_col = left_col.clone();
+    for step in SCRIPT.iter().rev() {
+        apply_inverse_action(
+            &TUTORIAL_HAND[step.card],
+            &mut left_col,
+            &mut right_col,
+            Box::new(|x| *x),
+        );
+    }
+    Puzzle {
+        // Not dealt, so there is no seed to give.
+        seed: 0,
+        left_col,
+        right_col,
+        cards: TUTORIAL_HAND.to_vec(),
+        par: SCRIPT.len(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TutorialStep {
+    Play(usize),
+    Done,
+}
+
+/// Where the tutorial is at, and its cards in the order they were dealt.
+pub(crate) struct Tutorial {
+    step: TutorialStep,
+    cards: Vec<Entity>,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        Self {
+            step: TutorialStep::Play(0),
+            cards: Vec::new(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct TutorialUi;
+
+// Arrows bob back and forth along the direction they point in.
+#[derive(Component)]
+struct TutorialArrow {
+    tip: Vec3,
+    direction: Vec3,
+}
+
+pub(crate) struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tutorial>()
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(tutorial_mode)
+                    .with_system(advance_tutorial.after("start_match").after("handle_input"))
+                    .with_system(crate::despawn_all::<TutorialUi>),
+            )
+            .add_system_to_stage(
+                "view",
+                show_tutorial
+                    .label("show_tutorial")
+                    .with_run_criteria(tutorial_mode),
+            )
+            .add_system_to_stage(
+                "view",
+                bob_tutorial_arrows
+                    .after("show_tutorial")
+                    .with_run_criteria(tutorial_mode),
+            );
+    }
+}
+
+fn tutorial_mode(mode: Res<GameMode>) -> ShouldRun {
+    if *mode == GameMode::Tutorial {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+// Moves through the script as the cards are played, and back when they are taken back. Only the
+// card of the current step can be played.
+fn advance_tutorial(
+    mut ev_start_match: EventReader<StartMatchEvent>,
+    mut ev_card_played: EventReader<CardPlayed>,
+    mut ev_undo: EventReader<UndoRequest>,
+    mut ev_restart: EventReader<RestartRequest>,
+    mut tutorial: ResMut<Tutorial>,
+    mut match_state: ResMut<MatchState>,
+) {
+    // Only borrowed mutably to write `playable`, so the match state isn't changed every frame.
+    let hand_order = match match_state.as_ref() {
+        MatchState::Playing(match_state) => &match_state.hand_order,
+        _ => return,
+    };
+    if ev_start_match.iter().count() > 0 {
+        *tutorial = Tutorial {
+            step: TutorialStep::Play(0),
+            cards: hand_order.clone(),
+        };
+    }
+    for _ in ev_card_played.iter() {
+        tutorial.step = match tutorial.step {
+            TutorialStep::Play(i) if i + 1 < SCRIPT.len() => TutorialStep::Play(i + 1),
+            _ => TutorialStep::Done,
+        };
+    }
+    for _ in ev_undo.iter() {
+        if let TutorialStep::Play(i) = tutorial.step {
+            tutorial.step = TutorialStep::Play(i.saturating_sub(1));
+        }
+    }
+    if ev_restart.iter().count() > 0 {
+        tutorial.step = TutorialStep::Play(0);
+    }
+
+    if tutorial.is_changed() {
+        if let MatchState::Playing(match_state) = match_state.as_mut() {
+            match_state.playable = match tutorial.step {
+                TutorialStep::Play(i) => tutorial.cards.get(SCRIPT[i].card).copied(),
+                TutorialStep::Done => None,
+            };
+        }
+    }
+}
+
+// Not a system! An arrow with its tip at `tip`, pointing along `direction`.
+fn spawn_arrow(commands: &mut Commands, tip: Vec3, direction: Vec2) -> Entity {
+    let head_offset = ARROW_HEAD_SIZE * FRAC_1_SQRT_2;
+    commands
+        .spawn()
+        .insert(Transform {
+            translation: tip,
+            // Drawn pointing down, then turned.
+            rotation: Quat::from_rotation_z(direction.y.atan2(direction.x) + FRAC_PI_2),
+            ..Default::default()
+        })
+        .insert(GlobalTransform::default())
+        .insert(TutorialUi)
+        .insert(TutorialArrow {
+            tip,
+            direction: direction.extend(0.),
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(SpriteBundle {
+                transform: Transform {
+                    translation: Vec3::new(0., head_offset, 0.),
+                    rotation: Quat::from_rotation_z(FRAC_PI_4),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(ARROW_HEAD_SIZE, ARROW_HEAD_SIZE)),
+                    color: ARROW_COLOR,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            parent.spawn_bundle(SpriteBundle {
+                transform: Transform::from_xyz(0., head_offset + ARROW_SHAFT_LENGTH / 2., 0.),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(ARROW_SHAFT_WIDTH, ARROW_SHAFT_LENGTH)),
+                    color: ARROW_COLOR,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        })
+        .id()
+}
+
+// Rebuilt on every step: the prompt, an arrow over the card to play and one beside each tile it
+// moves.
+fn show_tutorial(
+    tutorial: Res<Tutorial>,
+    layout: Res<Layout>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+    q_ui: Query<Entity, With<TutorialUi>>,
+    q_tiles: Query<(Entity, &TileSide, &Slot, &TileNature), With<Tile>>,
+    mut commands: Commands,
+) {
+    if !tutorial.is_changed() && !layout.is_changed() {
+        return;
+    }
+    for e in q_ui.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let prompt = match tutorial.step {
+        TutorialStep::Play(i) => SCRIPT[i].prompt,
+        TutorialStep::Done => DONE_PROMPT,
+    };
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                prompt,
+                TextStyle {
+                    font: asset_server.load("ReadexPro-Regular.ttf"),
+                    font_size: 26.,
+                    color: theme.text_color(),
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform::from_xyz(0., PROMPT_Y_POS, 50.),
+            ..Default::default()
+        })
+        .insert(TutorialUi)
+        .insert(ThemedText);
+
+    let step = match tutorial.step {
+        TutorialStep::Play(i) => &SCRIPT[i],
+        TutorialStep::Done => return,
+    };
+    if let Some(card) = tutorial.cards.get(step.card) {
+        let arrow = spawn_arrow(&mut commands, Vec3::new(0., CARD_ARROW_Y, 1.), -Vec2::Y);
+        commands.entity(*card).push_children(&[arrow]);
+    }
+
+    let (left_col, right_col) = tile_columns(
+        q_tiles
+            .iter()
+            .map(|(id, side, slot, nature)| (id, *side, *slot, *nature)),
+    );
+    let action = TUTORIAL_HAND[step.card];
+    for (side, row) in affected_tiles(&action, &left_col, &right_col, Box::new(|x| x.nature)) {
+        let tile = match side {
+            TileSide::Left => left_col[row].id,
+            TileSide::Right => right_col[row].id,
+        };
+        // From the outer side of the column, pointing in at the tile.
+        let outwards = layout.side_x(side, 1.);
+        let arrow = spawn_arrow(
+            &mut commands,
+            Vec3::new(outwards * TILE_ARROW_X_ABS, 0., 1.),
+            Vec2::new(-outwards, 0.),
+        );
+        commands.entity(tile).push_children(&[arrow]);
+    }
+}
+
+fn bob_tutorial_arrows(
+    clock: Res<AnimationClock>,
+    motion_settings: Res<MotionSettings>,
+    mut q: Query<(&TutorialArrow, &mut Transform)>,
+) {
+    let offset = if motion_settings.reduced_motion {
+        0.
+    } else {
+        -ARROW_BOB_DISTANCE * (clock.now() * ARROW_BOB_SPEED).sin().abs()
+    };
+    for (arrow, mut transform) in q.iter_mut() {
+        transform.translation = arrow.tip + arrow.direction * offset;
+    }
+}
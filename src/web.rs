@@ -0,0 +1,42 @@
+//! The browser build: the game draws into the page's canvas and keeps it the size of the page,
+//! so it fills the frame it is embedded in, e.g. on itch.io.
+
+use bevy::prelude::*;
+
+/// The canvas the game draws into. The page must have one, e.g. `<canvas id="bevy">`.
+pub const CANVAS_SELECTOR: &str = "#bevy";
+
+pub struct WebPlugin;
+
+impl Plugin for WebPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(fit_canvas_to_page.before("apply_layout"));
+    }
+}
+
+// The canvas keeps the size it was created with, so it follows the browser window instead. The
+// view then fits the window like a resized native one.
+fn fit_canvas_to_page(mut windows: ResMut<Windows>) {
+    let page = match web_sys::window() {
+        Some(page) => page,
+        None => return,
+    };
+    let (width, height) = match (page.inner_width(), page.inner_height()) {
+        (Ok(width), Ok(height)) => (
+            width.as_f64().unwrap_or(0.) as f32,
+            height.as_f64().unwrap_or(0.) as f32,
+        ),
+        _ => return,
+    };
+    // Only borrowed mutably on change, so the windows aren't changed every frame.
+    let fits = match windows.get_primary() {
+        Some(window) => window.width() == width && window.height() == height,
+        None => return,
+    };
+    if fits || width <= 0. || height <= 0. {
+        return;
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_resolution(width, height);
+    }
+}
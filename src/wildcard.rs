@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+
+use crate::{
+    controls::{ActionInput, InputAction},
+    core::{Action, TileSide},
+    layout::Layout,
+    match_plugin::{CardClicked, InputMode, MatchState, Pointer, Slot, Tile, TilePick, TILE_SIZE},
+    OpenMenus,
+};
+
+const MARKER_SIZE: f32 = 180.;
+const CURSOR_MARKER_COLOR: Color = Color::rgba(1., 0.85, 0.5, 0.8);
+const PICKED_MARKER_COLOR: Color = Color::rgba(0.6, 0.8, 1., 0.9);
+
+/// Drawn behind the tile under the tile cursor, and behind the first tile picked.
+#[derive(Component)]
+pub struct TilePickMarker;
+
+// Moves the tile cursor and picks the two tiles a wildcard swaps. Once both are picked the
+// wildcard is clicked again, and `handle_input` plays it as their swap.
+pub(crate) fn pick_tiles(
+    menus: OpenMenus,
+    input: ActionInput,
+    layout: Res<Layout>,
+    mut pointer: Pointer,
+    mut match_state: ResMut<MatchState>,
+    mut ev_card_clicked: EventWriter<CardClicked>,
+    q_tiles: Query<(&TileSide, &Slot, &GlobalTransform), With<Tile>>,
+) {
+    let cursor_moved = pointer.moved();
+    if menus.any_open() {
+        return;
+    }
+    // Only borrowed mutably once the pick changes, so the match state isn't changed every frame.
+    let picked = match match_state.as_ref() {
+        MatchState::Playing(match_state) => match match_state.input_mode {
+            InputMode::PickTiles(pick) => pick,
+            _ => return,
+        },
+        _ => return,
+    };
+    let mut set_input_mode = |input_mode| {
+        if let MatchState::Playing(match_state) = match_state.as_mut() {
+            match_state.input_mode = input_mode;
+        }
+    };
+
+    if input.keyboard.just_pressed(KeyCode::Escape) || input.just_pressed(InputAction::Undo) {
+        set_input_mode(InputMode::Hand);
+        return;
+    }
+    let mut pick = picked;
+
+    let rows = q_tiles
+        .iter()
+        .filter(|(side, _, _)| **side == pick.side)
+        .count();
+    // The columns are swapped on screen when mirrored, like the cards.
+    let (go_left, go_right) = (
+        input.just_pressed(InputAction::PreviousCard),
+        input.just_pressed(InputAction::NextCard),
+    );
+    let (left_side, right_side) = if layout.mirrored {
+        (TileSide::Right, TileSide::Left)
+    } else {
+        (TileSide::Left, TileSide::Right)
+    };
+    if pick.first.is_none() {
+        if go_left {
+            pick.side = left_side;
+        } else if go_right {
+            pick.side = right_side;
+        }
+    }
+    if input.keyboard.just_pressed(KeyCode::Up) {
+        pick.row = (pick.row + 1).min(rows.saturating_sub(1));
+    } else if input.keyboard.just_pressed(KeyCode::Down) {
+        pick.row = pick.row.saturating_sub(1);
+    }
+
+    let mut confirm = input.just_pressed(InputAction::PlayCard);
+    let tapped = pointer.tapped();
+    // Tiles have no hover to show for a finger, so a tap picks straight away, like a click.
+    let clicked = pointer.clicked() || tapped.is_some();
+    if cursor_moved || clicked {
+        let under_mouse = pointer.world_position(tapped).and_then(|point| {
+            q_tiles
+                .iter()
+                .find(|(_, _, transform)| {
+                    let half_size = TILE_SIZE * transform.scale.truncate() / 2.;
+                    let offset = point - transform.translation.truncate();
+                    offset.x.abs() <= half_size.x && offset.y.abs() <= half_size.y
+                })
+                .map(|(side, slot, _)| (*side, slot.0))
+        });
+        if let Some((side, row)) = under_mouse {
+            if pick.first.is_none() || side == pick.side {
+                pick.side = side;
+                pick.row = row;
+                confirm |= clicked;
+            }
+        }
+    }
+
+    if !confirm && pick == picked {
+        return;
+    }
+    set_input_mode(match (confirm, pick.first) {
+        (true, None) => InputMode::PickTiles(TilePick {
+            first: Some(pick.row),
+            ..pick
+        }),
+        // Picking the first tile again puts it back.
+        (true, Some(first)) if first == pick.row => InputMode::PickTiles(TilePick {
+            first: None,
+            ..pick
+        }),
+        (true, Some(first)) => {
+            ev_card_clicked.send(CardClicked);
+            InputMode::Picked(Action::SwapRows {
+                a: first,
+                b: pick.row,
+                side: pick.side,
+            })
+        }
+        (false, _) => InputMode::PickTiles(pick),
+    });
+}
+
+pub(crate) fn show_tile_pick(
+    match_state: Res<MatchState>,
+    q_tiles: Query<(Entity, &TileSide, &Slot), With<Tile>>,
+    q_markers: Query<Entity, With<TilePickMarker>>,
+    mut commands: Commands,
+) {
+    if !match_state.is_changed() {
+        return;
+    }
+    for marker in q_markers.iter() {
+        commands.entity(marker).despawn_recursive();
+    }
+    let pick = match match_state.as_ref() {
+        MatchState::Playing(match_state) => match match_state.input_mode {
+            InputMode::PickTiles(pick) => pick,
+            _ => return,
+        },
+        _ => return,
+    };
+
+    // The picked tile's marker goes over the cursor's when they are on the same tile.
+    let markers = [
+        (Some(pick.row), CURSOR_MARKER_COLOR, -0.05),
+        (pick.first, PICKED_MARKER_COLOR, -0.04),
+    ];
+    for (row, color, z) in markers {
+        let tile = q_tiles
+            .iter()
+            .find(|(_, side, slot)| **side == pick.side && Some(slot.0) == row);
+        if let Some((tile, _, _)) = tile {
+            let marker = commands
+                .spawn_bundle(SpriteBundle {
+                    transform: Transform::from_xyz(0., 0., z),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::new(MARKER_SIZE, MARKER_SIZE)),
+                        color,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(TilePickMarker)
+                .id();
+            commands.entity(tile).push_children(&[marker]);
+        }
+    }
+}